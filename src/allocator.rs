@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use bytes::{Bytes, BytesMut};
+
+/// Hands out the memory an [`HttpFile`](crate::HttpFile) keeps data in, see
+/// [`HttpFileBuilder::with_allocator`](crate::HttpFileBuilder::with_allocator).
+///
+/// Every buffer the file allocates to hold data comes from it: blocks of the
+/// [block cache](crate::HttpFileBuilder::with_block_cache),
+/// [coalesced](crate::HttpFileBuilder::with_request_coalescing) reads, and
+/// the bytes [`read_exact_at`](crate::HttpFile::read_exact_at) reads and
+/// keeps for the reads that follow. Chunks received from the network are
+/// kept as they are, without a copy.
+pub trait BufferAllocator: Send + Sync {
+    /// An empty buffer with room for at least `capacity` bytes.
+    fn allocate(&self, capacity: usize) -> BytesMut;
+
+    /// Take back `buf`, handed out by [`allocate`](Self::allocate), once
+    /// nothing refers to its bytes anymore, e.g. to hand it out again. The
+    /// default drops it.
+    fn release(&self, buf: BytesMut) {
+        drop(buf);
+    }
+}
+
+/// A buffer from a [`BufferAllocator`], or the global allocator without one,
+/// given back to the allocator once dropped.
+pub(crate) struct Allocated {
+    buf: BytesMut,
+    allocator: Option<Arc<dyn BufferAllocator>>,
+}
+
+impl Allocated {
+    /// An empty buffer with room for `capacity` bytes, asking `allocator`
+    /// only when there is room to allocate.
+    pub(crate) fn new(allocator: Option<Arc<dyn BufferAllocator>>, capacity: usize) -> Self {
+        match allocator {
+            Some(allocator) if capacity > 0 => Self {
+                buf: allocator.allocate(capacity),
+                allocator: Some(allocator),
+            },
+            _ => Self {
+                buf: BytesMut::with_capacity(capacity),
+                allocator: None,
+            },
+        }
+    }
+
+    /// The bytes of the buffer, which goes back to the allocator once the
+    /// last clone or slice of them is dropped.
+    pub(crate) fn freeze(mut self) -> Bytes {
+        if self.allocator.is_none() {
+            return std::mem::take(&mut self.buf).freeze();
+        }
+        Bytes::from_owner(self)
+    }
+}
+
+impl std::ops::Deref for Allocated {
+    type Target = BytesMut;
+
+    fn deref(&self) -> &BytesMut {
+        &self.buf
+    }
+}
+
+impl std::ops::DerefMut for Allocated {
+    fn deref_mut(&mut self) -> &mut BytesMut {
+        &mut self.buf
+    }
+}
+
+impl AsRef<[u8]> for Allocated {
+    fn as_ref(&self) -> &[u8] {
+        &self.buf
+    }
+}
+
+impl Drop for Allocated {
+    fn drop(&mut self) {
+        if let Some(allocator) = self.allocator.take() {
+            allocator.release(std::mem::take(&mut self.buf));
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use std::{
+    io::{Error, ErrorKind, SeekFrom},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+use crate::{HttpFile, LocalFile};
+
+/// A file opened from either an `http(s)://` or a `file://` URL, read and
+/// seeked the same way.
+#[derive(Debug)]
+pub enum AnyFile {
+    /// served over HTTP, boxed as it is much larger than a local file
+    Http(Box<HttpFile>),
+    /// on the local filesystem
+    Local(LocalFile),
+}
+
+impl AnyFile {
+    /// Open `url` with `client` if it is an `http(s)://` URL, or from the
+    /// filesystem if it is a `file://` one.
+    pub async fn open(client: reqwest::Client, url: &str) -> std::io::Result<Self> {
+        let parsed =
+            reqwest::Url::parse(url).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+        if parsed.scheme() != "file" {
+            return HttpFile::new(client, url)
+                .await
+                .map(|file| Self::Http(Box::new(file)))
+                .map_err(|e| crate::HttpFileError::from(e).into());
+        }
+        let path = parsed
+            .to_file_path()
+            .map_err(|()| Error::new(ErrorKind::InvalidInput, "not a local file path"))?;
+        LocalFile::open(path).await.map(Self::Local)
+    }
+
+    /// length of the file in bytes, if known
+    pub fn content_length(&self) -> Option<u64> {
+        match self {
+            Self::Http(file) => file.content_length(),
+            Self::Local(file) => Some(file.content_length()),
+        }
+    }
+
+    /// mime type of the file, if known
+    pub fn mime(&self) -> Option<&str> {
+        match self {
+            Self::Http(file) => file.mime(),
+            Self::Local(file) => file.mime(),
+        }
+    }
+}
+
+impl AsyncRead for AnyFile {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Self::Http(file) => Pin::new(&mut **file).poll_read(cx, buf),
+            Self::Local(file) => Pin::new(file).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncSeek for AnyFile {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        match self.get_mut() {
+            Self::Http(file) => Pin::new(&mut **file).start_seek(position),
+            Self::Local(file) => Pin::new(file).start_seek(position),
+        }
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        match self.get_mut() {
+            Self::Http(file) => Pin::new(&mut **file).poll_complete(cx),
+            Self::Local(file) => Pin::new(file).poll_complete(cx),
+        }
+    }
+}
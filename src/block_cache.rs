@@ -0,0 +1,58 @@
+use std::collections::{HashMap, VecDeque};
+
+use bytes::Bytes;
+
+/// The most recently used aligned blocks of a file, see
+/// [`HttpFileBuilder::with_block_cache`](crate::HttpFileBuilder::with_block_cache).
+#[derive(Debug)]
+pub(crate) struct BlockCache {
+    block_size: u64,
+    capacity: usize,
+    blocks: HashMap<u64, Bytes>,
+    /// indices of `blocks`, least recently used first
+    order: VecDeque<u64>,
+}
+
+impl BlockCache {
+    pub(crate) fn new(block_size: u64, capacity: usize) -> Self {
+        Self {
+            block_size,
+            capacity,
+            blocks: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn block_size(&self) -> u64 {
+        self.block_size
+    }
+
+    /// Block `index`, if held, marked as just used.
+    pub(crate) fn get(&mut self, index: u64) -> Option<Bytes> {
+        let block = self.blocks.get(&index)?.clone();
+        self.touch(index);
+        Some(block)
+    }
+
+    /// Keep `block` as block `index`, dropping the least recently used one
+    /// when full.
+    pub(crate) fn insert(&mut self, index: u64, block: Bytes) {
+        if self.blocks.insert(index, block).is_some() {
+            self.touch(index);
+            return;
+        }
+        self.order.push_back(index);
+        if self.order.len() > self.capacity
+            && let Some(evicted) = self.order.pop_front()
+        {
+            self.blocks.remove(&evicted);
+        }
+    }
+
+    fn touch(&mut self, index: u64) {
+        if let Some(i) = self.order.iter().position(|&held| held == index) {
+            self.order.remove(i);
+        }
+        self.order.push_back(index);
+    }
+}
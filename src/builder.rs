@@ -0,0 +1,824 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use base64::Engine;
+use bytes::Bytes;
+use futures_util::future::BoxFuture;
+use md5::Digest;
+use reqwest::header::{HeaderName, HeaderValue};
+
+use crate::{
+    BufferAllocator, Clock, HttpFile, HttpFileError, MerkleTree, Metrics, RetryAction, RetryPolicy,
+    Transport, allocator::Allocated, retry::Backoff,
+};
+
+/// A user-supplied hook, shown opaquely in `Debug` output.
+pub(crate) struct Hook<T: ?Sized>(pub(crate) Arc<T>);
+
+impl<T: ?Sized> Clone for Hook<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: ?Sized> std::fmt::Debug for Hook<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("<hook>")
+    }
+}
+
+/// A function applied to every chunk of the file as it arrives.
+pub type ChunkTransform = Arc<dyn Fn(Bytes) -> Bytes + Send + Sync>;
+
+/// A function told the position reached and the length of the file, if
+/// known, as reads and seeks complete.
+pub type Progress = Arc<ProgressFn>;
+type ProgressFn = dyn Fn(u64, Option<u64>) + Send + Sync;
+
+/// A function handing out a fresh url for the file, such as a newly signed
+/// one once the last has expired.
+pub type UrlProvider = Arc<UrlProviderFn>;
+type UrlProviderFn = dyn Fn() -> BoxFuture<'static, reqwest::Url> + Send + Sync;
+
+/// Tunables carried by an [`HttpFile`], set through [`HttpFileBuilder`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Options {
+    pub(crate) max_retries: Option<u8>,
+    pub(crate) backoff: Backoff,
+    pub(crate) max_body_bytes: Option<u64>,
+    pub(crate) max_connections: Option<u64>,
+    /// most bytes discarded from the open response to serve a forward seek
+    pub(crate) max_inline_skip: Option<u64>,
+    pub(crate) full_download_fallback: bool,
+    pub(crate) partial_delivery: bool,
+    /// keep reading past the reported length, see `with_trust_content_length`
+    pub(crate) distrust_content_length: bool,
+    /// accept responses with another etag than the opened file, see `with_etag_check`
+    pub(crate) skip_etag_check: bool,
+    /// read encoded range responses as is, see `with_identity_encoding`
+    pub(crate) accept_any_encoding: bool,
+    /// check bodies against the digest advertised, see `with_checksum_verification`
+    pub(crate) verify_checksums: bool,
+    /// minimum bytes per second, and the size of ranges to fall back to
+    pub(crate) slow_stream_policy: Option<(u64, u64)>,
+    /// minimum average bytes per second, enforced after the grace period
+    pub(crate) min_throughput: Option<(u64, Duration)>,
+    /// overrides of the default retry on 5xx for the listed statuses
+    pub(crate) status_retry_policy: HashMap<reqwest::StatusCode, RetryAction>,
+    /// end sent instead of leaving ranges open, e.g. `bytes=0-` becomes `bytes=0-{end}`
+    pub(crate) open_range_end: Option<u64>,
+    /// tree the chunks of this size are verified against
+    pub(crate) chunk_merkle: Option<(Arc<MerkleTree>, u64)>,
+    pub(crate) coalesce_reads: bool,
+    /// bytes of the read buffer, see `with_buffer_size`
+    pub(crate) buffer_size: Option<usize>,
+    /// most bytes read ahead of the caller, see `HttpFile::set_prefetch`
+    pub(crate) prefetch: Option<u64>,
+    /// size and number of the blocks cached, see `with_block_cache`
+    pub(crate) block_cache: Option<(u64, usize)>,
+    /// fewest bytes requested by a bounded request, see `HttpFile::set_min_fetch`
+    pub(crate) min_fetch: u64,
+    /// longest wait for the next chunk before reconnecting
+    pub(crate) stall_timeout: Option<Duration>,
+    /// longest wait for the response to a range request before sending it again
+    pub(crate) request_timeout: Option<Duration>,
+    /// when all reads and seeks give up
+    pub(crate) deadline: Option<Instant>,
+    /// shared by a file and its forks, set up when opening with `coalesce_reads`
+    pub(crate) in_flight: Option<Arc<crate::coalesce::InFlight>>,
+    pub(crate) chunk_transform: Option<Hook<dyn Fn(Bytes) -> Bytes + Send + Sync>>,
+    pub(crate) metrics: Option<Hook<dyn Metrics>>,
+    pub(crate) clock: Option<Hook<dyn Clock>>,
+    pub(crate) retry_policy: Option<Hook<dyn RetryPolicy>>,
+    pub(crate) progress: Option<Hook<ProgressFn>>,
+    pub(crate) transport: Option<Hook<dyn Transport>>,
+    pub(crate) allocator: Option<Hook<dyn BufferAllocator>>,
+    pub(crate) url_provider: Option<Hook<UrlProviderFn>>,
+    /// statuses answered to an expired url, on which the url provider is asked
+    pub(crate) url_refresh_statuses: Option<Vec<reqwest::StatusCode>>,
+    /// every url serving the file, in failover order, when there are mirrors
+    pub(crate) mirrors: Vec<reqwest::Url>,
+    /// sent with the `HEAD` and every range request
+    pub(crate) headers: reqwest::header::HeaderMap,
+    /// have a caching proxy check its copy with the origin, see `with_proxy`
+    pub(crate) revalidate: bool,
+}
+
+impl Options {
+    pub(crate) fn metrics(&self) -> Option<&dyn Metrics> {
+        self.metrics.as_ref().map(|m| &*m.0)
+    }
+
+    pub(crate) fn max_retries(&self) -> u8 {
+        self.max_retries.unwrap_or(DEFAULT_MAX_RETRIES)
+    }
+
+    /// Whether a request refused with `status` asks the url provider for a
+    /// new url.
+    pub(crate) fn refreshes_url(&self, status: reqwest::StatusCode) -> bool {
+        match &self.url_refresh_statuses {
+            Some(statuses) => statuses.contains(&status),
+            None => status == reqwest::StatusCode::FORBIDDEN,
+        }
+    }
+
+    pub(crate) fn max_inline_skip(&self) -> u64 {
+        self.max_inline_skip.unwrap_or(DEFAULT_MAX_INLINE_SKIP)
+    }
+
+    pub(crate) fn retry_action(&self, status: reqwest::StatusCode) -> RetryAction {
+        self.status_retry_policy
+            .get(&status)
+            .copied()
+            .unwrap_or_else(|| crate::retry::default_action(status))
+    }
+
+    /// Merge `headers` into those sent with every request, dropping the ones
+    /// the file manages itself.
+    pub(crate) fn add_headers(&mut self, headers: reqwest::header::HeaderMap) {
+        self.headers.extend(headers);
+        for name in [reqwest::header::RANGE, reqwest::header::IF_RANGE] {
+            if self.headers.remove(&name).is_some() {
+                log::warn!("ignoring extra {} header", name);
+            }
+        }
+    }
+
+    /// An empty buffer with room for `capacity` bytes, from the allocator if
+    /// one is set and there is room to allocate.
+    pub(crate) fn buffer(&self, capacity: usize) -> Allocated {
+        let allocator = self.allocator.as_ref().map(|a| a.0.clone());
+        Allocated::new(allocator, capacity)
+    }
+
+    pub(crate) fn now(&self) -> Instant {
+        self.clock.as_ref().map_or_else(Instant::now, |c| c.0.now())
+    }
+}
+
+/// Failed requests are attempted this many more times by default.
+const DEFAULT_MAX_RETRIES: u8 = 3;
+
+/// Forward seeks up to this far are read through rather than reconnected.
+const DEFAULT_MAX_INLINE_SKIP: u64 = 16 * 1024;
+
+const SSE_CUSTOMER_ALGORITHM: HeaderName =
+    HeaderName::from_static("x-amz-server-side-encryption-customer-algorithm");
+const SSE_CUSTOMER_KEY: HeaderName =
+    HeaderName::from_static("x-amz-server-side-encryption-customer-key");
+const SSE_CUSTOMER_KEY_MD5: HeaderName =
+    HeaderName::from_static("x-amz-server-side-encryption-customer-key-md5");
+
+/// A builder to configure an [`HttpFile`] before opening it.
+///
+/// ```rust no_run
+/// # async fn run() -> Result<(), remote_file::HttpFileError> {
+/// let file = remote_file::HttpFile::builder()
+///     .with_max_body_bytes(16 * 1024 * 1024)
+///     .build("http://example.com/largefile")
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HttpFileBuilder {
+    client: Option<reqwest::Client>,
+    proxy: Option<reqwest::Proxy>,
+    /// length to open with instead of sending a `HEAD`
+    skip_head: Option<u64>,
+    /// pin reads to the version seen on open
+    if_range: bool,
+    /// fail to open unless the server serves byte ranges
+    require_range: bool,
+    /// urls to fail over to, after the one given to `build`
+    mirrors: Vec<String>,
+    /// redirects the default client follows per request
+    max_redirects: Option<usize>,
+    options: Options,
+}
+
+impl HttpFileBuilder {
+    /// Use `client` for all requests, a default `reqwest::Client` is used otherwise.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Retry a failed request up to `retries` times, 3 by default.
+    ///
+    /// Timeouts, `5xx` responses and, with
+    /// [partial delivery](Self::with_partial_delivery), dropped responses are
+    /// retried. The count starts over whenever data is received.
+    pub fn retries(mut self, retries: u8) -> Self {
+        self.options.max_retries = Some(retries);
+        self
+    }
+
+    /// Wait between retries, growing from `base` by `multiplier` up to `max`.
+    ///
+    /// The first retry waits `base`, each following one `multiplier` times
+    /// longer, never more than `max`. With `jitter`, each delay is picked at
+    /// random between half and all of it, so clients failing together don't
+    /// retry together. The default is 200ms doubling up to 5s with jitter; a
+    /// zero `base` retries right away.
+    pub fn with_backoff(
+        mut self,
+        base: Duration,
+        multiplier: f64,
+        max: Duration,
+        jitter: bool,
+    ) -> Self {
+        self.options.backoff = Backoff {
+            base,
+            multiplier,
+            max,
+            jitter,
+        };
+        self
+    }
+
+    /// Send `headers` with the `HEAD` and every range request.
+    ///
+    /// They are added to the headers set by other options such as
+    /// [`with_sse_customer_key`](Self::with_sse_customer_key), replacing any
+    /// of the same name. `Range` and `If-Range` are managed by the file and
+    /// are dropped if set here.
+    pub fn extra_headers(mut self, headers: reqwest::header::HeaderMap) -> Self {
+        self.options.add_headers(headers);
+        self
+    }
+
+    /// Open the file as `content_length` bytes long, without sending a `HEAD`.
+    ///
+    /// Useful when the length is known from elsewhere, e.g. a listing, or the
+    /// server doesn't answer `HEAD`. The etag, mime type and modification
+    /// date stay unknown. See also [`HttpFile::from_known`].
+    pub fn skip_head(mut self, content_length: u64) -> Self {
+        self.skip_head = Some(content_length);
+        self
+    }
+
+    /// Route requests through a caching `proxy`, revalidating with the origin.
+    ///
+    /// Requests are sent with `Cache-Control: no-cache`, and range requests
+    /// with `If-None-Match` and the etag the file was opened with, so the
+    /// proxy checks its copy with the origin before serving it. A `304 Not
+    /// Modified` confirms the copy is the version being read, and the range
+    /// is then asked for again without the validator, to be served from the
+    /// cache. A changed file comes back with its new etag and fails with
+    /// [`HttpFileError::FileChanged`]. Whether the proxy had the file is
+    /// reported by [`HttpFile::last_cache_status`].
+    ///
+    /// The proxy is set on the default client, so along with
+    /// [`client`](Self::client), `build` fails with
+    /// [`HttpFileError::ClientOption`]; configure the proxy on that client.
+    pub fn with_proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self.options.revalidate = true;
+        self
+    }
+
+    /// Follow at most `max` redirects per request, instead of the 10 of
+    /// reqwest's default policy.
+    ///
+    /// A request redirected more often fails with
+    /// [`HttpFileError::TooManyRedirects`](crate::HttpFileError::TooManyRedirects),
+    /// and one redirected back to a url it already went through fails right
+    /// away with [`HttpFileError::RedirectLoop`](crate::HttpFileError::RedirectLoop),
+    /// which lists the cycle. Like the proxy, the limit is set on the default
+    /// client, so along with [`client`](Self::client), `build` fails with
+    /// [`HttpFileError::ClientOption`]; set a redirect policy on that client.
+    pub fn with_max_redirects(mut self, max: usize) -> Self {
+        self.max_redirects = Some(max);
+        self
+    }
+
+    /// Cap the number of bytes read from a response without a known length.
+    ///
+    /// When the server does not report a content length, the end of file is
+    /// only known when the stream ends. With a cap set, reading past `limit`
+    /// fails with [`HttpFileError::BodyTooLarge`](crate::HttpFileError::BodyTooLarge)
+    /// instead of following a runaway stream forever.
+    pub fn with_max_body_bytes(mut self, limit: u64) -> Self {
+        self.options.max_body_bytes = Some(limit);
+        self
+    }
+
+    /// Limit the number of response streams opened for reads and seeks.
+    ///
+    /// Once `limit` streams have been opened, forward seeks are served by
+    /// discarding bytes from the open response, and anything that would need
+    /// a new one fails with
+    /// [`HttpFileError::TooManyConnections`](crate::HttpFileError::TooManyConnections).
+    /// See [`HttpFile::connections_opened`].
+    pub fn with_max_connections(mut self, limit: u64) -> Self {
+        self.options.max_connections = Some(limit);
+        self
+    }
+
+    /// Set how far a forward seek may read through the open response.
+    ///
+    /// Seeking a short distance forward is cheaper served by reading and
+    /// discarding bytes from the open response than by opening a new one,
+    /// while discarding megabytes wastes bandwidth. Seeks that would discard
+    /// more than `bytes` open a new response. Defaults to 16 KiB; `0`
+    /// disables reading through, except for data already buffered.
+    pub fn with_max_inline_skip(mut self, bytes: u64) -> Self {
+        self.options.max_inline_skip = Some(bytes);
+        self
+    }
+
+    /// Reconnect when the open response delivers nothing for `timeout`.
+    ///
+    /// A connection can stay open without delivering any more data, e.g. a
+    /// half-open socket or a stuck proxy, and reads would wait on it forever.
+    /// With a stall timeout, the response is dropped and the range requested
+    /// again from the current position, spending one of the
+    /// [`retries`](Self::retries). Once none are left the read fails with
+    /// [`HttpFileError::Stalled`], an `ErrorKind::TimedOut` error. Positioned
+    /// reads such as [`read_exact_at`](HttpFile::read_exact_at) fail that way
+    /// right away. Off by default.
+    pub fn with_stall_timeout(mut self, timeout: Duration) -> Self {
+        self.options.stall_timeout = Some(timeout);
+        self
+    }
+
+    /// Give up on all reads and seeks once `deadline` passes.
+    ///
+    /// Whatever is waited on at that point, a request, a response or a
+    /// backoff delay, is interrupted and the read fails with
+    /// [`HttpFileError::Cancelled`], an `ErrorKind::TimedOut` error, as do
+    /// all later ones. Forks such as [`read_at`](HttpFile::read_at) share the
+    /// deadline. It can be moved with [`HttpFile::set_deadline`], e.g. to
+    /// reuse the file for the next request of a web service.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.options.deadline = Some(deadline);
+        self
+    }
+
+    /// Send a range request again when it isn't answered within `timeout`,
+    /// see [`HttpFile::set_request_timeout`].
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.options.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Fall back to downloading the whole file when the server ignores ranges.
+    ///
+    /// If a range request is answered with `200 OK` and the full body, the
+    /// entire file is downloaded into memory once, and every following read
+    /// and seek is served from that copy without further requests. The memory
+    /// used is the size of the whole file, so only enable this for files that
+    /// comfortably fit in memory.
+    ///
+    /// Without the fallback, such a response is still read correctly, by
+    /// discarding the bytes before the read position, but every seek then
+    /// downloads the file again from its start.
+    pub fn with_full_download_fallback(mut self, enabled: bool) -> Self {
+        self.options.full_download_fallback = enabled;
+        self
+    }
+
+    /// Resume from the current position when a response is cut off mid-body.
+    ///
+    /// Flaky origins sometimes accept a range request, send the headers and a
+    /// little data, then drop the connection. By default the error surfaces
+    /// from the read. When enabled, the bytes already delivered are kept and
+    /// a new range request picks up where the stream left off, counting
+    /// against the same retry budget as timeouts.
+    ///
+    /// Discarding the partial data and retrying the whole range is not
+    /// offered: a streaming reader can't take back bytes it has already
+    /// handed to the caller.
+    pub fn with_partial_delivery(mut self, enabled: bool) -> Self {
+        self.options.partial_delivery = enabled;
+        self
+    }
+
+    /// Switch to bounded ranges when an open stream gets too slow.
+    ///
+    /// Throughput of the open response is measured over 5 second intervals.
+    /// If it falls below `min_bps` bytes per second, the stream is closed and
+    /// reads go on with requests of at most `fallback_window` bytes from the
+    /// current position, which recovers throughput on backends struggling
+    /// with large ranges. Only files with a known length are downgraded, and
+    /// the switch lasts for the lifetime of the `HttpFile`.
+    pub fn with_slow_stream_policy(mut self, min_bps: u64, fallback_window: u64) -> Self {
+        self.options.slow_stream_policy = Some((min_bps, fallback_window));
+        self
+    }
+
+    /// Abort transfers whose average throughput is too low.
+    ///
+    /// Once `grace` has passed since the first data arrived, reads fail with
+    /// [`HttpFileError::TooSlow`] whenever the average throughput since then
+    /// is below `bytes_per_sec`. Unlike a stall timeout, this catches
+    /// transfers that keep going, just persistently too slowly. Time is taken
+    /// from the [clock](Self::with_clock).
+    pub fn with_min_throughput(mut self, bytes_per_sec: u64, grace: Duration) -> Self {
+        self.options.min_throughput = Some((bytes_per_sec, grace));
+        self
+    }
+
+    /// Send `If-Range` with every range request, `false` by default.
+    ///
+    /// The file is pinned to the etag, or `Last-Modified` date, seen when it
+    /// is opened, as with [`HttpFile::snapshot`]. If it changes on the server
+    /// between requests, the server answers `200 OK` instead of `206`, and the
+    /// read fails with [`HttpFileError::FileChanged`](crate::HttpFileError::FileChanged)
+    /// rather than mixing bytes of two versions. Leave it off for servers
+    /// whose etags are not stable across requests.
+    pub fn with_if_range(mut self, enabled: bool) -> Self {
+        self.if_range = enabled;
+        self
+    }
+
+    /// Fail to open a file whose server doesn't serve byte ranges, off by
+    /// default.
+    ///
+    /// [`build`](Self::build) then returns
+    /// [`HttpFileError::RangesNotSupported`](crate::HttpFileError::RangesNotSupported)
+    /// when the `HEAD` answers `Accept-Ranges: none`. Without the header, or
+    /// with [`skip_head`](Self::skip_head), a one byte range is requested to
+    /// find out, and anything but `206 Partial Content` fails the same way.
+    /// Off, such a server is only noticed on the first seek, and served by
+    /// reading from the start or by the
+    /// [full download fallback](Self::with_full_download_fallback).
+    pub fn require_range(mut self, required: bool) -> Self {
+        self.require_range = required;
+        self
+    }
+
+    /// Whether responses are checked against the etag the file was opened
+    /// with, `true` by default.
+    ///
+    /// A range response reporting another etag means the file changed on
+    /// the server, and the read fails with
+    /// [`HttpFileError::FileChanged`](crate::HttpFileError::FileChanged)
+    /// instead of mixing bytes of two versions. Turn it off for servers whose
+    /// etags differ from one request to the next for the same content.
+    pub fn with_etag_check(mut self, enabled: bool) -> Self {
+        self.options.skip_etag_check = !enabled;
+        self
+    }
+
+    /// Whether range requests ask for the identity encoding, `true` by default.
+    ///
+    /// Byte ranges of a compressed response count compressed bytes, so they
+    /// don't match offsets in the file once a client transparently
+    /// decompresses it, and random access goes wrong. By default, range
+    /// requests are sent with `Accept-Encoding: identity`, unless the
+    /// [extra headers](Self::extra_headers) set another, and a response that
+    /// still comes back encoded fails the read with
+    /// [`HttpFileError::EncodedResponse`](crate::HttpFileError::EncodedResponse).
+    /// With `false`, encoded responses are read as they come, see
+    /// [Content length](HttpFile#content-length) for how their length is
+    /// taken; only do so with a client that doesn't decompress.
+    pub fn with_identity_encoding(mut self, enabled: bool) -> Self {
+        self.options.accept_any_encoding = !enabled;
+        self
+    }
+
+    /// Whether responses are checked against the digest the server
+    /// advertises, `false` by default.
+    ///
+    /// A response whose body is the whole file is hashed as it is read, and
+    /// once read to the end, compared with the RFC 3230 `Digest` (`sha-512`,
+    /// `sha-256` or `md5`) or the `Content-MD5` sent with it, or else with
+    /// the `HEAD`. A range response can't be checked against a digest of the
+    /// whole file, and is only checked against its own `Content-MD5`, if
+    /// any. A mismatch fails the read with
+    /// [`HttpFileError::ChecksumMismatch`](crate::HttpFileError::ChecksumMismatch).
+    /// Positioned reads such as [`read_exact_at`](HttpFile::read_exact_at)
+    /// aren't checked.
+    pub fn with_checksum_verification(mut self, enabled: bool) -> Self {
+        self.options.verify_checksums = enabled;
+        self
+    }
+
+    /// Whether reads stop at the reported length of the file, `true` by default.
+    ///
+    /// Some buggy servers report a length shorter than the actual file. With
+    /// `false`, a response that goes on past the reported length is read to
+    /// its end, and the length is corrected upward from the larger
+    /// `Content-Range` total of a response or from the observed end of the
+    /// data. [`read_exact_at`](HttpFile::read_exact_at) may then also ask
+    /// for bytes past the reported length. The length never shrinks.
+    pub fn with_trust_content_length(mut self, trust: bool) -> Self {
+        self.options.distrust_content_length = !trust;
+        self
+    }
+
+    /// Decide per status code whether failed reads are retried.
+    ///
+    /// By default, `429` and `503` are retried as [`RetryAction::RetryAfter`],
+    /// waiting as long as the server asks, up to a cap, timeouts and other
+    /// `5xx` responses are retried after the [backoff](Self::with_backoff)
+    /// delay, and any other error status fails the read. Statuses listed in
+    /// `policy` override that, e.g. `503` with [`RetryAction::Fail`] to give
+    /// up at once.
+    ///
+    /// Retries of every kind share the same budget of attempts.
+    pub fn with_status_retry_policy(
+        mut self,
+        policy: HashMap<reqwest::StatusCode, RetryAction>,
+    ) -> Self {
+        self.options.status_retry_policy = policy;
+        self
+    }
+
+    /// Send `end` as the last byte of ranges that would otherwise be open-ended.
+    ///
+    /// Some servers only honor ranges with an explicit end, so `bytes=100-`
+    /// is sent as `bytes=100-{end}` instead. Pick a value well beyond any file
+    /// size you expect (e.g. `99999999999`): if the file is longer than `end`,
+    /// reads are cut short at `end`.
+    pub fn with_open_range_end(mut self, end: u64) -> Self {
+        self.options.open_range_end = Some(end);
+        self
+    }
+
+    /// Pass every chunk through `transform` before it is delivered.
+    ///
+    /// Useful for simple position-independent transforms such as XOR
+    /// masking. The transform must return a chunk of the same length, as
+    /// positions are tracked in bytes of the remote file; reads fail with
+    /// `ErrorKind::InvalidData` if the length changes. Chunk boundaries
+    /// follow the network and are not predictable.
+    pub fn with_chunk_transform(mut self, transform: ChunkTransform) -> Self {
+        self.options.chunk_transform = Some(Hook(transform));
+        self
+    }
+
+    /// Share one request between concurrent identical positioned reads.
+    ///
+    /// When [`read_exact_at`](HttpFile::read_exact_at) is called for the same
+    /// offset and length on the file or its forks while such a read is still
+    /// in flight, the later calls wait for that request and copy its bytes
+    /// instead of fetching them again. Dropping one of the calls doesn't
+    /// cancel the request for the others. Errors of the shared request are
+    /// reported to each caller with the same `ErrorKind`, wrapping the
+    /// original error.
+    pub fn with_request_coalescing(mut self, enabled: bool) -> Self {
+        self.options.coalesce_reads = enabled;
+        self
+    }
+
+    /// Keep the last `size` bytes read from the network in memory, off by
+    /// default.
+    ///
+    /// Reads and seeks back into those bytes are then served from memory
+    /// rather than with a new request, which helps formats read with many
+    /// small reads and short hops back and forth, such as zip archives.
+    /// Seeking outside of the buffer leaves it to be refilled from the new
+    /// position. A `size` of zero turns the buffer off.
+    pub fn with_buffer_size(mut self, size: usize) -> Self {
+        self.options.buffer_size = (size > 0).then_some(size);
+        self
+    }
+
+    /// Read up to `bytes` ahead of the caller in the background, see
+    /// [`HttpFile::set_prefetch`].
+    pub fn with_prefetch(mut self, bytes: u64) -> Self {
+        self.options.prefetch = (bytes > 0).then_some(bytes);
+        self
+    }
+
+    /// Cache up to `blocks` aligned blocks of `block_size` bytes in memory,
+    /// dropping the least recently used, off by default.
+    ///
+    /// Reads then fetch the whole block they fall in with a request of its
+    /// own, and are served from memory while it stays cached, so repeated
+    /// seeks around the same hot region, common in columnar formats, cost
+    /// one request. Seeks make no request. The cache is emptied when a
+    /// [`refresh`](HttpFile::refresh) finds the file changed. It only
+    /// applies once the length of the file is known; before that, reads
+    /// stream as usual.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` or `blocks` is zero.
+    pub fn with_block_cache(mut self, block_size: u64, blocks: usize) -> Self {
+        assert!(block_size > 0, "block size must be positive");
+        assert!(blocks > 0, "number of blocks must be positive");
+        self.options.block_cache = Some((block_size, blocks));
+        self
+    }
+
+    /// Verify every chunk of `chunk_size` bytes against its leaf in `tree`.
+    ///
+    /// Ranges are widened to whole chunks, and data is held back until its
+    /// chunk is complete and matches, so nothing unverified is ever read. A
+    /// mismatch fails the read with [`HttpFileError::ChunkHashMismatch`].
+    /// Build `tree` with [`MerkleTree::new`] from a trusted root, and the
+    /// leaves from any source: random access then only needs the chunks read.
+    ///
+    /// Chunks are hashed after the [chunk transform](Self::with_chunk_transform),
+    /// if any.
+    ///
+    /// # Panics
+    ///
+    /// If `chunk_size` is zero.
+    pub fn with_chunk_merkle(mut self, tree: MerkleTree, chunk_size: u64) -> Self {
+        assert!(chunk_size > 0, "chunk size must not be zero");
+        self.options.chunk_merkle = Some((Arc::new(tree), chunk_size));
+        self
+    }
+
+    /// Report requests, received bytes and retries to `metrics`.
+    ///
+    /// See [`Metrics`] for the events recorded. Forks share the same sink.
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.options.metrics = Some(Hook(metrics));
+        self
+    }
+
+    /// Call `progress` with the position and the file length, if known,
+    /// whenever a read or seek moves the position, e.g. to drive a progress
+    /// bar.
+    ///
+    /// It is called from within `poll_read`/`poll_complete`, so it should be
+    /// cheap and must not block. Forks share the same callback.
+    pub fn with_progress(mut self, progress: Progress) -> Self {
+        self.options.progress = Some(Hook(progress));
+        self
+    }
+
+    /// Decide which failures are retried, and after how long, with `policy`.
+    ///
+    /// It replaces the default decision of retrying timeouts and the
+    /// statuses of [`with_status_retry_policy`](Self::with_status_retry_policy)
+    /// after the [backoff](Self::with_backoff) delay, e.g. to also retry
+    /// connection resets, or some 4xx answered by a proxy. The number of
+    /// attempts is still bounded by [`retries`](Self::retries).
+    pub fn with_retry_policy(mut self, policy: Arc<dyn RetryPolicy>) -> Self {
+        self.options.retry_policy = Some(Hook(policy));
+        self
+    }
+
+    /// Fail over to `mirrors`, in order, when requests to the url given to
+    /// [`build`](Self::build) keep failing.
+    ///
+    /// Once the retries are exhausted on one url, reads resume from the
+    /// current position on the next, wrapping around, until every url failed
+    /// in turn. Opening also tries them in order. A mirror answering with
+    /// another etag or length than the file was opened with fails the read
+    /// with [`HttpFileError::MirrorMismatch`]. Positioned reads don't fail
+    /// over.
+    pub fn with_mirrors(mut self, mirrors: &[&str]) -> Self {
+        self.mirrors = mirrors.iter().map(|url| url.to_string()).collect();
+        self
+    }
+
+    /// Ask `provider` for a new url when a request is refused, as expired
+    /// presigned urls are, then resume from the current position with it.
+    ///
+    /// The provider is asked on the statuses set with
+    /// [`with_url_refresh_statuses`](Self::with_url_refresh_statuses),
+    /// `403 Forbidden` by default. Each new url spends a retry attempt, so a
+    /// provider handing out urls that are refused too doesn't loop forever.
+    /// Positioned reads don't ask for a new url. Use
+    /// [`build_with_url_provider`](Self::build_with_url_provider) to get the
+    /// first url from the provider as well.
+    pub fn with_url_provider(mut self, provider: UrlProvider) -> Self {
+        self.options.url_provider = Some(Hook(provider));
+        self
+    }
+
+    /// Ask the [url provider](Self::with_url_provider) for a new url when a
+    /// request is answered with one of `statuses`, instead of `403` only.
+    pub fn with_url_refresh_statuses(mut self, statuses: &[reqwest::StatusCode]) -> Self {
+        self.options.url_refresh_statuses = Some(statuses.to_vec());
+        self
+    }
+
+    /// Send every request through `transport` instead of straight through
+    /// the client, e.g. to run a middleware stack that refreshes tokens.
+    ///
+    /// See [`Transport`]. Forks share the same transport.
+    pub fn with_transport(mut self, transport: Arc<dyn Transport>) -> Self {
+        self.options.transport = Some(Hook(transport));
+        self
+    }
+
+    /// Take the buffers the file fills itself from `allocator` instead of
+    /// the global allocator, e.g. to serve them out of a preallocated pool.
+    ///
+    /// See [`BufferAllocator`]. Forks share the same allocator.
+    pub fn with_allocator(mut self, allocator: Arc<dyn BufferAllocator>) -> Self {
+        self.options.allocator = Some(Hook(allocator));
+        self
+    }
+
+    /// Take the time from `clock` instead of the system clock.
+    ///
+    /// Only time-based policies such as
+    /// [`with_slow_stream_policy`](Self::with_slow_stream_policy) use it,
+    /// which makes them testable without waiting in real time.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.options.clock = Some(Hook(clock));
+        self
+    }
+
+    /// Read an object encrypted with a customer-provided key (S3 SSE-C).
+    ///
+    /// The `x-amz-server-side-encryption-customer-*` headers are attached to
+    /// the `HEAD` and every range request, with the key base64 encoded and its
+    /// MD5 digest computed as the service expects. Only `AES256` with a 32-byte
+    /// key is supported, anything else is rejected with
+    /// [`HttpFileError::InvalidSseCustomerKey`].
+    pub fn with_sse_customer_key(
+        mut self,
+        algorithm: &str,
+        key: &[u8],
+    ) -> Result<Self, HttpFileError> {
+        if algorithm != "AES256" || key.len() != 32 {
+            return Err(HttpFileError::InvalidSseCustomerKey {
+                algorithm: algorithm.to_string(),
+                key_len: key.len(),
+            });
+        }
+
+        let engine = base64::engine::general_purpose::STANDARD;
+        let base64_value = |bytes: &[u8]| {
+            HeaderValue::try_from(engine.encode(bytes)).expect("base64 is a valid header value")
+        };
+        let mut key_value = base64_value(key);
+        key_value.set_sensitive(true);
+
+        let headers = &mut self.options.headers;
+        headers.insert(SSE_CUSTOMER_ALGORITHM, HeaderValue::from_static("AES256"));
+        headers.insert(SSE_CUSTOMER_KEY, key_value);
+        headers.insert(SSE_CUSTOMER_KEY_MD5, base64_value(&md5::Md5::digest(key)));
+        Ok(self)
+    }
+
+    /// Open the file at the url handed out by `provider`, which is asked for
+    /// a new one whenever it expires, see
+    /// [`with_url_provider`](Self::with_url_provider).
+    pub async fn build_with_url_provider(
+        self,
+        provider: UrlProvider,
+    ) -> Result<HttpFile, HttpFileError> {
+        let url = provider().await;
+        self.with_url_provider(provider).build(url.as_str()).await
+    }
+
+    /// Open the file at `url`, with a `HEAD` request unless [`skip_head`](Self::skip_head) was set.
+    pub async fn build(self, url: &str) -> Result<HttpFile, HttpFileError> {
+        let client = match self.client {
+            Some(_) if self.proxy.is_some() => {
+                return Err(HttpFileError::ClientOption {
+                    option: "with_proxy",
+                });
+            }
+            Some(_) if self.max_redirects.is_some() => {
+                return Err(HttpFileError::ClientOption {
+                    option: "with_max_redirects",
+                });
+            }
+            Some(client) => client,
+            None if self.proxy.is_none() && self.max_redirects.is_none() => {
+                reqwest::Client::default()
+            }
+            None => {
+                let mut client = reqwest::Client::builder();
+                if let Some(proxy) = self.proxy {
+                    client = client.proxy(proxy);
+                }
+                if let Some(max) = self.max_redirects {
+                    client = client.redirect(crate::redirect::policy(max));
+                }
+                client.build()?
+            }
+        };
+        let mut options = self.options;
+        if options.coalesce_reads {
+            options.in_flight = Some(Default::default());
+        }
+        if !self.mirrors.is_empty() {
+            options.mirrors = std::iter::once(url)
+                .chain(self.mirrors.iter().map(String::as_str))
+                .map(|url| Ok(client.get(url).build()?.url().clone()))
+                .collect::<reqwest::Result<_>>()?;
+        }
+        let mut file = match self.skip_head {
+            Some(content_length) => {
+                let url = client.get(url).build()?.url().clone();
+                HttpFile::known(client, url, content_length, None, options)
+            }
+            None if !options.mirrors.is_empty() => HttpFile::open_mirrors(client, options).await?,
+            None => HttpFile::open(client, url, options).await?,
+        };
+        if self.require_range {
+            file.check_range_support().await?;
+        }
+        if self.if_range {
+            file.snapshot();
+        }
+        Ok(file)
+    }
+}
@@ -0,0 +1,46 @@
+use reqwest::header::{HeaderMap, HeaderName};
+
+const X_CACHE: HeaderName = HeaderName::from_static("x-cache");
+const CACHE_STATUS: HeaderName = HeaderName::from_static("cache-status");
+
+/// Whether a cache in front of the origin served a response, as reported by
+/// the `Cache-Status` or `X-Cache` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CacheStatus {
+    /// Served from the cache.
+    Hit,
+    /// Forwarded to the origin.
+    Miss,
+}
+
+/// The status reported by the cache closest to us, if any.
+///
+/// `Cache-Status` (RFC 9211) lists caches from the origin outwards, so its
+/// last member is used, and `hit` there means a hit. The informal `X-Cache`
+/// comes in many forms such as `HIT`, `MISS from proxy` or `TCP_MISS`, and
+/// proxies usually prepend to it, so its first value is used.
+pub(crate) fn cache_status(headers: &HeaderMap) -> Option<CacheStatus> {
+    if let Some(value) = headers.get(CACHE_STATUS).and_then(|v| v.to_str().ok()) {
+        let closest = value.rsplit(',').next()?;
+        let hit = closest
+            .split(';')
+            .skip(1)
+            .any(|param| param.trim().eq_ignore_ascii_case("hit"));
+        return Some(if hit {
+            CacheStatus::Hit
+        } else {
+            CacheStatus::Miss
+        });
+    }
+
+    let value = headers.get(X_CACHE)?.to_str().ok()?;
+    let closest = value.split(',').next()?.to_ascii_uppercase();
+    if closest.contains("HIT") {
+        Some(CacheStatus::Hit)
+    } else if closest.contains("MISS") {
+        Some(CacheStatus::Miss)
+    } else {
+        None
+    }
+}
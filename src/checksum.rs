@@ -0,0 +1,174 @@
+use base64::Engine;
+use md5::Digest;
+use reqwest::{
+    StatusCode,
+    header::{CONTENT_RANGE, HeaderMap, HeaderName},
+};
+
+use crate::{HttpFileError, header_string};
+
+const CONTENT_MD5: HeaderName = HeaderName::from_static("content-md5");
+const DIGEST: HeaderName = HeaderName::from_static("digest");
+
+/// Algorithms of the RFC 3230 `Digest` header, most preferred first.
+const ALGORITHMS: [&str; 3] = ["sha-512", "sha-256", "md5"];
+
+/// A digest advertised by the server, checked against the body of a
+/// response as it is received, see
+/// [`HttpFileBuilder::with_checksum_verification`](crate::HttpFileBuilder::with_checksum_verification).
+pub(crate) struct Checksum {
+    /// name of the header the digest came from
+    source: &'static str,
+    /// the advertised digest, base64
+    expected: String,
+    hasher: Hasher,
+    /// length of the body, if known
+    len: Option<u64>,
+    /// body bytes hashed so far
+    received: u64,
+}
+
+enum Hasher {
+    Md5(md5::Md5),
+    Sha256(sha2::Sha256),
+    Sha512(sha2::Sha512),
+}
+
+impl Hasher {
+    fn new(algorithm: &str) -> Option<Self> {
+        match algorithm {
+            "md5" => Some(Self::Md5(md5::Md5::new())),
+            "sha-256" => Some(Self::Sha256(sha2::Sha256::new())),
+            "sha-512" => Some(Self::Sha512(sha2::Sha512::new())),
+            _ => None,
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Md5(hasher) => hasher.update(data),
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Sha512(hasher) => hasher.update(data),
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Md5(hasher) => hasher.finalize().to_vec(),
+            Self::Sha256(hasher) => hasher.finalize().to_vec(),
+            Self::Sha512(hasher) => hasher.finalize().to_vec(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Checksum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Checksum")
+            .field("source", &self.source)
+            .field("expected", &self.expected)
+            .field("len", &self.len)
+            .field("received", &self.received)
+            .finish()
+    }
+}
+
+impl Checksum {
+    /// The digest `resp` can be checked against: a digest of the whole file
+    /// when its body is the whole file, from its headers or else from
+    /// `head_headers`, or the `Content-MD5` of a partial body.
+    pub(crate) fn for_response(resp: &reqwest::Response, head_headers: &HeaderMap) -> Option<Self> {
+        let checksum = if is_whole(resp) {
+            whole(resp.headers()).or_else(|| whole(head_headers))
+        } else if resp.status() == StatusCode::PARTIAL_CONTENT {
+            content_md5(resp.headers())
+        } else {
+            None
+        }?;
+        Some(Self {
+            len: resp.content_length(),
+            ..checksum
+        })
+    }
+
+    fn new(source: &'static str, algorithm: &str, expected: String) -> Option<Self> {
+        Some(Self {
+            source,
+            expected,
+            hasher: Hasher::new(algorithm)?,
+            len: None,
+            received: 0,
+        })
+    }
+
+    pub(crate) fn push(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+        self.received += data.len() as u64;
+    }
+
+    /// Whether the whole body was hashed, as far as its length is known.
+    pub(crate) fn is_complete(&self) -> bool {
+        self.len == Some(self.received)
+    }
+
+    /// Compare the hash of the body received with the advertised digest.
+    pub(crate) fn verify(self) -> Result<(), HttpFileError> {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let actual = engine.encode(self.hasher.finalize());
+        if actual != self.expected {
+            return Err(HttpFileError::ChecksumMismatch {
+                header: self.source.to_string(),
+                expected: self.expected,
+                actual,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Whether the body of `resp` is the whole file.
+fn is_whole(resp: &reqwest::Response) -> bool {
+    match resp.status() {
+        StatusCode::OK => true,
+        StatusCode::PARTIAL_CONTENT => header_string(resp.headers(), CONTENT_RANGE)
+            .and_then(|value| {
+                let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+                let (start, end) = range.split_once('-')?;
+                let start = start.trim().parse::<u64>().ok()?;
+                let end = end.trim().parse::<u64>().ok()?;
+                let total = total.trim().parse::<u64>().ok()?;
+                Some(start == 0 && end.checked_add(1) == Some(total))
+            })
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// The digest of the whole file in `headers`: the strongest algorithm of
+/// `Digest` known, or else `Content-MD5`.
+fn whole(headers: &HeaderMap) -> Option<Checksum> {
+    let digests: Vec<(String, String)> = headers
+        .get_all(DIGEST)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|item| {
+            let (algorithm, value) = item.split_once('=')?;
+            Some((
+                algorithm.trim().to_ascii_lowercase(),
+                value.trim().to_string(),
+            ))
+        })
+        .collect();
+    ALGORITHMS
+        .iter()
+        .find_map(|&preferred| {
+            let (algorithm, value) = digests.iter().find(|(a, _)| a == preferred)?;
+            Checksum::new("Digest", algorithm, value.clone())
+        })
+        .or_else(|| content_md5(headers))
+}
+
+fn content_md5(headers: &HeaderMap) -> Option<Checksum> {
+    let value = header_string(headers, CONTENT_MD5)?;
+    Checksum::new("Content-MD5", "md5", value.trim().to_string())
+}
@@ -0,0 +1,8 @@
+use std::time::Instant;
+
+/// A source of the current time, see
+/// [`HttpFileBuilder::with_clock`](crate::HttpFileBuilder::with_clock).
+pub trait Clock: Send + Sync {
+    /// The current time.
+    fn now(&self) -> Instant;
+}
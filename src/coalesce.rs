@@ -0,0 +1,103 @@
+use std::{
+    collections::HashMap,
+    io::Error,
+    sync::{Arc, Mutex},
+};
+
+use bytes::Bytes;
+use futures_util::{
+    FutureExt,
+    future::{BoxFuture, Shared, WeakShared},
+};
+
+use crate::HttpFile;
+
+type Fetch = BoxFuture<'static, Result<Bytes, Arc<Error>>>;
+
+/// Positioned reads in flight for a file and all its forks, keyed by offset
+/// and length, see [`HttpFileBuilder::with_request_coalescing`](crate::HttpFileBuilder::with_request_coalescing).
+///
+/// Only the waiters hold on to a read, the map merely lets others join it.
+#[derive(Default)]
+pub(crate) struct InFlight(Mutex<HashMap<(u64, usize), WeakShared<Fetch>>>);
+
+impl std::fmt::Debug for InFlight {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reads = self.0.lock().unwrap().len();
+        f.debug_struct("InFlight").field("reads", &reads).finish()
+    }
+}
+
+/// A waiter on a read in flight, taking it out of the map once the last one
+/// is gone.
+struct Waiter<'a> {
+    read: Option<Shared<Fetch>>,
+    in_flight: &'a InFlight,
+    key: (u64, usize),
+}
+
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        // drops the fetch if this was the last waiter
+        self.read.take();
+        let mut reads = self.in_flight.0.lock().unwrap();
+        if reads
+            .get(&self.key)
+            .is_some_and(|read| read.upgrade().is_none())
+        {
+            reads.remove(&self.key);
+        }
+    }
+}
+
+impl InFlight {
+    /// Read `len` bytes at `pos` from `file`, joining an identical read in
+    /// flight if there is one.
+    ///
+    /// The fetch runs on a fork of `file` and is only dropped with its last
+    /// waiter, so cancelling one of them doesn't affect the others.
+    pub(crate) async fn read(
+        self: &Arc<Self>,
+        file: &HttpFile,
+        pos: u64,
+        len: usize,
+    ) -> std::io::Result<Bytes> {
+        let key = (pos, len);
+        let read = {
+            let mut reads = self.0.lock().unwrap();
+            match reads.get(&key).and_then(WeakShared::upgrade) {
+                Some(read) => read,
+                None => {
+                    let read = self.fetch(file, pos, len);
+                    reads.insert(key, read.downgrade().expect("not polled yet"));
+                    read
+                }
+            }
+        };
+        let mut waiter = Waiter {
+            read: Some(read),
+            in_flight: self,
+            key,
+        };
+        let read = waiter.read.as_mut().expect("taken on drop");
+        read.await.map_err(|e| Error::new(e.kind(), e))
+    }
+
+    /// Read `len` bytes at `pos` on a fork of `file`, out of the map once
+    /// done so the next read fetches afresh.
+    fn fetch(self: &Arc<Self>, file: &HttpFile, pos: u64, len: usize) -> Shared<Fetch> {
+        let mut reader = file.fork();
+        let in_flight = Arc::downgrade(self);
+        async move {
+            let mut buf = reader.options.buffer(len);
+            buf.resize(len, 0);
+            let result = reader.fetch_exact_at(pos, &mut buf[..]).await;
+            if let Some(in_flight) = in_flight.upgrade() {
+                in_flight.0.lock().unwrap().remove(&(pos, len));
+            }
+            result.map(|()| buf.freeze()).map_err(Arc::new)
+        }
+        .boxed()
+        .shared()
+    }
+}
@@ -0,0 +1,347 @@
+use std::{
+    io::{Error, ErrorKind, SeekFrom},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, ready},
+    time::SystemTime,
+};
+
+use bytes::Bytes;
+use futures_util::{FutureExt, future::BoxFuture};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, ReadBuf};
+
+use crate::{HttpFile, HttpFileSlice, RemoteFile};
+
+/// Blocks are this large unless set with [`DiskCache::with_block_size`].
+const DEFAULT_BLOCK_SIZE: u64 = 1024 * 1024;
+
+/// Name of the file recording the version an entry was filled from.
+const META: &str = "meta";
+
+/// A directory caching blocks of remote files, so repeated reads of the same
+/// ranges are served from disk rather than downloaded again.
+///
+/// Each file has an entry keyed by its url, holding the blocks read so far
+/// along with the etag they were read at. Opening a file whose etag changed
+/// since empties its entry. Files without an etag can't be validated and
+/// aren't cached. Once the blocks of all entries take more than the maximum
+/// size, the least recently used ones are removed.
+///
+/// ```rust no_run
+/// # async fn run() -> std::io::Result<()> {
+/// use tokio::io::AsyncReadExt;
+///
+/// let cache = remote_file::DiskCache::new("/tmp/remote-file", 1 << 30);
+/// let file = remote_file::HttpFile::new(reqwest::Client::new(), "http://example.com/file")
+///     .await
+///     .map_err(std::io::Error::other)?;
+/// let mut file = cache.open(file).await?;
+/// let mut buf = vec![];
+/// file.read_to_end(&mut buf).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+    max_size: u64,
+    block_size: u64,
+}
+
+impl DiskCache {
+    /// A cache in `dir`, created if missing, holding up to `max_size` bytes
+    /// of blocks.
+    pub fn new(dir: impl Into<PathBuf>, max_size: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            max_size,
+            block_size: DEFAULT_BLOCK_SIZE,
+        }
+    }
+
+    /// Fetch and cache files in blocks of `bytes`, 1 MiB by default. An entry
+    /// filled with another block size is emptied when opened.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` is zero.
+    pub fn with_block_size(mut self, bytes: u64) -> Self {
+        assert!(bytes > 0, "block size must be positive");
+        self.block_size = bytes;
+        self
+    }
+
+    /// Read `file` through the cache, from position 0.
+    ///
+    /// The etag `file` was opened with is checked against the one the entry
+    /// for its url was filled at, and the entry is emptied if they differ.
+    /// The length of the file must be known.
+    pub async fn open(&self, file: HttpFile) -> std::io::Result<CachedHttpFile> {
+        let len = file.content_length().ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "cannot cache without known content length",
+            )
+        })?;
+        let entry = match file.etag() {
+            Some(etag) => Some(self.open_entry(file.url(), etag).await?),
+            None => {
+                log::debug!("{} has no etag, reading it uncached", file.url());
+                None
+            }
+        };
+        Ok(CachedHttpFile {
+            file,
+            cache: Arc::new(Cache {
+                root: self.dir.clone(),
+                max_size: self.max_size,
+                block_size: self.block_size,
+                entry,
+                len,
+            }),
+            pos: 0,
+            block: None,
+            pending: None,
+        })
+    }
+
+    /// The directory of the entry for `url`, emptied unless it was filled at
+    /// `etag` with the current block size.
+    async fn open_entry(&self, url: &reqwest::Url, etag: &str) -> std::io::Result<PathBuf> {
+        let key = Sha256::digest(url.as_str().as_bytes());
+        let dir = self.dir.join(hex(&key));
+        let meta = format!("{}\n{}\n", self.block_size, etag);
+        match tokio::fs::read_to_string(dir.join(META)).await {
+            Ok(saved) if saved == meta => return Ok(dir),
+            Ok(_) => {
+                log::info!("{} changed, dropping its cached blocks", url);
+                tokio::fs::remove_dir_all(&dir).await?;
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        tokio::fs::create_dir_all(&dir).await?;
+        tokio::fs::write(dir.join(META), meta).await?;
+        Ok(dir)
+    }
+}
+
+/// What reads of a [`CachedHttpFile`] need to find and store its blocks.
+#[derive(Debug)]
+struct Cache {
+    root: PathBuf,
+    max_size: u64,
+    block_size: u64,
+    /// directory of the entry of the file, `None` when it isn't cached
+    entry: Option<PathBuf>,
+    len: u64,
+}
+
+impl Cache {
+    /// Block `index` of the file, from the cache if there, or else read from
+    /// `part`, the slice of the file it covers, and stored.
+    async fn block(self: Arc<Self>, mut part: HttpFileSlice, index: u64) -> std::io::Result<Bytes> {
+        let len = part.len();
+        let path = self.entry.as_ref().map(|dir| dir.join(index.to_string()));
+        if let Some(path) = &path {
+            match tokio::fs::read(path).await {
+                Ok(data) if data.len() as u64 == len => {
+                    touch(path).await;
+                    return Ok(data.into());
+                }
+                Ok(_) => log::warn!("ignoring truncated cache block {}", path.display()),
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => log::warn!("failed to read cache block {}: {}", path.display(), e),
+            }
+        }
+        let mut bytes = vec![0; len as usize];
+        part.read_exact(&mut bytes).await?;
+        if let Some(path) = &path
+            && let Err(e) = self.store(path, &bytes).await
+        {
+            log::warn!("failed to cache block {}: {}", path.display(), e);
+        }
+        Ok(bytes.into())
+    }
+
+    /// Write a block to `path` atomically, then evict blocks over the size.
+    async fn store(&self, path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        tokio::fs::write(&tmp, bytes).await?;
+        tokio::fs::rename(&tmp, path).await?;
+        self.evict().await
+    }
+
+    /// Remove the least recently used blocks of all entries until they fit
+    /// in the maximum size.
+    async fn evict(&self) -> std::io::Result<()> {
+        let mut blocks = vec![];
+        let mut total = 0;
+        let mut entries = tokio::fs::read_dir(&self.root).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if !entry.file_type().await?.is_dir() {
+                continue;
+            }
+            let mut files = tokio::fs::read_dir(entry.path()).await?;
+            while let Some(file) = files.next_entry().await? {
+                let name = file.file_name();
+                if name == META || name.to_string_lossy().ends_with(".tmp") {
+                    continue;
+                }
+                let meta = file.metadata().await?;
+                total += meta.len();
+                blocks.push((meta.modified()?, meta.len(), file.path()));
+            }
+        }
+        if total <= self.max_size {
+            return Ok(());
+        }
+        blocks.sort();
+        for (_, len, path) in blocks {
+            if total <= self.max_size {
+                break;
+            }
+            match tokio::fs::remove_file(&path).await {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+                _ => total -= len,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Mark the block at `path` as just used. Failing only makes it more likely
+/// to be evicted, so errors are ignored.
+async fn touch(path: &Path) {
+    if let Ok(file) = tokio::fs::OpenOptions::new().append(true).open(path).await {
+        let _ = file.into_std().await.set_modified(SystemTime::now());
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+type BlockFuture = BoxFuture<'static, std::io::Result<Bytes>>;
+
+/// An [`HttpFile`] read through a [`DiskCache`], see [`DiskCache::open`].
+///
+/// Reads fetch whole blocks, each with a range request of its own, unless the
+/// cache has them already.
+pub struct CachedHttpFile {
+    file: HttpFile,
+    cache: Arc<Cache>,
+    pos: u64,
+    /// the block the position was last in, with its index
+    block: Option<(u64, Bytes)>,
+    /// the block being read, with its index
+    pending: Option<(u64, BlockFuture)>,
+}
+
+impl std::fmt::Debug for CachedHttpFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachedHttpFile")
+            .field("file", &self.file)
+            .field("cache", &self.cache)
+            .field("pos", &self.pos)
+            .field("block", &self.block.as_ref().map(|(index, _)| index))
+            .field("pending", &self.pending.as_ref().map(|(index, _)| index))
+            .finish()
+    }
+}
+
+impl CachedHttpFile {
+    /// The file read through the cache.
+    pub fn get_ref(&self) -> &HttpFile {
+        &self.file
+    }
+
+    /// Whether reads go through the cache, `false` for a file without an etag.
+    pub fn is_cached(&self) -> bool {
+        self.cache.entry.is_some()
+    }
+
+    /// The position of the cursor.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+}
+
+impl AsyncRead for CachedHttpFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = &mut *self;
+        loop {
+            if this.pos >= this.cache.len || buf.remaining() == 0 {
+                return Poll::Ready(Ok(()));
+            }
+            let index = this.pos / this.cache.block_size;
+            if let Some((i, block)) = &this.block
+                && *i == index
+            {
+                let offset = (this.pos - index * this.cache.block_size) as usize;
+                let n = buf.remaining().min(block.len() - offset);
+                buf.put_slice(&block[offset..offset + n]);
+                this.pos += n as u64;
+                return Poll::Ready(Ok(()));
+            }
+            if !matches!(&this.pending, Some((i, _)) if *i == index) {
+                let start = index * this.cache.block_size;
+                let end = start
+                    .saturating_add(this.cache.block_size)
+                    .min(this.cache.len);
+                let part = this.file.slice(start..end);
+                let fetch = this.cache.clone().block(part, index);
+                this.pending = Some((index, fetch.boxed()));
+            }
+            let (_, fetch) = this.pending.as_mut().expect("just set");
+            let block = ready!(fetch.poll_unpin(cx));
+            this.pending = None;
+            this.block = Some((index, block?));
+        }
+    }
+}
+
+impl AsyncSeek for CachedHttpFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        let pos = match position {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(n) => self.cache.len.checked_add_signed(n),
+            SeekFrom::Current(n) => self.pos.checked_add_signed(n),
+        };
+        let pos =
+            pos.ok_or_else(|| Error::new(ErrorKind::InvalidInput, "invalid seek before start"))?;
+        if pos > self.cache.len {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "invalid seek beyond end",
+            ));
+        }
+        self.pos = pos;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        Poll::Ready(Ok(self.pos))
+    }
+}
+
+impl RemoteFile for CachedHttpFile {
+    fn content_length(&self) -> Option<u64> {
+        Some(self.cache.len)
+    }
+
+    fn etag(&self) -> Option<&str> {
+        self.file.etag()
+    }
+
+    fn mime(&self) -> Option<&str> {
+        self.file.mime()
+    }
+}
@@ -0,0 +1,104 @@
+/// The file name suggested by a `Content-Disposition` header value.
+///
+/// The RFC 5987 `filename*=charset'lang'value` form is preferred over the
+/// plain `filename=`, as RFC 6266 asks. Any directory part is dropped, so the
+/// name can't point outside of the directory it is saved to.
+pub(crate) fn filename(value: &str) -> Option<String> {
+    let params = params(value);
+    let extended = params
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("filename*"))
+        .and_then(|(_, value)| decode_extended(value));
+    let name = extended.or_else(|| {
+        params
+            .into_iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("filename"))
+            .map(|(_, value)| value)
+    })?;
+    base_name(&name)
+}
+
+/// The last segment of the path of `url`, percent-decoded.
+pub(crate) fn url_filename(url: &reqwest::Url) -> Option<String> {
+    let segment = url.path_segments()?.next_back()?;
+    let decoded = percent_decode(segment)?;
+    base_name(&String::from_utf8_lossy(&decoded))
+}
+
+/// `name` without any directory part, if anything is left.
+fn base_name(name: &str) -> Option<String> {
+    let base = name.rsplit(['/', '\\']).next()?.trim();
+    (!base.is_empty() && base != "." && base != "..").then(|| base.to_string())
+}
+
+/// The `name=value` parameters after the disposition type, with quoted
+/// values unescaped.
+fn params(value: &str) -> Vec<(String, String)> {
+    let mut params = vec![];
+    let mut rest = match value.split_once(';') {
+        Some((_, rest)) => rest,
+        None => return params,
+    };
+    while let Some((name, after)) = rest.split_once('=') {
+        let name = name.trim().to_string();
+        let after = after.trim_start();
+        let (value, next) = match after.strip_prefix('"') {
+            Some(quoted) => {
+                let mut value = String::new();
+                let mut chars = quoted.char_indices();
+                let mut end = quoted.len();
+                while let Some((i, c)) = chars.next() {
+                    match c {
+                        '\\' => value.extend(chars.next().map(|(_, c)| c)),
+                        '"' => {
+                            end = i + 1;
+                            break;
+                        }
+                        c => value.push(c),
+                    }
+                }
+                let next = &quoted[end..];
+                (value, next.split_once(';').map_or("", |(_, next)| next))
+            }
+            None => match after.split_once(';') {
+                Some((value, next)) => (value.trim().to_string(), next),
+                None => (after.trim().to_string(), ""),
+            },
+        };
+        params.push((name, value));
+        rest = next;
+    }
+    params
+}
+
+/// Decode an RFC 5987 `charset'lang'value`, in UTF-8 or ISO-8859-1.
+fn decode_extended(value: &str) -> Option<String> {
+    let mut parts = value.splitn(3, '\'');
+    let (charset, _lang, encoded) = (parts.next()?, parts.next()?, parts.next()?);
+    let bytes = percent_decode(encoded)?;
+    if charset.eq_ignore_ascii_case("utf-8") {
+        String::from_utf8(bytes).ok()
+    } else if charset.eq_ignore_ascii_case("iso-8859-1") {
+        Some(bytes.into_iter().map(char::from).collect())
+    } else {
+        None
+    }
+}
+
+/// `value` with its `%XX` escapes decoded, `None` if one is malformed.
+fn percent_decode(value: &str) -> Option<Vec<u8>> {
+    let mut bytes = value.bytes();
+    let mut decoded = Vec::with_capacity(value.len());
+    while let Some(b) = bytes.next() {
+        if b != b'%' {
+            decoded.push(b);
+            continue;
+        }
+        let hex = [bytes.next()?, bytes.next()?];
+        if !hex.iter().all(u8::is_ascii_hexdigit) {
+            return None;
+        }
+        decoded.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+    }
+    Some(decoded)
+}
@@ -0,0 +1,262 @@
+use std::{
+    ffi::OsString,
+    io::{Error, ErrorKind, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use bytes::Buf;
+use futures_util::StreamExt;
+use tokio::io::{AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{HttpFile, HttpFileError};
+
+/// Progress is saved every time this many bytes have been written.
+const CHECKPOINT_INTERVAL: u64 = 1024 * 1024;
+
+/// `path` with `suffix` appended to its file name.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    name.into()
+}
+
+/// Remove the file at `path`, if there is one.
+async fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Progress of an interrupted download, as saved next to it.
+struct Checkpoint {
+    written: u64,
+    etag: String,
+}
+
+impl Checkpoint {
+    async fn load(path: &Path) -> std::io::Result<Option<Self>> {
+        let text = match tokio::fs::read_to_string(path).await {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let parsed = text.split_once('\n').and_then(|(written, etag)| {
+            Some(Self {
+                written: written.parse().ok()?,
+                etag: etag.trim_end_matches('\n').to_string(),
+            })
+        });
+        if parsed.is_none() {
+            log::warn!("ignoring invalid checkpoint {}", path.display());
+        }
+        Ok(parsed)
+    }
+
+    /// Replace the checkpoint at `path` atomically, so a crash never leaves
+    /// a torn one behind.
+    async fn save(&self, path: &Path) -> std::io::Result<()> {
+        let tmp = with_suffix(path, ".tmp");
+        tokio::fs::write(&tmp, format!("{}\n{}\n", self.written, self.etag)).await?;
+        tokio::fs::rename(&tmp, path).await
+    }
+}
+
+impl HttpFile {
+    /// Copy the file from the current position to its end into `writer`,
+    /// returning the number of bytes written.
+    ///
+    /// Chunks are written as they arrive from the open response, so memory
+    /// use stays at about one chunk whatever the size of the file, and
+    /// retries and checks apply as for any read. `writer` is flushed, and
+    /// the cursor is left at the end of the file.
+    pub async fn download_to_writer<W: AsyncWrite + Unpin + ?Sized>(
+        &mut self,
+        writer: &mut W,
+    ) -> std::io::Result<u64> {
+        let mut written = 0;
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = self.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            writer.write_all(&buf[..n]).await?;
+            written += n as u64;
+            // the rest of a large chunk goes out without another copy
+            while let Some(mut rest) = self.chunk_as_buf() {
+                let len = rest.chunk().len();
+                writer.write_all(rest.chunk()).await?;
+                rest.advance(len);
+                written += len as u64;
+            }
+        }
+        writer.flush().await?;
+        Ok(written)
+    }
+
+    /// Download the whole file into `out` over up to `connections` range
+    /// requests at a time, each fetching `chunk_size` bytes.
+    ///
+    /// Each chunk is written at its offset as soon as it arrives, so at most
+    /// `connections` chunks are held in memory. The cursor of this file is
+    /// left untouched. The length of the file must be known.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `connections` or `chunk_size` is zero.
+    pub async fn download_parallel<W: AsyncWrite + AsyncSeek + Unpin>(
+        &self,
+        out: &mut W,
+        connections: usize,
+        chunk_size: u64,
+    ) -> std::io::Result<()> {
+        assert!(connections > 0, "connections must be positive");
+        assert!(chunk_size > 0, "chunk size must be positive");
+        let len = self.content_length.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "cannot download in parallel without known content length",
+            )
+        })?;
+        let chunks = (0..len)
+            .step_by(chunk_size.try_into().unwrap_or(usize::MAX))
+            .map(|start| async move {
+                let end = start.saturating_add(chunk_size).min(len) - 1;
+                let bytes = self.read_range(start, end).await?;
+                Ok::<_, Error>((start, bytes))
+            });
+        let mut chunks = futures_util::stream::iter(chunks).buffer_unordered(connections);
+        while let Some(chunk) = chunks.next().await {
+            let (start, bytes) = chunk?;
+            out.seek(SeekFrom::Start(start)).await?;
+            out.write_all(&bytes).await?;
+        }
+        out.flush().await
+    }
+
+    /// Download the whole file to `path`, picking up a partial download left
+    /// by a previous run.
+    ///
+    /// Data goes to `path.part`, which is renamed to `path` once complete.
+    /// An existing `path.part` is resumed from its size with a range request.
+    /// When the file has an etag, it is saved to `path.part.etag`, and a
+    /// partial download of another version is truncated and started over.
+    /// Reads are pinned with [`snapshot`](Self::snapshot), so the file
+    /// changing on the server midway fails the download rather than mixing
+    /// versions.
+    pub async fn download_to_file(&mut self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let part = with_suffix(path, ".part");
+        let etag_path = with_suffix(&part, ".etag");
+
+        let mut out = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&part)
+            .await?;
+        let on_disk = out.metadata().await?.len();
+        let saved_etag = match tokio::fs::read_to_string(&etag_path).await {
+            Ok(etag) => Some(etag),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+        let same_version = self.etag.is_none() || saved_etag == self.etag;
+        let fits = self.content_length.is_none_or(|len| on_disk <= len);
+        let resume_at = if same_version && fits {
+            on_disk
+        } else {
+            log::info!("{} changed, restarting the download", self.url);
+            0
+        };
+        if let Some(etag) = &self.etag {
+            tokio::fs::write(&etag_path, etag).await?;
+        }
+        out.set_len(resume_at).await?;
+        out.seek(SeekFrom::Start(resume_at)).await?;
+        self.snapshot();
+        self.seek(SeekFrom::Start(resume_at)).await?;
+
+        self.download_to_writer(&mut out).await?;
+        out.sync_all().await?;
+        drop(out);
+        tokio::fs::rename(&part, path).await?;
+        remove_if_exists(&etag_path).await
+    }
+
+    /// Download the file at `url` to `dest`, resuming a previous run.
+    ///
+    /// Every megabyte, `dest` is synced and the number of bytes written so far
+    /// is saved along with the etag of the file to `checkpoint_path`. When a
+    /// checkpoint is found on start and the etag is unchanged, the download
+    /// picks up from there with a range request, so it survives a restart of
+    /// the process. If the etag changed, or the server reports none, the
+    /// download starts over. The file is pinned with
+    /// [`snapshot`](Self::snapshot) throughout, and the checkpoint is removed
+    /// once the download completes. Returns the length of the file.
+    pub async fn download_to_resumable(
+        client: reqwest::Client,
+        url: &str,
+        dest: impl AsRef<Path>,
+        checkpoint_path: impl AsRef<Path>,
+    ) -> std::io::Result<u64> {
+        let (dest, checkpoint_path) = (dest.as_ref(), checkpoint_path.as_ref());
+        let mut file = HttpFile::new(client, url)
+            .await
+            .map_err(|e| Error::from(HttpFileError::from(e)))?;
+        file.snapshot();
+        let etag = file.etag().map(str::to_string);
+
+        let mut out = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(dest)
+            .await?;
+        let on_disk = out.metadata().await?.len();
+        let resume_at = match Checkpoint::load(checkpoint_path).await? {
+            Some(checkpoint)
+                if etag.as_ref() == Some(&checkpoint.etag) && checkpoint.written <= on_disk =>
+            {
+                checkpoint.written
+            }
+            Some(_) => {
+                log::info!("{} changed, restarting the download", url);
+                0
+            }
+            None => 0,
+        };
+        // anything past the checkpoint may not have been synced
+        out.set_len(resume_at).await?;
+        out.seek(SeekFrom::Start(resume_at)).await?;
+        file.seek(SeekFrom::Start(resume_at)).await?;
+
+        let mut written = resume_at;
+        let mut next_checkpoint = written + CHECKPOINT_INTERVAL;
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = file.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            out.write_all(&buf[..n]).await?;
+            written += n as u64;
+            if let Some(etag) = &etag
+                && written >= next_checkpoint
+            {
+                out.sync_data().await?;
+                let checkpoint = Checkpoint {
+                    written,
+                    etag: etag.clone(),
+                };
+                checkpoint.save(checkpoint_path).await?;
+                next_checkpoint = written + CHECKPOINT_INTERVAL;
+            }
+        }
+
+        out.sync_all().await?;
+        remove_if_exists(checkpoint_path).await?;
+        Ok(written)
+    }
+}
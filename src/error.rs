@@ -0,0 +1,322 @@
+/// Errors reported by [`HttpFile`](crate::HttpFile) operations.
+///
+/// When surfaced through the `AsyncRead`/`AsyncSeek` traits, it is wrapped in
+/// a `std::io::Error` and can be recovered with `downcast_ref`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum HttpFileError {
+    /// The underlying HTTP request failed without a response, e.g. it could
+    /// not connect or timed out.
+    Network(reqwest::Error),
+    /// The server answered with an error status.
+    Status {
+        /// the status code
+        code: reqwest::StatusCode,
+        /// the error reported for it
+        source: reqwest::Error,
+    },
+    /// The server answered `416 Range Not Satisfiable`, the range requested
+    /// starts past the end of the file.
+    RangeNotSatisfiable {
+        /// the error reported for it
+        source: reqwest::Error,
+    },
+    /// The remote file no longer matches the version that was opened.
+    FileChanged {
+        /// etag observed when the file was opened
+        expected: Option<String>,
+        /// etag reported by the server now
+        actual: Option<String>,
+    },
+    /// A response without a known length went past the configured cap.
+    BodyTooLarge {
+        /// the configured maximum body size in bytes
+        limit: u64,
+    },
+    /// Serving the request needs a new connection, but the configured limit
+    /// has been reached.
+    TooManyConnections {
+        /// the configured maximum number of connections
+        limit: u64,
+    },
+    /// The algorithm or key length given for SSE-C is not supported.
+    InvalidSseCustomerKey {
+        /// the requested algorithm
+        algorithm: String,
+        /// length of the given key in bytes
+        key_len: usize,
+    },
+    /// A field in the response trailer does not match the body received.
+    TrailerMismatch {
+        /// name of the trailer field
+        name: String,
+        /// value sent in the trailer
+        expected: String,
+        /// value computed from the body
+        actual: String,
+    },
+    /// A body doesn't match the digest the server advertised for it, see
+    /// [`HttpFileBuilder::with_checksum_verification`](crate::HttpFileBuilder::with_checksum_verification).
+    ChecksumMismatch {
+        /// the header carrying the digest, `Digest` or `Content-MD5`
+        header: String,
+        /// the advertised digest, base64
+        expected: String,
+        /// digest of the body received, base64
+        actual: String,
+    },
+    /// The server answered `304 Not Modified` to a request that wasn't
+    /// conditional, typically a misbehaving cache or proxy.
+    UnexpectedNotModified,
+    /// The average throughput fell below the configured minimum.
+    TooSlow {
+        /// measured average throughput in bytes per second
+        bytes_per_sec: u64,
+        /// the configured minimum in bytes per second
+        min: u64,
+    },
+    /// The response stopped delivering data for longer than the stall
+    /// timeout, and no retry attempts were left to reconnect.
+    Stalled {
+        /// the configured stall timeout
+        timeout: std::time::Duration,
+    },
+    /// A request wasn't answered within the request timeout, and no retry
+    /// attempts were left to send it again.
+    ResponseTimeout {
+        /// the configured request timeout
+        timeout: std::time::Duration,
+    },
+    /// The deadline set for the file passed, see
+    /// [`HttpFileBuilder::with_deadline`](crate::HttpFileBuilder::with_deadline).
+    Cancelled,
+    /// A range was asked for whose end comes before its start.
+    InvalidRange {
+        /// first byte asked for
+        start: u64,
+        /// last byte asked for, inclusive
+        end: u64,
+    },
+    /// A range response doesn't cover exactly the bytes requested.
+    RangeMismatch {
+        /// the inclusive range requested
+        requested: (u64, u64),
+        /// `Content-Range` of the response, if any
+        content_range: Option<String>,
+        /// number of body bytes received
+        received: u64,
+    },
+    /// The server doesn't serve byte ranges, see
+    /// [`HttpFileBuilder::require_range`](crate::HttpFileBuilder::require_range).
+    RangesNotSupported {
+        /// `Accept-Ranges` reported by the server, if any
+        accept_ranges: Option<String>,
+    },
+    /// A mirror failed over to serves another version of the file, with a
+    /// different etag or length.
+    MirrorMismatch {
+        /// url of the mirror
+        url: String,
+    },
+    /// A range response came back with a `Content-Encoding`, so its bytes
+    /// don't line up with offsets in the file, see
+    /// [`HttpFileBuilder::with_identity_encoding`](crate::HttpFileBuilder::with_identity_encoding).
+    EncodedResponse {
+        /// the `Content-Encoding` of the response
+        encoding: String,
+    },
+    /// A request was redirected more times than allowed, see
+    /// [`HttpFileBuilder::with_max_redirects`](crate::HttpFileBuilder::with_max_redirects).
+    TooManyRedirects {
+        /// the configured maximum number of redirects
+        limit: usize,
+    },
+    /// A request was redirected back to a url it had already been through.
+    RedirectLoop {
+        /// the urls of the cycle, starting and ending with the repeated one
+        cycle: Vec<String>,
+    },
+    /// The leaf hashes given for a Merkle tree don't add up to its root.
+    MerkleRootMismatch,
+    /// A chunk of the file doesn't match its leaf in the Merkle tree.
+    ChunkHashMismatch {
+        /// index of the chunk in the file
+        index: u64,
+    },
+    /// A builder option applied to the default client, such as
+    /// [`with_proxy`](crate::HttpFileBuilder::with_proxy), was combined with a
+    /// client of one's own, see [`HttpFileBuilder::client`](crate::HttpFileBuilder::client).
+    ClientOption {
+        /// name of the builder method
+        option: &'static str,
+    },
+}
+
+impl HttpFileError {
+    /// Status code the server answered with, if the request failed on one,
+    /// e.g. to sign a URL again on `403 Forbidden`.
+    pub fn status(&self) -> Option<reqwest::StatusCode> {
+        match self {
+            Self::Network(e) => e.status(),
+            Self::Status { code, .. } => Some(*code),
+            Self::RangeNotSatisfiable { .. } => Some(reqwest::StatusCode::RANGE_NOT_SATISFIABLE),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for HttpFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Network(e) => write!(f, "request failed: {}", e),
+            Self::Status { code, .. } => write!(f, "request failed with status {}", code),
+            Self::RangeNotSatisfiable { .. } => {
+                write!(f, "requested range starts past the end of the file")
+            }
+            Self::FileChanged { expected, actual } => write!(
+                f,
+                "remote file changed: expected etag {:?}, got {:?}",
+                expected, actual
+            ),
+            Self::BodyTooLarge { limit } => {
+                write!(f, "response body exceeds the limit of {} bytes", limit)
+            }
+            Self::TooManyConnections { limit } => {
+                write!(f, "connection limit of {} reached", limit)
+            }
+            Self::InvalidSseCustomerKey { algorithm, key_len } => write!(
+                f,
+                "unsupported SSE-C key: {} byte key for {:?}",
+                key_len, algorithm
+            ),
+            Self::TrailerMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "trailer {} is {:?}, but the body has {:?}",
+                name, expected, actual
+            ),
+            Self::ChecksumMismatch {
+                header,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} is {:?}, but the body has {:?}",
+                header, expected, actual
+            ),
+            Self::UnexpectedNotModified => {
+                write!(f, "got 304 Not Modified to an unconditional request")
+            }
+            Self::TooSlow { bytes_per_sec, min } => write!(
+                f,
+                "transfer too slow: {} B/s, below the minimum of {} B/s",
+                bytes_per_sec, min
+            ),
+            Self::Stalled { timeout } => {
+                write!(f, "no data received for {:?}", timeout)
+            }
+            Self::ResponseTimeout { timeout } => {
+                write!(f, "no response within {:?}", timeout)
+            }
+            Self::Cancelled => write!(f, "deadline passed"),
+            Self::InvalidRange { start, end } => {
+                write!(f, "invalid range {}-{}, ends before it starts", start, end)
+            }
+            Self::RangeMismatch {
+                requested: (start, end),
+                content_range,
+                received,
+            } => write!(
+                f,
+                "requested bytes {}-{}, got {} bytes with Content-Range {:?}",
+                start, end, received, content_range
+            ),
+            Self::RangesNotSupported { accept_ranges } => write!(
+                f,
+                "server doesn't support range requests (Accept-Ranges: {:?})",
+                accept_ranges
+            ),
+            Self::MirrorMismatch { url } => {
+                write!(f, "mirror {} serves a different version of the file", url)
+            }
+            Self::EncodedResponse { encoding } => write!(
+                f,
+                "range response is {} encoded, its bytes don't match file offsets",
+                encoding
+            ),
+            Self::TooManyRedirects { limit } => {
+                write!(f, "more than {} redirects", limit)
+            }
+            Self::RedirectLoop { cycle } => {
+                write!(f, "redirect loop: {}", cycle.join(" -> "))
+            }
+            Self::MerkleRootMismatch => {
+                write!(f, "leaf hashes don't match the merkle root")
+            }
+            Self::ChunkHashMismatch { index } => {
+                write!(f, "chunk {} doesn't match its merkle leaf", index)
+            }
+            Self::ClientOption { option } => write!(
+                f,
+                "{} can't be applied to a given client, configure that client instead",
+                option
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HttpFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Network(e)
+            | Self::Status { source: e, .. }
+            | Self::RangeNotSatisfiable { source: e } => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for HttpFileError {
+    fn from(e: reqwest::Error) -> Self {
+        if let Some(redirect) = crate::redirect::error(&e) {
+            return redirect;
+        }
+        match e.status() {
+            Some(reqwest::StatusCode::RANGE_NOT_SATISFIABLE) => {
+                Self::RangeNotSatisfiable { source: e }
+            }
+            Some(code) => Self::Status { code, source: e },
+            None => Self::Network(e),
+        }
+    }
+}
+
+impl From<HttpFileError> for std::io::Error {
+    fn from(e: HttpFileError) -> Self {
+        let kind = match e.status() {
+            Some(reqwest::StatusCode::NOT_FOUND) => std::io::ErrorKind::NotFound,
+            Some(reqwest::StatusCode::FORBIDDEN) => std::io::ErrorKind::PermissionDenied,
+            _ if matches!(
+                e,
+                HttpFileError::Stalled { .. }
+                    | HttpFileError::ResponseTimeout { .. }
+                    | HttpFileError::Cancelled
+            ) =>
+            {
+                std::io::ErrorKind::TimedOut
+            }
+            _ if matches!(
+                e,
+                HttpFileError::InvalidRange { .. } | HttpFileError::ClientOption { .. }
+            ) =>
+            {
+                std::io::ErrorKind::InvalidInput
+            }
+            _ => std::io::ErrorKind::Other,
+        };
+        std::io::Error::new(kind, e)
+    }
+}
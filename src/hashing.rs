@@ -0,0 +1,95 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use digest::{Digest, Output};
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// A reader hashing the bytes read through it with `D`, e.g. to check a
+/// download against a published checksum without reading it twice.
+///
+/// The digest is available from [`digest`](Self::digest) once the inner
+/// reader reached EOF. Only the bytes read through this reader are hashed,
+/// so start it at the position the checksum covers from, usually 0.
+///
+/// ```rust no_run
+/// # async fn run(file: remote_file::HttpFile) -> std::io::Result<()> {
+/// use remote_file::HashingReader;
+/// use tokio::io::AsyncReadExt;
+///
+/// let mut reader = HashingReader::<_, sha2::Sha256>::new(file);
+/// let mut buf = vec![];
+/// reader.read_to_end(&mut buf).await?;
+/// let checksum = reader.digest().expect("read to the end");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct HashingReader<R, D: Digest> {
+    inner: R,
+    /// the running hash, until EOF
+    hasher: Option<D>,
+    /// the digest of all bytes read, once at EOF
+    digest: Option<Output<D>>,
+}
+
+impl<R, D: Digest> HashingReader<R, D> {
+    /// Hash what is read from `inner` with a fresh `D`.
+    pub fn new(inner: R) -> Self {
+        Self::with_hasher(inner, D::new())
+    }
+
+    /// Hash what is read from `inner` with `hasher`, carrying on from the
+    /// bytes it was already given.
+    pub fn with_hasher(inner: R, hasher: D) -> Self {
+        Self {
+            inner,
+            hasher: Some(hasher),
+            digest: None,
+        }
+    }
+
+    /// The digest of all bytes read, once the inner reader reached EOF.
+    pub fn digest(&self) -> Option<&Output<D>> {
+        self.digest.as_ref()
+    }
+
+    /// The inner reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// The inner reader and the digest, if reading reached EOF.
+    pub fn into_inner(self) -> (R, Option<Output<D>>) {
+        (self.inner, self.digest)
+    }
+}
+
+impl<R, D> AsyncRead for HashingReader<R, D>
+where
+    R: AsyncRead + Unpin,
+    D: Digest + Unpin,
+    Output<D>: Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = &mut *self;
+        let (before, room) = (buf.filled().len(), buf.remaining());
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        let read = &buf.filled()[before..];
+        if !read.is_empty() {
+            if let Some(hasher) = this.hasher.as_mut() {
+                hasher.update(read);
+            }
+        } else if room > 0
+            && let Some(hasher) = this.hasher.take()
+        {
+            this.digest = Some(hasher.finalize());
+        }
+        Poll::Ready(Ok(()))
+    }
+}
@@ -1,143 +1,2690 @@
 #![warn(missing_docs)]
 #![doc = include_str!("../README.md")]
 
-use futures_util::{FutureExt, StreamExt, future::BoxFuture, stream::BoxStream};
-use std::{num::NonZeroU64, task::ready};
-use tokio::io::{AsyncRead, AsyncSeek};
-
-type RequestFuture = BoxFuture<'static, reqwest::Result<ResponseStream>>;
-type ResponseStream = BoxStream<'static, reqwest::Result<bytes::Bytes>>;
-
-fn new_request(client: &reqwest::Client, url: reqwest::Url, pos: u64) -> RequestFuture {
-    client
-        .get(url)
-        .header(reqwest::header::RANGE, format!("bytes={}-", pos))
-        .send()
-        .map(|resp| match resp {
-            Ok(resp) => match resp.error_for_status() {
-                Ok(resp) => Ok(resp.bytes_stream().boxed()),
-                Err(e) => Err(e),
+use futures_util::{FutureExt, StreamExt, TryStreamExt, future::BoxFuture, stream::BoxStream};
+use std::{task::ready, time::Instant};
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncSeek};
+
+mod allocator;
+mod any_file;
+mod block_cache;
+mod builder;
+mod cache_status;
+mod checksum;
+mod clock;
+mod coalesce;
+mod disk_cache;
+mod disposition;
+mod download;
+mod error;
+mod hashing;
+mod local;
+mod memory;
+mod merkle;
+mod metrics;
+mod multipart;
+mod prefetch;
+mod read_buffer;
+mod redirect;
+mod remote_file;
+mod retry;
+mod slice;
+mod stats;
+mod throttle;
+mod trace;
+mod trailer;
+mod transport;
+mod zip;
+pub use allocator::BufferAllocator;
+pub use any_file::AnyFile;
+pub use builder::{ChunkTransform, HttpFileBuilder, Progress, UrlProvider};
+pub use cache_status::CacheStatus;
+pub use clock::Clock;
+pub use disk_cache::{CachedHttpFile, DiskCache};
+pub use error::HttpFileError;
+pub use hashing::HashingReader;
+pub use local::LocalFile;
+pub use memory::MemoryFile;
+pub use merkle::MerkleTree;
+pub use metrics::Metrics;
+pub use remote_file::RemoteFile;
+pub use retry::{RetryAction, RetryPolicy};
+pub use slice::HttpFileSlice;
+pub use stats::HttpFileStats;
+pub use transport::Transport;
+pub use zip::ZipEntry;
+
+use builder::Options;
+
+type RequestFuture = BoxFuture<'static, reqwest::Result<reqwest::Response>>;
+/// A stream of response body chunks.
+pub type ResponseStream = BoxStream<'static, reqwest::Result<bytes::Bytes>>;
+type DownloadFuture = BoxFuture<'static, reqwest::Result<bytes::Bytes>>;
+type BlockFuture = BoxFuture<'static, std::io::Result<bytes::Bytes>>;
+
+/// Most bytes [`HttpFile::read_rest`] allocates up front, the rest is grown
+/// into as it arrives.
+const READ_REST_PREALLOC: u64 = 1 << 20;
+
+/// Interval over which throughput is measured for the slow stream policy.
+const SLOW_STREAM_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// The block cache the options ask for, if any.
+fn block_cache(options: &Options) -> Option<block_cache::BlockCache> {
+    let (block_size, blocks) = options.block_cache?;
+    Some(block_cache::BlockCache::new(block_size, blocks))
+}
+
+fn new_request(
+    request: reqwest::RequestBuilder,
+    pos: u64,
+    end: Option<u64>,
+    options: &Options,
+) -> RequestFuture {
+    let range = match end {
+        Some(end) => format!("bytes={}-{}", pos, end),
+        None => format!("bytes={}-", pos),
+    };
+    send_range(request, range, options)
+}
+
+/// A `HEAD` for `url` carrying the configured headers.
+fn new_head(
+    client: &reqwest::Client,
+    url: impl reqwest::IntoUrl,
+    options: &Options,
+) -> reqwest::RequestBuilder {
+    let request = client.head(url).headers(options.headers.clone());
+    if options.revalidate {
+        // a caching proxy checks its copy with the origin
+        return request.header(reqwest::header::CACHE_CONTROL, "no-cache");
+    }
+    request
+}
+
+/// Run `fut`, failing with [`HttpFileError::Cancelled`] once `deadline`
+/// passes.
+async fn until_deadline<T, E: From<HttpFileError>>(
+    deadline: Option<Instant>,
+    fut: impl std::future::Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline.into(), fut)
+            .await
+            .map_err(|_| HttpFileError::Cancelled)?,
+        None => fut.await,
+    }
+}
+
+/// Error for a request not answered within the request timeout.
+fn response_timeout(timeout: std::time::Duration) -> HttpFileError {
+    HttpFileError::ResponseTimeout { timeout }
+}
+
+/// Await `request`, failing with [`HttpFileError::ResponseTimeout`] if it
+/// isn't answered within the configured request timeout.
+async fn with_request_timeout(
+    request: RequestFuture,
+    options: &Options,
+) -> Result<reqwest::Result<reqwest::Response>, HttpFileError> {
+    match options.request_timeout {
+        Some(timeout) => tokio::time::timeout(timeout, request)
+            .await
+            .map_err(|_| response_timeout(timeout)),
+        None => Ok(request.await),
+    }
+}
+
+/// Send `request` through the configured transport, if any.
+fn send(request: reqwest::RequestBuilder, options: &Options) -> RequestFuture {
+    let Some(transport) = &options.transport else {
+        return request.send().boxed();
+    };
+    let (client, request) = request.build_split();
+    match request {
+        Ok(request) => transport.0.execute(&client, request),
+        Err(e) => futures_util::future::ready(Err(e)).boxed(),
+    }
+}
+
+/// Send `request` for the given `Range` header value.
+///
+/// When revalidating through a proxy, a `304 Not Modified` to the etag the
+/// request carries confirms the copy of the proxy, which is then asked for
+/// without the validator.
+fn send_range(request: reqwest::RequestBuilder, range: String, options: &Options) -> RequestFuture {
+    let metrics = options.metrics.clone();
+    let start = Instant::now();
+    let request = request.header(reqwest::header::RANGE, range);
+    let sent = match options
+        .revalidate
+        .then(|| unconditional(&request))
+        .flatten()
+    {
+        Some(retry) => {
+            let options = options.clone();
+            let first = send(request, &options);
+            async move {
+                let resp = first.await?;
+                if resp.status() != reqwest::StatusCode::NOT_MODIFIED {
+                    return Ok(resp);
+                }
+                log::debug!("proxy copy of {} is current", resp.url());
+                send(retry, &options).await
+            }
+            .boxed()
+        }
+        None => send(request, options),
+    };
+    sent.map(move |resp| {
+        if let Some(metrics) = metrics {
+            record_request(&*metrics.0, reqwest::Method::GET, start, &resp);
+        }
+        resp
+    })
+    .boxed()
+}
+
+/// `request` without `If-None-Match` and `Cache-Control`, if it has a
+/// validator.
+fn unconditional(request: &reqwest::RequestBuilder) -> Option<reqwest::RequestBuilder> {
+    let (client, request) = request.try_clone()?.build_split();
+    let mut request = request.ok()?;
+    request
+        .headers_mut()
+        .remove(reqwest::header::IF_NONE_MATCH)?;
+    request.headers_mut().remove(reqwest::header::CACHE_CONTROL);
+    Some(reqwest::RequestBuilder::from_parts(client, request))
+}
+
+/// Report a request started at `start` and its outcome.
+fn record_request(
+    metrics: &dyn Metrics,
+    method: reqwest::Method,
+    start: Instant,
+    resp: &reqwest::Result<reqwest::Response>,
+) {
+    let status = resp.as_ref().ok().map(|resp| resp.status());
+    metrics.request(&method, status, start.elapsed());
+}
+
+fn header_string(
+    headers: &reqwest::header::HeaderMap,
+    name: reqwest::header::HeaderName,
+) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+}
+
+/// The `Content-Encoding` of a response, `None` for identity.
+fn content_encoding(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    header_string(headers, reqwest::header::CONTENT_ENCODING)
+        .map(|encoding| encoding.trim().to_ascii_lowercase())
+        .filter(|encoding| !encoding.is_empty() && encoding != "identity")
+}
+
+/// The complete length from a `Content-Range: bytes start-end/total` header.
+///
+/// Values that are not numeric or contradict the range itself are rejected
+/// with a warning rather than trusted, as a wrong length breaks EOF handling.
+fn content_range_total(headers: &reqwest::header::HeaderMap) -> Option<u64> {
+    let value = headers.get(reqwest::header::CONTENT_RANGE)?;
+    let parsed = value.to_str().ok().and_then(|value| {
+        let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+        let (start, end) = range.split_once('-')?;
+        let start = start.trim().parse::<u64>().ok()?;
+        let end = end.trim().parse::<u64>().ok()?;
+        let total = total.trim().parse::<u64>().ok()?;
+        (start <= end && end < total).then_some(total)
+    });
+    if parsed.is_none() && !value.as_bytes().ends_with(b"/*") {
+        log::warn!("ignoring invalid Content-Range: {:?}", value);
+    }
+    parsed
+}
+
+/// The version of the file pinned by [`HttpFile::snapshot`].
+#[derive(Debug, Clone)]
+enum Pinned {
+    /// to be taken from the next response
+    Pending,
+    Etag(String),
+    LastModified(String),
+}
+
+impl Pinned {
+    /// Validator to send as `If-Range`, weak etags are not allowed there.
+    fn if_range(&self) -> Option<&str> {
+        match self {
+            Pinned::Etag(etag) if !etag.starts_with("W/") => Some(etag),
+            Pinned::LastModified(date) => Some(date),
+            _ => None,
+        }
+    }
+}
+
+/// Refill `chunk` from `stream` if it is empty, returns `false` once the stream ends.
+async fn next_nonempty(
+    stream: &mut ResponseStream,
+    chunk: &mut bytes::Bytes,
+) -> reqwest::Result<bool> {
+    while chunk.is_empty() {
+        match stream.next().await {
+            Some(next) => *chunk = next?,
+            None => return Ok(false),
+        }
+    }
+    Ok(true)
+}
+
+/// Metadata of a file, given to [`HttpFile::from_parts`].
+#[cfg(feature = "test-util")]
+#[derive(Debug, Clone, Default)]
+pub struct FileMeta {
+    /// content length of the file in bytes, if known
+    pub content_length: Option<u64>,
+    /// etag of the file
+    pub etag: Option<String>,
+    /// mime type of the file
+    pub mime: Option<String>,
+    /// `Last-Modified` date of the file
+    pub last_modified: Option<String>,
+    /// position the initial stream starts at
+    pub pos: u64,
+}
+
+/// An remote file accessed over HTTP.
+/// Implements `AsyncRead` and `AsyncSeek` traits.
+///
+/// * Supports seeking and reading at arbitrary positions.
+/// * Uses HTTP Range requests to fetch data.
+/// * Handles transient network errors with retries.
+/// * `stream_position()` is cheap, as it is tracked locally.
+///
+/// # Trailers
+///
+/// When a response is read to the end, the following trailer fields are
+/// recognized, any other is ignored:
+/// * `Content-Length`: checked against the body received, and taken as the
+///   end of the file if the length was not known.
+/// * `Content-MD5`: checked against the MD5 of the body, only when announced
+///   in the `Trailer` header of the response.
+/// * `ETag`: taken as the etag of the file if none was known.
+///
+/// A mismatch fails the read with [`HttpFileError::TrailerMismatch`].
+///
+/// # Content length
+///
+/// The length of the file is taken from, in order of precedence:
+/// 1. the total of the `Content-Range` of a range response whose
+///    `Content-Encoding` differs from the one the current length was learned
+///    with, e.g. a `HEAD` reporting `gzip` answered by identity `GET`s. Without
+///    a `Content-Range`, the length becomes unknown and reads go on until the
+///    response ends.
+/// 2. the `Content-Length` of the `HEAD`.
+/// 3. the `Content-Range` total of the first range response, when the `HEAD`
+///    had no length.
+/// 4. the `Content-Length` trailer, or the end of the body, see above.
+///
+/// While the length is unknown, a seek from the end sends a suffix range
+/// request such as `bytes=-100` to learn it from the `Content-Range` total.
+///
+/// # Content encoding
+///
+/// Transparent decompression and random access don't mix: the ranges of a
+/// compressed response are ranges of the compressed bytes. Range requests
+/// therefore ask for the identity encoding, and an encoded response fails the
+/// read with [`HttpFileError::EncodedResponse`], unless turned off with
+/// [`HttpFileBuilder::with_identity_encoding`].
+pub struct HttpFile {
+    client: reqwest::Client,
+
+    // info
+    url: reqwest::Url,
+    content_length: Option<u64>,
+    /// `Content-Encoding` of the response `content_length` was learned from
+    length_encoding: Option<String>,
+    etag: Option<String>,
+    mime: Option<String>,
+    /// `Accept-Ranges` reported by the `HEAD`
+    accept_ranges: Option<String>,
+    last_modified: Option<String>,
+    /// all headers of the `HEAD` response, empty when the file was opened
+    /// without one
+    head_headers: reqwest::header::HeaderMap,
+
+    // inner states
+    pos: u64,
+    request: Option<(u64, RequestFuture)>,
+    response: Option<ResponseStream>,
+    last_chunk: Option<bytes::Bytes>,
+    /// bytes still to be discarded from `response` before `pos`
+    skip: u64,
+    seek: Option<u64>,
+    /// distance back from the end of a seek made while the length is unknown,
+    /// and the suffix range request sent to learn it
+    seek_from_end: Option<(u64, Option<RequestFuture>)>,
+    retry_attempt: u8,
+    /// backoff, or delay asked for by `Retry-After`, before the next request
+    retry_wait: Option<std::pin::Pin<Box<tokio::time::Sleep>>>,
+    /// new url asked of the url provider after the old one was refused
+    url_refresh: Option<BoxFuture<'static, reqwest::Url>>,
+    /// fires once the deadline set in the options passes
+    cancel_at: Option<std::pin::Pin<Box<tokio::time::Sleep>>>,
+    /// deadline for the response to the request in flight, or for the next
+    /// chunk of the open response, with a request or stall timeout
+    deadline: Option<std::pin::Pin<Box<tokio::time::Sleep>>>,
+    connections_opened: u64,
+    /// whole-file download started by the full download fallback
+    download: Option<DownloadFuture>,
+    /// the whole file, once downloaded by the fallback
+    local: Option<bytes::Bytes>,
+    pinned: Option<Pinned>,
+    /// trailer of the open response, filled in once it is read to the end
+    trailer: Option<trailer::TrailerSlot>,
+    /// start of the current throughput measurement and bytes received since
+    throughput: Option<(Instant, u64)>,
+    /// switched to bounded ranges by the slow stream policy
+    downgraded: bool,
+    /// reported by a cache on the last response
+    cache_status: Option<CacheStatus>,
+    /// checks the open response against the Merkle tree, if one is set
+    verifier: Option<merkle::Verifier>,
+    /// checks the open response against the digest advertised, if enabled
+    checksum: Option<checksum::Checksum>,
+    /// recent bytes kept to serve reads and seeks back into them, if enabled
+    read_buffer: Option<read_buffer::ReadBuffer>,
+    /// blocks kept to serve reads at any position, if enabled
+    blocks: Option<block_cache::BlockCache>,
+    /// the block being fetched for the cache, with its index
+    block_fetch: Option<(u64, BlockFuture)>,
+    /// when data first arrived and bytes received since, for the minimum throughput
+    transferred: Option<(Instant, u64)>,
+    /// caps the delivery rate, if set
+    throttle: Option<throttle::Throttle>,
+    /// behind `stats`
+    counters: std::sync::Arc<stats::Counters>,
+    /// of the range request the response comes from
+    span: trace::RequestSpan,
+    /// index of `url` in the mirrors, if any
+    mirror: usize,
+    /// mirrors not yet failed over to since data last arrived
+    failovers_left: usize,
+
+    options: Options,
+}
+
+impl std::fmt::Debug for HttpFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpFile")
+            .field("client", &self.client)
+            .field("url", &self.url)
+            .field("content_length", &self.content_length)
+            .field("etag", &self.etag)
+            .field("pos", &self.pos)
+            .field(
+                "request",
+                &self
+                    .request
+                    .as_ref()
+                    .map(|(pos, _)| format!("request at {}", pos)),
+            )
+            .field("response", &"[response stream]")
+            .field("last_chunk", &self.last_chunk)
+            .field("skip", &self.skip)
+            .field("seek", &self.seek)
+            .field(
+                "seek_from_end",
+                &self.seek_from_end.as_ref().map(|(back, _)| back),
+            )
+            .field(
+                "retry_wait",
+                &self.retry_wait.as_ref().map(|wait| wait.deadline()),
+            )
+            .field("url_refresh", &self.url_refresh.is_some())
+            .field("deadline", &self.deadline.as_ref().map(|d| d.deadline()))
+            .field("cancel_at", &self.cancel_at.as_ref().map(|c| c.deadline()))
+            .field("connections_opened", &self.connections_opened)
+            .field(
+                "download",
+                &self.download.as_ref().map(|_| "[full download]"),
+            )
+            .field("local", &self.local.as_ref().map(|b| b.len()))
+            .field("pinned", &self.pinned)
+            .field("trailer", &self.trailer)
+            .field("checksum", &self.checksum)
+            .field("downgraded", &self.downgraded)
+            .field("cache_status", &self.cache_status)
+            .field(
+                "read_buffer",
+                &self
+                    .read_buffer
+                    .as_ref()
+                    .map(|b| format!("buffered up to {}", b.end())),
+            )
+            .field("blocks", &self.blocks)
+            .field(
+                "block_fetch",
+                &self.block_fetch.as_ref().map(|(index, _)| index),
+            )
+            .field("throttle", &self.throttle)
+            .field("counters", &self.counters)
+            .field("span", &self.span)
+            .field("mirror", &self.mirror)
+            .field("failovers_left", &self.failovers_left)
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+impl HttpFile {
+    /// url of the file
+    pub fn url(&self) -> &reqwest::Url {
+        &self.url
+    }
+    /// content length of the file(in bytes), if present
+    pub fn content_length(&self) -> Option<u64> {
+        self.content_length
+    }
+    /// content length of the file(in bytes), if present, same as
+    /// [`content_length`](Self::content_length)
+    pub fn len(&self) -> Option<u64> {
+        self.content_length
+    }
+    /// whether the file is empty, if its length is known
+    pub fn is_empty(&self) -> Option<bool> {
+        self.content_length.map(|len| len == 0)
+    }
+    /// Current position in the file, as `stream_position` would return it
+    /// once no seek is pending. A seek started but not yet completed is not
+    /// reflected.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+    /// bytes left from the current position to the end, if the length is known
+    pub fn remaining(&self) -> Option<u64> {
+        self.content_length.map(|len| len.saturating_sub(self.pos))
+    }
+    /// etag of the file, if present
+    pub fn etag(&self) -> Option<&str> {
+        self.etag.as_deref()
+    }
+    /// Mime type of the file, if present
+    pub fn mime(&self) -> Option<&str> {
+        self.mime.as_deref()
+    }
+    /// `Last-Modified` date of the file, if reported and valid.
+    pub fn last_modified(&self) -> Option<std::time::SystemTime> {
+        httpdate::parse_http_date(self.last_modified.as_deref()?).ok()
+    }
+    /// `Last-Modified` of the file as the server sent it, if present, e.g. to
+    /// send back in `If-Modified-Since` unchanged.
+    pub fn last_modified_str(&self) -> Option<&str> {
+        self.last_modified.as_deref()
+    }
+    /// File name suggested by the server in the `Content-Disposition` of the
+    /// `HEAD`, or else the last segment of the url path, if any.
+    ///
+    /// Both `filename=` and the percent-encoded `filename*=UTF-8''...` form
+    /// are understood, the latter taking precedence. Any directory part of
+    /// the name is dropped, but the name is otherwise as the server sent it;
+    /// sanitize it further before trusting it for a path.
+    pub fn filename(&self) -> Option<String> {
+        header_string(&self.head_headers, reqwest::header::CONTENT_DISPOSITION)
+            .as_deref()
+            .and_then(disposition::filename)
+            .or_else(|| disposition::url_filename(&self.url))
+    }
+    /// Whether the server advertised byte range support with `Accept-Ranges`
+    /// on the `HEAD`: `Some(false)` for `none` or other units only, `None`
+    /// when the header was absent. Servers often serve ranges without
+    /// advertising them, so `None` doesn't rule random access out.
+    pub fn supports_range(&self) -> Option<bool> {
+        let accept_ranges = self.accept_ranges.as_deref()?;
+        Some(
+            accept_ranges
+                .split(',')
+                .any(|unit| unit.trim().eq_ignore_ascii_case("bytes")),
+        )
+    }
+    /// attempts made again after a failed request before giving up, see
+    /// [`HttpFileBuilder::retries`]
+    pub fn max_retries(&self) -> u8 {
+        self.options.max_retries()
+    }
+    /// Retry a failed request up to `retries` times from now on, see
+    /// [`HttpFileBuilder::retries`].
+    pub fn set_max_retries(&mut self, retries: u8) {
+        self.options.max_retries = Some(retries);
+        self.retry_attempt = retries;
+    }
+    /// farthest a forward seek reads through the open response, see
+    /// [`HttpFileBuilder::with_max_inline_skip`]
+    pub fn max_inline_skip(&self) -> u64 {
+        self.options.max_inline_skip()
+    }
+    /// Read through the open response for forward seeks of up to `bytes`
+    /// from now on, see [`HttpFileBuilder::with_max_inline_skip`].
+    pub fn set_max_inline_skip(&mut self, bytes: u64) {
+        self.options.max_inline_skip = Some(bytes);
+    }
+    /// Cap the rate reads are served at to `bytes_per_sec` from now on, or
+    /// lift the cap with `0`, the default.
+    ///
+    /// Reads wait as needed to keep the sustained rate under the cap, while
+    /// up to a second worth of bytes, or the size set with
+    /// [`set_rate_burst`](Self::set_rate_burst), can be read at once after
+    /// an idle period. Bytes are accounted once delivered, so a read is
+    /// never cut short, and the next one waits out any excess. Bytes taken
+    /// through `AsyncBufRead` or [`chunk_as_buf`](Self::chunk_as_buf) count
+    /// the same as those read.
+    pub fn set_max_rate(&mut self, bytes_per_sec: u64) {
+        self.throttle =
+            (bytes_per_sec > 0).then(|| throttle::Throttle::new(bytes_per_sec, bytes_per_sec));
+    }
+    /// Let up to `bytes` be read at once under the
+    /// [rate cap](Self::set_max_rate), when reads have kept under it.
+    pub fn set_rate_burst(&mut self, bytes: u64) {
+        if let Some(throttle) = self.throttle.as_mut() {
+            throttle.set_burst(bytes);
+        }
+    }
+    /// Ask `provider` for a new url whenever a request is refused with one of
+    /// the [refresh statuses](HttpFileBuilder::with_url_refresh_statuses),
+    /// from now on, see [`HttpFileBuilder::with_url_provider`].
+    pub fn set_url_provider(&mut self, provider: UrlProvider) {
+        self.options.url_provider = Some(builder::Hook(provider));
+    }
+    /// Give up on all reads and seeks once `deadline` passes, or never with
+    /// `None`, see [`HttpFileBuilder::with_deadline`].
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.options.deadline = deadline;
+        self.cancel_at = None;
+    }
+    /// Bound the wait for the response to each range request to `timeout`
+    /// from now on, without a timeout on the shared `reqwest::Client`.
+    ///
+    /// Only the wait for the status and headers is bounded, so long bodies
+    /// can still stream; see [`HttpFileBuilder::with_stall_timeout`] for the
+    /// body. A request not answered in time is dropped and sent again,
+    /// spending one of the [`retries`](HttpFileBuilder::retries), and once none
+    /// are left the read fails with [`HttpFileError::ResponseTimeout`], an
+    /// `ErrorKind::TimedOut` error. Positioned reads fail that way right away.
+    /// A lower timeout set on the client still applies.
+    pub fn set_request_timeout(&mut self, timeout: std::time::Duration) {
+        self.options.request_timeout = Some(timeout);
+    }
+    /// Never request fewer than `bytes` bytes for a positioned read or a
+    /// bounded window, except where the file ends sooner.
+    ///
+    /// [`read_exact_at`](Self::read_exact_at) then fetches at least this
+    /// much, and the bytes past the ones asked for are kept to serve the
+    /// reads that follow, so a run of small sequential reads costs one
+    /// request per `bytes`. Zero, the default, turns the floor off.
+    pub fn set_min_fetch(&mut self, bytes: usize) {
+        self.options.min_fetch = bytes as u64;
+    }
+    /// Read up to `bytes` ahead of the caller in the background, from the
+    /// next response on, or stop with `0`, the default.
+    ///
+    /// The response is then read on a task of its own while the caller
+    /// processes what it was handed, so that the next bytes are there by the
+    /// time it asks for them. The bytes read ahead but not yet read by the
+    /// caller are kept under `bytes`, plus the chunk received last. A seek
+    /// away from the open response drops them along with it, while a short
+    /// hop forward within it reads through them, see
+    /// [`set_max_inline_skip`](Self::set_max_inline_skip). Needs a Tokio
+    /// runtime, as reqwest does anyway.
+    pub fn set_prefetch(&mut self, bytes: u64) {
+        self.options.prefetch = (bytes > 0).then_some(bytes);
+    }
+    /// All headers of the `HEAD` response the file was opened, or last
+    /// [refreshed](Self::refresh), with, such as `x-amz-*` metadata. Empty
+    /// when opened without a `HEAD`, e.g. with [`HttpFileBuilder::skip_head`].
+    pub fn headers(&self) -> &reqwest::header::HeaderMap {
+        &self.head_headers
+    }
+    /// headers sent with every request, see [`HttpFileBuilder::extra_headers`]
+    pub fn extra_headers(&self) -> &reqwest::header::HeaderMap {
+        &self.options.headers
+    }
+    /// Send `headers` with every request from now on, as with
+    /// [`HttpFileBuilder::extra_headers`]. Useful to refresh a session cookie
+    /// or a token that expired.
+    pub fn set_extra_headers(&mut self, headers: reqwest::header::HeaderMap) {
+        self.options.add_headers(headers);
+    }
+    /// number of response streams opened so far to serve reads and seeks
+    pub fn connections_opened(&self) -> u64 {
+        self.connections_opened
+    }
+    /// Requests made, bytes received and read, and retries so far, e.g. to
+    /// see how buffering or coalescing settings change the request count.
+    ///
+    /// Forks keep their own counts.
+    pub fn stats(&self) -> HttpFileStats {
+        self.counters.snapshot(self.connections_opened, self.mirror)
+    }
+    /// Cache status reported on the last response, see [`CacheStatus`].
+    ///
+    /// Updated by the `HEAD` when opening and by every range request since;
+    /// `None` when that response carried no `Cache-Status` or `X-Cache`.
+    pub fn last_cache_status(&self) -> Option<CacheStatus> {
+        self.cache_status
+    }
+}
+
+impl HttpFile {
+    /// Create a new `HttpFile` from a `reqwest::Client` and a file URL.
+    ///
+    /// Arguments:
+    /// * `client`: A `reqwest::Client` instance to make HTTP requests.
+    /// * `url`: The URL of the file to access.
+    ///
+    pub async fn new(client: reqwest::Client, url: &str) -> reqwest::Result<Self> {
+        Self::open(client, url, Options::default()).await
+    }
+
+    /// Open the file served at `url` and each of `mirrors`, failing over
+    /// from one to the next when requests keep failing, see
+    /// [`HttpFileBuilder::with_mirrors`].
+    pub async fn new_with_mirrors(
+        client: reqwest::Client,
+        url: &str,
+        mirrors: &[&str],
+    ) -> Result<Self, HttpFileError> {
+        Self::builder()
+            .client(client)
+            .with_mirrors(mirrors)
+            .build(url)
+            .await
+    }
+
+    /// Create a [`HttpFileBuilder`] to configure the file before opening it.
+    pub fn builder() -> HttpFileBuilder {
+        HttpFileBuilder::default()
+    }
+
+    /// Open the file at the first of the mirrors that answers the `HEAD`.
+    async fn open_mirrors(client: reqwest::Client, options: Options) -> reqwest::Result<Self> {
+        let mut failure = None;
+        for (i, url) in options.mirrors.iter().enumerate() {
+            match Self::open(client.clone(), url.as_str(), options.clone()).await {
+                Ok(file) => return Ok(Self { mirror: i, ..file }),
+                Err(e) => {
+                    log::warn!("failed to open mirror {}: {}", url, e);
+                    failure = Some(e);
+                }
+            }
+        }
+        Err(failure.expect("mirrors are never empty"))
+    }
+
+    async fn open(client: reqwest::Client, url: &str, options: Options) -> reqwest::Result<Self> {
+        log::debug!("HEAD {}", url);
+        let start = Instant::now();
+        let resp = send(new_head(&client, url, &options), &options).await;
+        if let Some(metrics) = options.metrics() {
+            record_request(metrics, reqwest::Method::HEAD, start, &resp);
+        }
+        let resp = resp?.error_for_status()?;
+        let etag = header_string(resp.headers(), reqwest::header::ETAG);
+
+        let content_length = resp
+            .headers()
+            .get(reqwest::header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.parse().ok());
+
+        let mime = header_string(resp.headers(), reqwest::header::CONTENT_TYPE);
+        let accept_ranges = header_string(resp.headers(), reqwest::header::ACCEPT_RANGES);
+        let last_modified = header_string(resp.headers(), reqwest::header::LAST_MODIFIED);
+        let length_encoding = content_encoding(resp.headers());
+
+        let cache_status = cache_status::cache_status(resp.headers());
+        let url = resp.url().clone();
+
+        Ok(Self {
+            content_length,
+            etag,
+            mime,
+            accept_ranges,
+            last_modified,
+            length_encoding,
+            cache_status,
+            head_headers: resp.headers().clone(),
+            ..Self::unopened(client, url, options)
+        })
+    }
+
+    /// A file at position 0 with no metadata and nothing in flight.
+    fn unopened(client: reqwest::Client, url: reqwest::Url, options: Options) -> Self {
+        Self {
+            client,
+            url,
+            content_length: None,
+            length_encoding: None,
+            etag: None,
+            mime: None,
+            accept_ranges: None,
+            last_modified: None,
+            head_headers: Default::default(),
+            pos: 0,
+            request: None,
+            response: None,
+            last_chunk: None,
+            skip: 0,
+            seek: None,
+            seek_from_end: None,
+            retry_attempt: options.max_retries(),
+            retry_wait: None,
+            url_refresh: None,
+            deadline: None,
+            cancel_at: None,
+            connections_opened: 0,
+            download: None,
+            local: None,
+            pinned: None,
+            trailer: None,
+            throughput: None,
+            downgraded: false,
+            cache_status: None,
+            verifier: None,
+            checksum: None,
+            read_buffer: options.buffer_size.map(read_buffer::ReadBuffer::new),
+            blocks: block_cache(&options),
+            block_fetch: None,
+            transferred: None,
+            throttle: None,
+            counters: Default::default(),
+            span: Default::default(),
+            mirror: 0,
+            failovers_left: options.mirrors.len().saturating_sub(1),
+            options,
+        }
+    }
+
+    /// Create a file from metadata known up front, without any request.
+    ///
+    /// Nothing is fetched until the first read, and seeks from the end work
+    /// right away using `content_length`. When `etag` is given, reads are
+    /// pinned to it as with [`snapshot`](Self::snapshot): range requests
+    /// carry `If-Range`, and fail with [`HttpFileError::FileChanged`] if the
+    /// object turns out to be different.
+    pub fn from_known(
+        client: reqwest::Client,
+        url: reqwest::Url,
+        content_length: u64,
+        etag: Option<String>,
+    ) -> Self {
+        Self::known(client, url, content_length, etag, Options::default())
+    }
+
+    fn known(
+        client: reqwest::Client,
+        url: reqwest::Url,
+        content_length: u64,
+        etag: Option<String>,
+        options: Options,
+    ) -> Self {
+        let mut file = Self {
+            content_length: Some(content_length),
+            etag,
+            ..Self::unopened(client, url, options)
+        };
+        if file.etag.is_some() {
+            file.snapshot();
+        }
+        file
+    }
+
+    /// Build a file from its parts, without any network I/O.
+    ///
+    /// The cursor starts at `meta.pos`, and reads are served from
+    /// `initial_stream` until it ends or a seek needs another request, which
+    /// is then made to `url` through `client` as usual. Callers must uphold:
+    /// * `initial_stream` yields the bytes of the file starting exactly at
+    ///   `meta.pos`, and ends at the end of the file or earlier.
+    /// * `meta.content_length`, if given, is the real length of the file, as
+    ///   it decides where EOF is; `0` is taken as unknown.
+    ///
+    /// Meant for deterministic tests of the read/seek state machine and for
+    /// embedding custom transports.
+    #[cfg(feature = "test-util")]
+    pub fn from_parts(
+        client: reqwest::Client,
+        url: reqwest::Url,
+        meta: FileMeta,
+        initial_stream: Option<ResponseStream>,
+    ) -> Self {
+        Self {
+            content_length: meta.content_length,
+            etag: meta.etag,
+            mime: meta.mime,
+            last_modified: meta.last_modified,
+            pos: meta.pos,
+            response: initial_stream,
+            ..Self::unopened(client, url, Options::default())
+        }
+    }
+
+    /// Open an independent cursor over the same file, starting at position 0,
+    /// without any request.
+    ///
+    /// The client, the options and what is known about the file (url,
+    /// length, etag, mime type) are shared, while the read and seek state is
+    /// fresh, so each clone can be moved to its own task. Use
+    /// [`try_fork`](Self::try_fork) to confirm first that the file hasn't
+    /// changed.
+    pub fn try_clone(&self) -> HttpFile {
+        self.fork()
+    }
+
+    /// Open an independent cursor over the same file, starting at position 0.
+    ///
+    /// A `HEAD` request is made first to confirm the server still reports the
+    /// etag seen by `self`; if it differs, [`HttpFileError::FileChanged`] is
+    /// returned. A file opened without an etag cannot be validated and is
+    /// forked as-is.
+    pub async fn try_fork(&self) -> Result<HttpFile, HttpFileError> {
+        let resp = self.head(None).await?.error_for_status()?;
+        let etag = header_string(resp.headers(), reqwest::header::ETAG);
+        if self.etag.is_some() && etag != self.etag {
+            return Err(HttpFileError::FileChanged {
+                expected: self.etag.clone(),
+                actual: etag,
+            });
+        }
+        Ok(self.fork())
+    }
+
+    /// Whether the file no longer matches `etag`, checked with a `HEAD`
+    /// carrying `If-None-Match: etag`.
+    ///
+    /// The server answers `304 Not Modified` when the etag still matches,
+    /// and `false` is returned, so a cache keyed by etag can keep serving its
+    /// bytes without downloading them again. Any successful answer means the
+    /// file changed. `etag` is sent as given, quotes included, as
+    /// [`etag`](Self::etag) returns it.
+    pub async fn is_modified_since(&self, etag: &str) -> reqwest::Result<bool> {
+        let resp = self.head(Some(etag)).await?;
+        if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(false);
+        }
+        resp.error_for_status()?;
+        Ok(true)
+    }
+
+    /// Send a `HEAD` to the file url carrying the configured headers, and
+    /// `If-None-Match: etag` if given.
+    async fn head(&self, if_none_match: Option<&str>) -> reqwest::Result<reqwest::Response> {
+        log::debug!("HEAD {}", self.url);
+        let start = Instant::now();
+        let mut request = new_head(&self.client, self.url.clone(), &self.options);
+        if let Some(etag) = if_none_match {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        let resp = send(request, &self.options).await;
+        if let Some(metrics) = self.options.metrics() {
+            record_request(metrics, reqwest::Method::HEAD, start, &resp);
+        }
+        resp
+    }
+
+    /// Send a new `HEAD` and update the length, etag, mime type and
+    /// modification date to what the server reports now.
+    ///
+    /// The position is kept. If the length or the etag changed, the open
+    /// response and any data buffered from the old version are dropped, and
+    /// the next read fetches from the current position again, finding EOF
+    /// there if the file shrank below it. A [`snapshot`](Self::snapshot)
+    /// stays pinned to the version it was taken on.
+    pub async fn refresh(&mut self) -> reqwest::Result<()> {
+        let fresh =
+            Self::open(self.client.clone(), self.url.as_str(), self.options.clone()).await?;
+        let changed = fresh.content_length != self.content_length || fresh.etag != self.etag;
+        self.content_length = fresh.content_length;
+        self.etag = fresh.etag;
+        self.mime = fresh.mime;
+        self.accept_ranges = fresh.accept_ranges;
+        self.last_modified = fresh.last_modified;
+        self.head_headers = fresh.head_headers;
+        self.length_encoding = fresh.length_encoding;
+        self.cache_status = fresh.cache_status;
+        if changed {
+            self.pause();
+            self.local = None;
+            self.read_buffer = self.options.buffer_size.map(read_buffer::ReadBuffer::new);
+            self.blocks = block_cache(&self.options);
+            self.block_fetch = None;
+        }
+        Ok(())
+    }
+
+    /// Fail unless the server serves byte ranges, going by `Accept-Ranges`
+    /// or, without it, by whether a one byte range gets a `206`.
+    async fn check_range_support(&self) -> Result<(), HttpFileError> {
+        let supported = match self.supports_range() {
+            Some(supported) => supported,
+            // nothing to seek in
+            None if self.content_length == Some(0) => true,
+            None => {
+                log::debug!(bytes_from = 0, bytes_to = 0 ; "GET {}", self.url);
+                let range = "bytes=0-0".to_string();
+                let resp = send_range(self.get(), range, &self.options).await;
+                resp?.status() == reqwest::StatusCode::PARTIAL_CONTENT
+            }
+        };
+        if !supported {
+            return Err(HttpFileError::RangesNotSupported {
+                accept_ranges: self.accept_ranges.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Pin all further reads to the version of the file seen so far.
+    ///
+    /// From now on every range request carries `If-Range` with the file's
+    /// etag, or its `Last-Modified` date when there is no etag, and every
+    /// response is compared against it. Reads fail with
+    /// [`HttpFileError::FileChanged`] as soon as the server serves a different
+    /// version. If neither is known yet, the version reported by the next
+    /// response is pinned. Responses carrying no validator at all can't be
+    /// compared and are accepted.
+    pub fn snapshot(&mut self) {
+        self.pinned = Some(if let Some(etag) = &self.etag {
+            Pinned::Etag(etag.clone())
+        } else if let Some(last_modified) = &self.last_modified {
+            Pinned::LastModified(last_modified.clone())
+        } else {
+            Pinned::Pending
+        });
+    }
+
+    /// Release the connection, keeping the current position.
+    ///
+    /// The open response and any request in flight are dropped, so a long
+    /// stall downstream doesn't hold a connection open. Data already fetched
+    /// but not read yet is discarded and fetched again later, unless it was
+    /// taken out first through [`chunk_as_buf`](Self::chunk_as_buf).
+    pub fn pause(&mut self) {
+        self.request = None;
+        self.response = None;
+        self.last_chunk = None;
+        self.skip = 0;
+        self.download = None;
+        self.trailer = None;
+        self.retry_wait = None;
+        self.deadline = None;
+        self.verifier = None;
+        self.checksum = None;
+    }
+
+    /// Counterpart of [`pause`](Self::pause): open a new response from the
+    /// current position, so the next read finds the stream going again.
+    ///
+    /// The first chunk received is kept for that read. Nothing is fetched
+    /// while a response is still open, or when the data at the position is
+    /// already at hand.
+    pub async fn resume(&mut self) -> std::io::Result<()> {
+        if self.response.is_some() || self.last_chunk.is_some() {
+            return Ok(());
+        }
+        // reading into no room at all leaves the next chunk buffered whole
+        let mut empty = tokio::io::ReadBuf::new(&mut []);
+        std::future::poll_fn(|cx| std::pin::Pin::new(&mut *self).poll_read(cx, &mut empty)).await
+    }
+
+    /// Read exactly `buf.len()` bytes starting at `pos`.
+    ///
+    /// Unlike a `seek` followed by `read_exact`, this issues a single bounded
+    /// range request for exactly the bytes needed. Afterwards the cursor is at
+    /// `pos + buf.len()`, as if the bytes had been read normally.
+    ///
+    /// With [request coalescing](HttpFileBuilder::with_request_coalescing),
+    /// concurrent calls for the same range on this file and its forks share
+    /// a single request.
+    pub async fn read_exact_at(&mut self, pos: u64, buf: &mut [u8]) -> std::io::Result<()> {
+        if self
+            .read_buffer
+            .as_ref()
+            .is_some_and(|buffer| buffer.read_at(pos, buf))
+        {
+            let end = pos + buf.len() as u64;
+            if !self.seek_in_buffer(end) {
+                self.settle_at(end);
+            }
+            return Ok(());
+        }
+        if pos == self.pos
+            && let Some(chunk) = &self.last_chunk
+            && chunk.len() >= buf.len()
+        {
+            buf.copy_from_slice(&chunk[..buf.len()]);
+            let rest = chunk.slice(buf.len()..);
+            self.last_chunk = (!rest.is_empty()).then_some(rest);
+            self.pos += buf.len() as u64;
+            return Ok(());
+        }
+        if let Some(in_flight) = self.options.in_flight.clone()
+            && self.local.is_none()
+            && !buf.is_empty()
+        {
+            let deadline = self.options.deadline;
+            let bytes = until_deadline(deadline, in_flight.read(self, pos, buf.len())).await?;
+            buf.copy_from_slice(&bytes);
+            self.settle_at(pos + buf.len() as u64);
+            return Ok(());
+        }
+        until_deadline(self.options.deadline, self.fetch_exact_at(pos, buf)).await
+    }
+
+    /// Read up to `buf.len()` bytes at `offset` without moving the cursor,
+    /// returning how many were read, fewer only at the end of the file.
+    ///
+    /// The bytes are fetched with a range request of their own, leaving the
+    /// position and the open response of this file untouched, so reads at
+    /// several offsets can run concurrently on a shared reference.
+    pub async fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        if let Some(local) = &self.local {
+            let start = offset.min(local.len() as u64) as usize;
+            let n = buf.len().min(local.len() - start);
+            buf[..n].copy_from_slice(&local[start..start + n]);
+            return Ok(n);
+        }
+        let mut file = self.fork();
+        let Some(len) = self.content_length else {
+            // no length to bound the request by, read until the body ends
+            tokio::io::AsyncSeekExt::seek(&mut file, std::io::SeekFrom::Start(offset)).await?;
+            let mut filled = 0;
+            while filled < buf.len() {
+                match tokio::io::AsyncReadExt::read(&mut file, &mut buf[filled..]).await? {
+                    0 => break,
+                    n => filled += n,
+                }
+            }
+            return Ok(filled);
+        };
+        let available = len.saturating_sub(offset).try_into().unwrap_or(usize::MAX);
+        let n = buf.len().min(available);
+        if n > 0 {
+            file.read_exact_at(offset, &mut buf[..n]).await?;
+        }
+        Ok(n)
+    }
+
+    /// Fetch the bytes from `start` to `end` inclusive with a single request,
+    /// without moving the cursor.
+    ///
+    /// The response must be a `206 Partial Content` whose `Content-Range`
+    /// and body cover exactly the range asked for, otherwise
+    /// [`HttpFileError::RangeMismatch`] is returned, e.g. for a range that
+    /// runs past the end of the file. An `end` less than `start` fails with
+    /// [`HttpFileError::InvalidRange`] without any request.
+    pub async fn read_range(&self, start: u64, end: u64) -> Result<bytes::Bytes, HttpFileError> {
+        if end < start {
+            return Err(HttpFileError::InvalidRange { start, end });
+        }
+        until_deadline(self.options.deadline, self.fetch_range(start, end)).await
+    }
+
+    /// [`read_range`](Self::read_range) without the deadline.
+    async fn fetch_range(&self, start: u64, end: u64) -> Result<bytes::Bytes, HttpFileError> {
+        let mismatch = |content_range, received| HttpFileError::RangeMismatch {
+            requested: (start, end),
+            content_range,
+            received,
+        };
+        if let Some(local) = &self.local {
+            let range = (start as usize).min(local.len())
+                ..(end as usize).saturating_add(1).min(local.len());
+            if range.len() as u64 != end - start + 1 {
+                return Err(mismatch(None, range.len() as u64));
+            }
+            return Ok(local.slice(range));
+        }
+        log::debug!(bytes_from = start, bytes_to = end ; "GET {}", self.url);
+        let mut span = trace::RequestSpan::new(&self.url, start, 1);
+        let resp = self
+            .send_checked(format!("bytes={}-{}", start, end), &mut span)
+            .await?;
+        let content_range = header_string(resp.headers(), reqwest::header::CONTENT_RANGE);
+        let expected = format!("bytes {}-{}/", start, end);
+        let partial = resp.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+        if !partial
+            || !content_range
+                .as_ref()
+                .is_some_and(|c| c.starts_with(&expected))
+        {
+            return Err(mismatch(content_range, 0));
+        }
+        let bytes = resp.bytes().await?;
+        span.delivered(bytes.len());
+        if bytes.len() as u64 != end - start + 1 {
+            return Err(mismatch(content_range, bytes.len() as u64));
+        }
+        Ok(bytes)
+    }
+
+    /// Send a request for the `Range` header value `range` on a fork, within
+    /// the connection budget, and check the response as any read does:
+    /// answered within the request timeout, successful, and from the version
+    /// of the file being read.
+    async fn send_checked(
+        &self,
+        range: String,
+        span: &mut trace::RequestSpan,
+    ) -> Result<reqwest::Response, HttpFileError> {
+        self.check_connection_budget()?;
+        let mut file = self.fork();
+        file.counters = self.counters.clone();
+        let resp =
+            with_request_timeout(send_range(file.get(), range, &self.options), &self.options)
+                .await??;
+        span.status(resp.status());
+        let resp = resp.error_for_status()?;
+        file.check_not_modified(&resp)?;
+        file.check_identity(&resp)?;
+        file.check_version(&resp)?;
+        Ok(resp)
+    }
+
+    /// Fetch the last `n` bytes of the file, or all of it if shorter, with a
+    /// single request, without moving the cursor.
+    ///
+    /// With the length known, the absolute range is requested. Otherwise a
+    /// suffix range such as `bytes=-22` is sent, and the length is learned
+    /// from the `Content-Range` total of the response, as for a seek from
+    /// the end, which is what `&mut self` is taken for. Handy for trailers
+    /// such as the end of a ZIP archive.
+    ///
+    /// The response is checked as for [`read_range`](Self::read_range), so a
+    /// pinned file that changed fails with [`HttpFileError::FileChanged`]
+    /// rather than handing out the end of another version.
+    pub async fn read_suffix(&mut self, n: u64) -> Result<bytes::Bytes, HttpFileError> {
+        if let Some(local) = &self.local {
+            let n = usize::try_from(n).unwrap_or(usize::MAX);
+            return Ok(local.slice(local.len().saturating_sub(n)..));
+        }
+        if n == 0 || self.content_length == Some(0) {
+            return Ok(bytes::Bytes::new());
+        }
+        until_deadline(self.options.deadline, self.fetch_suffix(n)).await
+    }
+
+    /// [`read_suffix`](Self::read_suffix) without the deadline.
+    async fn fetch_suffix(&mut self, n: u64) -> Result<bytes::Bytes, HttpFileError> {
+        let (start, range) = match self.content_length {
+            Some(len) => {
+                let start = len.saturating_sub(n);
+                (start, format!("bytes={}-{}", start, len - 1))
+            }
+            None => (0, format!("bytes=-{}", n)),
+        };
+        log::debug!(bytes_back = n ; "GET {}", self.url);
+        let mut span = trace::RequestSpan::new(&self.url, start, 1);
+        let resp = self.send_checked(range, &mut span).await?;
+        self.learn_length(&resp);
+        let partial = resp.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+        let body = resp.bytes().await?;
+        span.delivered(body.len());
+        if partial {
+            return Ok(body);
+        }
+        // the range was ignored, and the whole file sent
+        if self.content_length.is_none() {
+            self.content_length = Some(body.len() as u64);
+        }
+        let n = usize::try_from(n).unwrap_or(usize::MAX);
+        Ok(body.slice(body.len().saturating_sub(n)..))
+    }
+
+    /// Fetch the bytes of each of `ranges`, with a single multi-range
+    /// request, without moving the cursor.
+    ///
+    /// The server answers such a request with a `multipart/byteranges` body,
+    /// which is split into one buffer per range, in the order of `ranges`.
+    /// Parts may come back merged or reordered. Should the server answer
+    /// with the whole file, every range is taken out of it. Ranges not
+    /// covered by the parts sent are then fetched with a request each.
+    /// Ranges running past the end of the file come back short, and those
+    /// starting at or after it come back empty, without a request if the
+    /// length is known.
+    ///
+    /// Every response is checked as for [`read_range`](Self::read_range), so
+    /// parts of a pinned file that changed in between fail with
+    /// [`HttpFileError::FileChanged`] rather than mixing versions.
+    pub async fn read_ranges(
+        &self,
+        ranges: &[std::ops::Range<u64>],
+    ) -> Result<Vec<bytes::Bytes>, HttpFileError> {
+        if let Some(local) = &self.local {
+            return Ok(ranges
+                .iter()
+                .map(|range| multipart::slice_whole(local, range))
+                .collect());
+        }
+        until_deadline(self.options.deadline, self.fetch_ranges(ranges)).await
+    }
+
+    /// [`read_ranges`](Self::read_ranges) without the deadline.
+    async fn fetch_ranges(
+        &self,
+        ranges: &[std::ops::Range<u64>],
+    ) -> Result<Vec<bytes::Bytes>, HttpFileError> {
+        let ranges: Vec<_> = ranges
+            .iter()
+            .map(|range| match self.content_length {
+                Some(len) => range.start.min(len)..range.end.min(len),
+                None => range.clone(),
+            })
+            .collect();
+        let wanted: Vec<_> = ranges.iter().filter(|r| !r.is_empty()).cloned().collect();
+        let answer = match wanted.len() {
+            0 | 1 => multipart::Answer::Parts(vec![]),
+            _ => self.fetch_multipart(&wanted).await?,
+        };
+        let mut buffers = Vec::with_capacity(ranges.len());
+        for range in &ranges {
+            let bytes = match &answer {
+                _ if range.is_empty() => bytes::Bytes::new(),
+                multipart::Answer::Whole(whole) => multipart::slice_whole(whole, range),
+                multipart::Answer::Parts(parts) => match multipart::find_range(parts, range) {
+                    Some(bytes) => bytes,
+                    None => self.fetch_part(range).await?,
+                },
+            };
+            buffers.push(bytes);
+        }
+        Ok(buffers)
+    }
+
+    /// Request all of `ranges` at once, returning the parts of the response,
+    /// or the whole file if the server ignored the ranges.
+    async fn fetch_multipart(
+        &self,
+        ranges: &[std::ops::Range<u64>],
+    ) -> Result<multipart::Answer, HttpFileError> {
+        let spec: Vec<_> = ranges
+            .iter()
+            .map(|range| format!("{}-{}", range.start, range.end - 1))
+            .collect();
+        log::debug!(ranges = ranges.len() ; "GET {}", self.url);
+        let range = format!("bytes={}", spec.join(","));
+        let mut span = trace::RequestSpan::new(&self.url, ranges[0].start, 1);
+        let resp = match self.send_checked(range, &mut span).await {
+            Ok(resp) => resp,
+            // none of them within a file of unknown length, left to `fetch_part`
+            Err(e) if e.status() == Some(reqwest::StatusCode::RANGE_NOT_SATISFIABLE) => {
+                return Ok(multipart::Answer::Parts(vec![]));
+            }
+            Err(e) => return Err(e),
+        };
+        let partial = resp.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+        let boundary = header_string(resp.headers(), reqwest::header::CONTENT_TYPE)
+            .and_then(|content_type| multipart::boundary(&content_type));
+        let single = header_string(resp.headers(), reqwest::header::CONTENT_RANGE)
+            .and_then(|value| multipart::content_range(&value));
+        let body = resp.bytes().await?;
+        span.delivered(body.len());
+        if !partial {
+            log::debug!("{} ignored the multi-range request", self.url);
+            return Ok(multipart::Answer::Whole(body));
+        }
+        let parts = match (boundary, single) {
+            (Some(boundary), _) => multipart::parse(&body, &boundary).unwrap_or_else(|| {
+                log::warn!(
+                    "ignoring malformed multipart/byteranges body from {}",
+                    self.url
+                );
+                vec![]
+            }),
+            (None, Some((start, _))) => vec![(start, body)],
+            (None, None) => vec![],
+        };
+        Ok(multipart::Answer::Parts(parts))
+    }
+
+    /// Fetch `range` with a request of its own, taking it out of the whole
+    /// file should the server ignore the range.
+    async fn fetch_part(
+        &self,
+        range: &std::ops::Range<u64>,
+    ) -> Result<bytes::Bytes, HttpFileError> {
+        log::debug!(bytes_from = range.start, bytes_to = range.end - 1 ; "GET {}", self.url);
+        let mut span = trace::RequestSpan::new(&self.url, range.start, 1);
+        let resp = match self
+            .send_checked(
+                format!("bytes={}-{}", range.start, range.end - 1),
+                &mut span,
+            )
+            .await
+        {
+            Ok(resp) => resp,
+            // past the end of a file of unknown length
+            Err(e) if e.status() == Some(reqwest::StatusCode::RANGE_NOT_SATISFIABLE) => {
+                return Ok(bytes::Bytes::new());
+            }
+            Err(e) => return Err(e),
+        };
+        let whole = resp.status() == reqwest::StatusCode::OK;
+        let body = resp.bytes().await?;
+        span.delivered(body.len());
+        if !whole {
+            return Ok(body);
+        }
+        Ok(multipart::slice_whole(&body, range))
+    }
+
+    /// Stream the bytes in `range` with a request of its own, without moving
+    /// the cursor.
+    ///
+    /// `start..` asks for `bytes=start-`, and bounded ranges for
+    /// `bytes=start-end` with `end` inclusive. The request is only sent once
+    /// the stream is polled. Should the server ignore the range and answer
+    /// with the whole file, the bytes outside of `range` are dropped.
+    pub fn byte_stream(&self, range: impl std::ops::RangeBounds<u64>) -> ResponseStream {
+        use std::ops::Bound;
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => Some(end),
+            Bound::Excluded(&end) => match end.checked_sub(1) {
+                Some(end) => Some(end),
+                None => return futures_util::stream::empty().boxed(),
             },
-            Err(e) => Err(e),
+            Bound::Unbounded => None,
+        };
+        if end.is_some_and(|end| end < start) {
+            return futures_util::stream::empty().boxed();
+        }
+        log::debug!(bytes_from = start ; "GET {}", self.url);
+        let request = new_request(self.get(), start, end, &self.options);
+        futures_util::stream::once(request)
+            .map(move |resp| {
+                let resp = resp?.error_for_status()?;
+                let skip = if resp.status() == reqwest::StatusCode::OK {
+                    start
+                } else {
+                    0
+                };
+                let left = end.map(|end| end - start + 1);
+                let body = resp
+                    .bytes_stream()
+                    .scan((skip, left), |(skip, left), chunk| {
+                        if *left == Some(0) {
+                            return futures_util::future::ready(None);
+                        }
+                        let chunk = chunk.map(|chunk| {
+                            let skipped = (*skip).min(chunk.len() as u64);
+                            *skip -= skipped;
+                            let mut chunk = chunk.slice(skipped as usize..);
+                            if let Some(left) = left {
+                                chunk.truncate((*left).min(chunk.len() as u64) as usize);
+                                *left -= chunk.len() as u64;
+                            }
+                            chunk
+                        });
+                        futures_util::future::ready(Some(chunk))
+                    })
+                    .try_filter(|chunk| futures_util::future::ready(!chunk.is_empty()));
+                Ok(body)
+            })
+            .try_flatten()
+            .boxed()
+    }
+
+    /// [`read_exact_at`](Self::read_exact_at) with a request of its own.
+    async fn fetch_exact_at(&mut self, pos: u64, buf: &mut [u8]) -> std::io::Result<()> {
+        let end = pos.checked_add(buf.len() as u64).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid read range")
+        })?;
+        if !self.options.distrust_content_length && self.content_length.is_some_and(|len| end > len)
+        {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+
+        if let Some(local) = &self.local {
+            let range = (pos as usize).min(local.len())..(end as usize).min(local.len());
+            if range.len() < buf.len() {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+            buf.copy_from_slice(&local[range]);
+        } else if !buf.is_empty() {
+            self.check_connection_budget()?;
+            let fetch_end = self.fetch_end(pos, end);
+            log::debug!(bytes_from = pos, bytes_to = fetch_end - 1 ; "GET {}", self.url);
+            let (start, range_end) = self.request_range(pos, Some(fetch_end - 1));
+            let mut span = trace::RequestSpan::new(&self.url, start, 1);
+            let resp = with_request_timeout(
+                new_request(self.get(), start, range_end, &self.options),
+                &self.options,
+            )
+            .await?
+            .inspect(|resp| span.status(resp.status()))
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| std::io::Error::from(HttpFileError::from(e)))?;
+            self.connections_opened += 1;
+            self.cache_status = cache_status::cache_status(resp.headers());
+            self.check_not_modified(&resp)?;
+            self.check_mirror(&resp)?;
+            self.check_identity(&resp)?;
+            self.check_version(&resp)?;
+            self.check_encoding(&resp);
+            self.learn_length(&resp);
+            if self.range_ignored(&resp) {
+                let bytes = resp
+                    .bytes()
+                    .await
+                    .map_err(|e| std::io::Error::from(HttpFileError::from(e)))?;
+                self.receive_whole(bytes)?;
+                return Box::pin(self.fetch_exact_at(pos, buf)).await;
+            }
+            let start = self.body_start(&resp, start);
+            let mut verifier = self.verifier_from(start);
+            let mut skip = (pos - start) as usize;
+            let mut stream = resp.bytes_stream();
+            let mut filled = 0;
+            // read ahead up to `fetch_end`, kept for the reads that follow
+            let ahead_len = (fetch_end - end) as usize;
+            let mut ahead = self.options.buffer(ahead_len);
+            while filled < buf.len() || ahead.len() < ahead_len {
+                let next = match self.options.stall_timeout {
+                    Some(timeout) => tokio::time::timeout(timeout, stream.next())
+                        .await
+                        .map_err(|_| HttpFileError::Stalled { timeout })?,
+                    None => stream.next().await,
+                };
+                let chunk = match next {
+                    Some(chunk) => {
+                        let chunk =
+                            chunk.map_err(|e| std::io::Error::from(HttpFileError::from(e)))?;
+                        let chunk = self.receive_chunk(chunk)?;
+                        match verifier.as_mut() {
+                            Some(verifier) => verifier.push(&chunk)?,
+                            None => chunk,
+                        }
+                    }
+                    // a last short chunk is only verified once the response ends
+                    None => match verifier.take() {
+                        Some(mut verifier) => verifier.finish()?,
+                        None if filled < buf.len() => {
+                            return Err(std::io::ErrorKind::UnexpectedEof.into());
+                        }
+                        None => break,
+                    },
+                };
+                let skipped = skip.min(chunk.len());
+                skip -= skipped;
+                let chunk = &chunk[skipped..];
+                let size = chunk.len().min(buf.len() - filled);
+                buf[filled..filled + size].copy_from_slice(&chunk[..size]);
+                filled += size;
+                let more = (chunk.len() - size).min(ahead_len - ahead.len());
+                ahead.extend_from_slice(&chunk[size..size + more]);
+            }
+            span.delivered(filled + ahead.len());
+            self.grow_length(end + ahead.len() as u64);
+            self.settle_at(end);
+            if !ahead.is_empty() {
+                let ahead = ahead.freeze();
+                if let Some(buffer) = self.read_buffer.as_mut() {
+                    let mut kept = self.options.buffer(buf.len());
+                    kept.extend_from_slice(buf);
+                    buffer.push(pos, kept.freeze());
+                    buffer.push(end, ahead.clone());
+                }
+                self.last_chunk = Some(ahead);
+            }
+            return Ok(());
+        }
+
+        self.grow_length(end);
+        self.settle_at(end);
+        Ok(())
+    }
+
+    /// End of the range to fetch for a positioned read up to `end`, widened to
+    /// the minimum fetch size but not past the end of the file.
+    fn fetch_end(&self, pos: u64, end: u64) -> u64 {
+        let floor = pos.saturating_add(self.options.min_fetch);
+        match self.content_length {
+            Some(len) => end.max(floor.min(len)),
+            None => end.max(floor),
+        }
+    }
+
+    /// Correct the length upward to `end` if it is distrusted and data was
+    /// seen up to there.
+    fn grow_length(&mut self, end: u64) {
+        if self.options.distrust_content_length
+            && let Some(len) = self.content_length
+            && end > len
+        {
+            log::warn!("{} is longer than reported: {} > {}", self.url, end, len);
+            self.content_length = Some(end);
+        }
+    }
+
+    /// Put the cursor at `pos` after a positioned read, with nothing open.
+    fn settle_at(&mut self, pos: u64) {
+        self.pos = pos;
+        self.seek = None;
+        self.request = None;
+        self.response = None;
+        self.last_chunk = None;
+        self.skip = 0;
+    }
+
+    /// The data already buffered at the current position, as a [`bytes::Buf`].
+    ///
+    /// Returns `None` when nothing is buffered, e.g. before the first read or
+    /// right after a seek. Consuming from the `Buf` (`advance`, `get_u32`, ...)
+    /// advances the file position accordingly, so parsing can mix `Buf`
+    /// accessors and regular reads. No request is ever made.
+    pub fn chunk_as_buf(&mut self) -> Option<impl bytes::Buf + '_> {
+        if self.last_chunk.as_ref().is_none_or(|c| c.is_empty()) {
+            return None;
+        }
+        Some(ChunkBuf { file: self })
+    }
+
+    /// Read a type-length-value record at the current position.
+    ///
+    /// The type and length fields are `type_bytes` and `len_bytes` wide (at
+    /// most 8 bytes each) and decoded as big or little endian unsigned
+    /// integers. Returns the type and the value, leaving the cursor right
+    /// after the value. Records are read through the open response, so
+    /// consecutive records cost no extra requests.
+    pub async fn read_tlv(
+        &mut self,
+        type_bytes: usize,
+        len_bytes: usize,
+        big_endian: bool,
+    ) -> std::io::Result<(u64, bytes::Bytes)> {
+        use tokio::io::AsyncReadExt;
+
+        if !(1..=8).contains(&type_bytes) || !(1..=8).contains(&len_bytes) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "tlv fields must be 1 to 8 bytes wide",
+            ));
+        }
+        let decode = |bytes: &[u8]| {
+            let mut value = [0u8; 8];
+            if big_endian {
+                value[8 - bytes.len()..].copy_from_slice(bytes);
+                u64::from_be_bytes(value)
+            } else {
+                value[..bytes.len()].copy_from_slice(bytes);
+                u64::from_le_bytes(value)
+            }
+        };
+
+        let mut header = [0u8; 16];
+        let header = &mut header[..type_bytes + len_bytes];
+        self.read_exact(header).await?;
+        let tlv_type = decode(&header[..type_bytes]);
+        let len = decode(&header[type_bytes..]);
+
+        // don't trust `len` for the allocation, the value may be truncated
+        let mut value = Vec::new();
+        (&mut *self).take(len).read_to_end(&mut value).await?;
+        if (value.len() as u64) < len {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok((tlv_type, value.into()))
+    }
+
+    /// Read everything from the current position to the end of the file.
+    ///
+    /// Unlike rewinding and reading to end, this picks up wherever the cursor
+    /// is, e.g. right after a parsed header. The buffer is sized up front
+    /// when the content length is known, up to 1 MiB so a bogus length can't
+    /// exhaust memory before a byte arrives, and the cursor is left at EOF.
+    pub async fn read_rest(&mut self) -> std::io::Result<bytes::Bytes> {
+        use tokio::io::AsyncReadExt;
+
+        let remaining = self
+            .content_length()
+            .map_or(0, |len| len.saturating_sub(self.pos));
+        let mut rest = Vec::with_capacity(remaining.min(READ_REST_PREALLOC) as usize);
+        self.read_to_end(&mut rest).await?;
+        Ok(rest.into())
+    }
+
+    /// Stream the lines of the file from the current position, lazily.
+    ///
+    /// Lines end with `\n` or `\r\n`, which are not included, and a last
+    /// line without a trailing newline is yielded too. Only one line and the
+    /// chunk being read are held in memory at a time, so arbitrarily large
+    /// files can be processed. A line that is not valid UTF-8 yields an
+    /// `ErrorKind::InvalidData` error.
+    pub fn lines_stream(self) -> impl futures_util::Stream<Item = std::io::Result<String>> {
+        use tokio::io::AsyncBufReadExt;
+
+        let lines = self.lines();
+        futures_util::stream::try_unfold(lines, |mut lines| async move {
+            Ok(lines.next_line().await?.map(|line| (line, lines)))
         })
-        .boxed()
-}
+    }
+
+    /// Read text from the current position to the end of the file, decoding
+    /// it according to its byte order mark.
+    ///
+    /// When reading from the start of the file, a UTF-8, UTF-16LE or UTF-16BE
+    /// BOM is detected and stripped, and the text decoded accordingly. Text
+    /// without a BOM, or read from further in the file, is decoded as UTF-8.
+    /// A file shorter than a BOM is simply decoded as UTF-8. Invalid text
+    /// fails with `ErrorKind::InvalidData`. The cursor is left at EOF.
+    pub async fn read_to_string_strip_bom(&mut self) -> std::io::Result<String> {
+        let at_start = self.pos == 0;
+        let bytes = self.read_rest().await?;
+        let invalid = |e: Box<dyn std::error::Error + Send + Sync>| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+        };
+
+        let utf16 = |bytes: &[u8], from: fn([u8; 2]) -> u16| {
+            if !bytes.len().is_multiple_of(2) {
+                return Err(invalid("odd number of bytes in UTF-16 text".into()));
+            }
+            let units = bytes.chunks_exact(2).map(|c| from([c[0], c[1]]));
+            char::decode_utf16(units)
+                .collect::<Result<String, _>>()
+                .map_err(|e| invalid(e.into()))
+        };
+        match &bytes[..] {
+            [0xEF, 0xBB, 0xBF, rest @ ..] if at_start => {
+                String::from_utf8(rest.to_vec()).map_err(|e| invalid(e.into()))
+            }
+            [0xFF, 0xFE, rest @ ..] if at_start => utf16(rest, u16::from_le_bytes),
+            [0xFE, 0xFF, rest @ ..] if at_start => utf16(rest, u16::from_be_bytes),
+            bytes => String::from_utf8(bytes.to_vec()).map_err(|e| invalid(e.into())),
+        }
+    }
+
+    /// Check whether `self` and `other` have identical content.
+    ///
+    /// Cheap checks come first: files with different known lengths are
+    /// unequal, and files with the same strong etag are equal, without any
+    /// body being transferred. Otherwise both files are streamed from the
+    /// start and compared, stopping at the first difference.
+    ///
+    /// Differing etags are not taken as proof of different content, as etags
+    /// are opaque and servers derive them differently (e.g. from mtime).
+    pub async fn content_equals(&self, other: &HttpFile) -> reqwest::Result<bool> {
+        if let (Some(a), Some(b)) = (self.content_length, other.content_length)
+            && a != b
+        {
+            return Ok(false);
+        }
+        if let (Some(a), Some(b)) = (&self.etag, &other.etag)
+            && !a.starts_with("W/")
+            && a == b
+        {
+            return Ok(true);
+        }
+
+        log::debug!("GET {} and {} to compare", self.url, other.url);
+        let (left, right) = futures_util::try_join!(
+            new_request(self.get(), 0, self.options.open_range_end, &self.options)
+                .map(|resp| resp?.error_for_status()),
+            new_request(other.get(), 0, other.options.open_range_end, &other.options)
+                .map(|resp| resp?.error_for_status())
+        )?;
+        let mut left = left.bytes_stream().boxed();
+        let mut right = right.bytes_stream().boxed();
+        let mut left_chunk = bytes::Bytes::new();
+        let mut right_chunk = bytes::Bytes::new();
+        loop {
+            let left_more = next_nonempty(&mut left, &mut left_chunk).await?;
+            let right_more = next_nonempty(&mut right, &mut right_chunk).await?;
+            if !left_more || !right_more {
+                return Ok(left_more == right_more);
+            }
+            let size = left_chunk.len().min(right_chunk.len());
+            if left_chunk[..size] != right_chunk[..size] {
+                return Ok(false);
+            }
+            left_chunk = left_chunk.slice(size..);
+            right_chunk = right_chunk.slice(size..);
+        }
+    }
+
+    /// Same metadata, fresh read/seek state.
+    fn fork(&self) -> Self {
+        Self {
+            content_length: self.content_length,
+            length_encoding: self.length_encoding.clone(),
+            etag: self.etag.clone(),
+            mime: self.mime.clone(),
+            accept_ranges: self.accept_ranges.clone(),
+            last_modified: self.last_modified.clone(),
+            head_headers: self.head_headers.clone(),
+            pinned: self.pinned.clone(),
+            mirror: self.mirror,
+            ..Self::unopened(self.client.clone(), self.url.clone(), self.options.clone())
+        }
+    }
+
+    /// A `GET` to the file url carrying the configured headers.
+    fn get(&self) -> reqwest::RequestBuilder {
+        self.counters.request();
+        let request = self
+            .client
+            .get(self.url.clone())
+            .headers(self.options.headers.clone());
+        let request = if self.options.accept_any_encoding
+            || self
+                .options
+                .headers
+                .contains_key(reqwest::header::ACCEPT_ENCODING)
+        {
+            request
+        } else {
+            request.header(reqwest::header::ACCEPT_ENCODING, "identity")
+        };
+        let request = match (self.options.revalidate, &self.etag) {
+            (true, Some(etag)) => request
+                .header(reqwest::header::CACHE_CONTROL, "no-cache")
+                .header(reqwest::header::IF_NONE_MATCH, etag),
+            (true, None) => request.header(reqwest::header::CACHE_CONTROL, "no-cache"),
+            (false, _) => request,
+        };
+        match self.pinned.as_ref().and_then(Pinned::if_range) {
+            Some(validator) => request.header(reqwest::header::IF_RANGE, validator),
+            None => request,
+        }
+    }
+
+    fn reset_retry(&mut self) {
+        self.retry_attempt = self.options.max_retries();
+        self.failovers_left = self.options.mirrors.len().saturating_sub(1);
+    }
+
+    /// Learn the length with a `bytes=-n` request for the pending seek from
+    /// the end, then either settle on its response, which starts right at the
+    /// target, or leave the seek to the target to the usual path.
+    fn poll_seek_from_end(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        loop {
+            let Some((back, request)) = self.seek_from_end.as_mut() else {
+                return std::task::Poll::Ready(Ok(()));
+            };
+            let back = *back;
+            let request = match request {
+                Some(request) => request,
+                None => {
+                    ready!(self.poll_retry_wait(cx));
+                    self.check_connection_budget()?;
+                    log::debug!(bytes_back = back ; "GET {}", self.url);
+                    // an empty suffix is invalid, the last byte gives the length too
+                    let range = format!("bytes=-{}", back.max(1));
+                    let request = send_range(self.get(), range, &self.options);
+                    self.deadline = None;
+                    self.seek_from_end
+                        .insert((back, Some(request)))
+                        .1
+                        .as_mut()
+                        .unwrap()
+                }
+            };
+            let resp = match request.poll_unpin(cx) {
+                std::task::Poll::Ready(resp) => resp,
+                std::task::Poll::Pending => {
+                    let timeout = self.options.request_timeout;
+                    ready!(self.poll_deadline(cx, timeout, response_timeout))?;
+                    self.seek_from_end = Some((back, None));
+                    continue;
+                }
+            };
+            let resp = match resp {
+                Ok(resp) => {
+                    let wait = retry::retry_after(resp.headers());
+                    resp.error_for_status().map_err(|err| (err, wait))
+                }
+                Err(err) => Err((err, None)),
+            };
+            let resp = match resp {
+                Ok(resp) => resp,
+                Err((err, wait)) => {
+                    self.retry_request(err, wait)?;
+                    self.seek_from_end = Some((back, None));
+                    continue;
+                }
+            };
+
+            let partial = resp.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+            let total = if partial {
+                content_range_total(resp.headers())
+            } else {
+                resp.content_length()
+            };
+            let Some(total) = total else {
+                return std::task::Poll::Ready(Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "cannot seek from end without known content length",
+                )));
+            };
+            let pos = total.checked_sub(back).ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek to end")
+            })?;
+            self.content_length = Some(total);
+            if partial && back > 0 && self.options.chunk_merkle.is_none() {
+                self.accept_response(resp, pos)?;
+                self.pos = pos;
+                self.seek = None;
+            } else {
+                self.connections_opened += 1;
+                self.seek = Some(pos);
+            }
+            return std::task::Poll::Ready(Ok(()));
+        }
+    }
+
+    /// Retry a request or response stream that failed with `err` once the
+    /// delay from [`retry_delay`](Self::retry_delay) has passed, or give up
+    /// with the error when it isn't retried or no attempts are left.
+    fn retry_request(
+        &mut self,
+        err: reqwest::Error,
+        retry_after: Option<std::time::Duration>,
+    ) -> std::io::Result<()> {
+        if let Some(provider) = &self.options.url_provider
+            && self.retry_attempt > 0
+            && err
+                .status()
+                .is_some_and(|status| self.options.refreshes_url(status))
+        {
+            log::warn!(bytes_from = self.pos ; "{}, asking for a new url", err);
+            self.url_refresh = Some((provider.0)());
+            self.start_retry(std::time::Duration::ZERO);
+            return Ok(());
+        }
+        let delay = match self.retry_delay(&err, retry_after) {
+            Some(delay) if self.retry_attempt > 0 => delay,
+            _ if self.fail_over() => {
+                log::warn!(bytes_from = self.pos ; "{}, failing over to {}", err, self.url);
+                return Ok(());
+            }
+            _ => return Err(HttpFileError::from(err).into()),
+        };
+        log::warn!(
+            bytes_from = self.pos ;
+            "{}, retrying... attempts left: {}",
+            err,
+            self.retry_attempt
+        );
+        self.start_retry(delay);
+        Ok(())
+    }
+
+    /// Move on to the next mirror, with a fresh retry budget, so the next poll
+    /// resumes from `pos` there. Returns `false` once every mirror failed in
+    /// turn.
+    fn fail_over(&mut self) -> bool {
+        if self.failovers_left == 0 {
+            return false;
+        }
+        self.failovers_left -= 1;
+        self.mirror = (self.mirror + 1) % self.options.mirrors.len();
+        self.url = self.options.mirrors[self.mirror].clone();
+        self.counters.fail_over();
+        self.retry_attempt = self.options.max_retries();
+        self.retry_wait = None;
+        self.url_refresh = None;
+        self.deadline = None;
+        self.request = None;
+        self.response = None;
+        self.skip = 0;
+        true
+    }
+
+    /// Delay before retrying after `err`, or `None` to give up, as decided by
+    /// the retry policy if one is set. Otherwise timeouts are retried, and
+    /// connections dropped mid-body with partial delivery, and statuses as
+    /// the status policy says, after the backoff delay or the `Retry-After`
+    /// one.
+    fn retry_delay(
+        &self,
+        err: &reqwest::Error,
+        retry_after: Option<std::time::Duration>,
+    ) -> Option<std::time::Duration> {
+        if self.retry_attempt == 0 {
+            return None;
+        }
+        let retried = self
+            .options
+            .max_retries()
+            .saturating_sub(self.retry_attempt);
+        if let Some(policy) = &self.options.retry_policy {
+            return policy.0.should_retry(err, retried);
+        }
+        let dropped = self.options.partial_delivery && (err.is_decode() || err.is_body());
+        let action = match err.status() {
+            Some(status) => self.options.retry_action(status),
+            None if err.is_timeout() || dropped => RetryAction::Retry,
+            None => RetryAction::Fail,
+        };
+        let backoff = self.backoff_delay();
+        match action {
+            RetryAction::Retry => Some(backoff),
+            RetryAction::RetryAfter => {
+                let cap = retry::MAX_RETRY_AFTER.max(self.options.backoff.max);
+                Some(retry_after.map_or(backoff, |wait| wait.min(cap)))
+            }
+            RetryAction::Fail => None,
+        }
+    }
+
+    /// Fail with [`HttpFileError::Cancelled`] once the deadline has passed,
+    /// dropping the request and response in flight. Otherwise the task is
+    /// woken when it passes, interrupting whatever it waits on.
+    fn check_deadline(&mut self, cx: &mut std::task::Context<'_>) -> std::io::Result<()> {
+        let Some(deadline) = self.options.deadline else {
+            return Ok(());
+        };
+        let cancel_at = self
+            .cancel_at
+            .get_or_insert_with(|| Box::pin(tokio::time::sleep_until(deadline.into())));
+        if cancel_at.poll_unpin(cx).is_ready() {
+            self.pause();
+            self.seek = None;
+            self.seek_from_end = None;
+            return Err(HttpFileError::Cancelled.into());
+        }
+        Ok(())
+    }
+
+    /// Backoff delay before the next retry.
+    fn backoff_delay(&self) -> std::time::Duration {
+        let retried = self
+            .options
+            .max_retries()
+            .saturating_sub(self.retry_attempt);
+        self.options.backoff.delay(retried)
+    }
+
+    /// Wait on `timeout` while the server sends nothing, be it the response
+    /// to the request in flight or the next chunk of the open one. Once it
+    /// passes, both are dropped for a retry from `pos`, and `Ready(Ok)` tells
+    /// to go on with a new request; without attempts left it fails with the
+    /// error made by `timed_out`.
+    fn poll_deadline(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+        timeout: Option<std::time::Duration>,
+        timed_out: fn(std::time::Duration) -> HttpFileError,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        let Some(timeout) = timeout else {
+            return std::task::Poll::Pending;
+        };
+        let deadline = self
+            .deadline
+            .get_or_insert_with(|| Box::pin(tokio::time::sleep(timeout)));
+        ready!(deadline.poll_unpin(cx));
+        self.deadline = None;
+        if self.retry_attempt == 0 {
+            self.request = None;
+            return std::task::Poll::Ready(Err(timed_out(timeout).into()));
+        }
+        log::warn!(
+            bytes_from = self.pos ;
+            "no data for {:?}, reconnecting... attempts left: {}",
+            timeout,
+            self.retry_attempt
+        );
+        self.start_retry(self.backoff_delay());
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    /// Open the tracing span of a new range request from `start`, closing
+    /// the previous one.
+    fn start_span(&mut self, start: u64) {
+        let attempt = (self.options.max_retries())
+            .saturating_sub(self.retry_attempt)
+            .saturating_add(1);
+        self.span = trace::RequestSpan::new(&self.url, start, attempt);
+        if self.connections_opened > 0 {
+            self.span.reconnect(start);
+        }
+    }
+
+    /// Wait out the delay before a retry, then the new url if one is being
+    /// fetched.
+    fn poll_retry_wait(&mut self, cx: &mut std::task::Context<'_>) -> std::task::Poll<()> {
+        if let Some(wait) = self.retry_wait.as_mut() {
+            ready!(wait.poll_unpin(cx));
+            self.retry_wait = None;
+        }
+        if let Some(refresh) = self.url_refresh.as_mut() {
+            self.url = ready!(refresh.poll_unpin(cx));
+            self.url_refresh = None;
+        }
+        std::task::Poll::Ready(())
+    }
+
+    /// Spend one retry attempt and drop the response, so the next poll makes
+    /// a new request from `pos` once `delay` has passed.
+    fn start_retry(&mut self, delay: std::time::Duration) {
+        self.retry_wait = (!delay.is_zero()).then(|| Box::pin(tokio::time::sleep(delay)));
+        self.deadline = None;
+        self.request = None;
+        self.retry_attempt -= 1;
+        self.counters.retry();
+        self.span.retry(self.pos, self.retry_attempt, delay);
+        self.span.close();
+        if let Some(metrics) = self.options.metrics() {
+            metrics.retry();
+        }
+        self.response = None;
+        self.skip = 0;
+    }
+
+    /// Whether the server answered a range request with the whole file and
+    /// the full download fallback should take over.
+    fn range_ignored(&self, resp: &reqwest::Response) -> bool {
+        self.options.full_download_fallback && resp.status() == reqwest::StatusCode::OK
+    }
+
+    /// Offset in the file at which the body of `resp` starts, for a range
+    /// request from `start`. A server that ignores ranges answers with
+    /// `200 OK` and the whole file, whose first `start` bytes are then skipped.
+    fn body_start(&self, resp: &reqwest::Response, start: u64) -> u64 {
+        if resp.status() != reqwest::StatusCode::OK || start == 0 {
+            return start;
+        }
+        log::warn!(
+            "{} ignored the range request, skipping {} bytes",
+            self.url,
+            start
+        );
+        0
+    }
+
+    /// Fail on a `304 Not Modified` to a request that wasn't conditional,
+    /// rather than reading its empty body as the file content.
+    fn check_not_modified(&self, resp: &reqwest::Response) -> Result<(), HttpFileError> {
+        let conditional = [
+            reqwest::header::IF_NONE_MATCH,
+            reqwest::header::IF_MODIFIED_SINCE,
+        ]
+        .iter()
+        .any(|name| self.options.headers.contains_key(name));
+        if resp.status() == reqwest::StatusCode::NOT_MODIFIED && !conditional {
+            return Err(HttpFileError::UnexpectedNotModified);
+        }
+        Ok(())
+    }
+
+    /// Fail if `resp` serves a different version than the pinned one, or
+    /// reports another etag than the one the file was opened with.
+    fn check_version(&mut self, resp: &reqwest::Response) -> Result<(), HttpFileError> {
+        let etag = header_string(resp.headers(), reqwest::header::ETAG);
+        let last_modified = header_string(resp.headers(), reqwest::header::LAST_MODIFIED);
+        let (expected, actual) = match &self.pinned {
+            // unpinned, only a different etag than the one opened is caught
+            None => match (&self.etag, etag) {
+                (Some(expected), Some(actual))
+                    if !self.options.skip_etag_check && *expected != actual =>
+                {
+                    (expected, Some(actual))
+                }
+                _ => return Ok(()),
+            },
+            Some(Pinned::Pending) => {
+                if let Some(etag) = etag {
+                    self.pinned = Some(Pinned::Etag(etag));
+                } else if let Some(last_modified) = last_modified {
+                    self.pinned = Some(Pinned::LastModified(last_modified));
+                }
+                return Ok(());
+            }
+            Some(Pinned::Etag(expected)) => (expected, etag),
+            Some(Pinned::LastModified(expected)) => (expected, last_modified),
+        };
+        let changed = match &actual {
+            Some(actual) => actual != expected,
+            // `If-Range` was sent, so a full response means it didn't match
+            None => {
+                resp.status() == reqwest::StatusCode::OK
+                    && self.pinned.as_ref().and_then(Pinned::if_range).is_some()
+            }
+        };
+        if changed {
+            return Err(HttpFileError::FileChanged {
+                expected: Some(expected.clone()),
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Take over the response of a range request made to read from `pos`.
+    ///
+    /// On error the previous response, if any, is left in place.
+    fn accept_response(&mut self, resp: reqwest::Response, pos: u64) -> Result<(), HttpFileError> {
+        self.connections_opened += 1;
+        self.deadline = None;
+        self.cache_status = cache_status::cache_status(resp.headers());
+        self.follow_redirect(&resp);
+        self.check_not_modified(&resp)?;
+        self.check_mirror(&resp)?;
+        self.check_identity(&resp)?;
+        self.check_version(&resp)?;
+        self.check_encoding(&resp);
+        self.learn_length(&resp);
+        let (start, _) = self.request_range(pos, None);
+        let start = self.body_start(&resp, start);
+        self.skip = pos - start;
+        self.verifier = self.verifier_from(start);
+        self.checksum = self
+            .options
+            .verify_checksums
+            .then(|| checksum::Checksum::for_response(&resp, &self.head_headers))
+            .flatten();
+        self.last_chunk = None;
+        self.response = None;
+        self.throughput = Some((self.options.now(), 0));
+        if self.range_ignored(&resp) {
+            log::warn!(
+                "range requests unsupported, downloading {} in full",
+                self.url
+            );
+            self.download = Some(resp.bytes().boxed());
+            return Ok(());
+        }
+        let (stream, trailer) = trailer::body_stream(resp);
+        let stream = match self.options.prefetch {
+            Some(budget) => prefetch::prefetch(stream, budget),
+            None => stream,
+        };
+        self.response = Some(stream);
+        self.trailer = Some(trailer);
+        Ok(())
+    }
+
+    /// Send the next requests straight to where `resp` was redirected, if it
+    /// was, rather than through the redirect again.
+    fn follow_redirect(&mut self, resp: &reqwest::Response) {
+        if *resp.url() != self.url {
+            log::debug!("{} redirected to {}", self.url, resp.url());
+            self.url = resp.url().clone();
+        }
+    }
+
+    /// Fail if `resp`, from one of several mirrors, reports another etag or
+    /// length than the file was opened with.
+    fn check_mirror(&self, resp: &reqwest::Response) -> Result<(), HttpFileError> {
+        if self.options.mirrors.len() < 2 {
+            return Ok(());
+        }
+        let etag = header_string(resp.headers(), reqwest::header::ETAG);
+        let total = (resp.status() == reqwest::StatusCode::PARTIAL_CONTENT)
+            .then(|| content_range_total(resp.headers()))
+            .flatten();
+        let etag_differs = matches!((&self.etag, &etag), (Some(a), Some(b)) if a != b);
+        let length_differs = !self.options.distrust_content_length
+            && matches!((self.content_length, total), (Some(a), Some(b)) if a != b);
+        if etag_differs || length_differs {
+            return Err(HttpFileError::MirrorMismatch {
+                url: self.url.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Fail if `resp` is encoded although the identity encoding was asked for.
+    fn check_identity(&self, resp: &reqwest::Response) -> Result<(), HttpFileError> {
+        if self.options.accept_any_encoding {
+            return Ok(());
+        }
+        match content_encoding(resp.headers()) {
+            Some(encoding) => Err(HttpFileError::EncodedResponse { encoding }),
+            None => Ok(()),
+        }
+    }
+
+    /// Forget the length if `resp` is encoded differently from the response it
+    /// was learned from, as it then counts bytes of another representation.
+    /// The total of the `Content-Range`, if any, is taken instead.
+    fn check_encoding(&mut self, resp: &reqwest::Response) {
+        let encoding = content_encoding(resp.headers());
+        if encoding == self.length_encoding {
+            return;
+        }
+        log::warn!(
+            "{} is served as {:?} but its length was given for {:?}",
+            self.url,
+            encoding.as_deref().unwrap_or("identity"),
+            self.length_encoding.as_deref().unwrap_or("identity")
+        );
+        self.content_length = (resp.status() == reqwest::StatusCode::PARTIAL_CONTENT)
+            .then(|| content_range_total(resp.headers()))
+            .flatten();
+        self.length_encoding = encoding;
+    }
+
+    /// Take the length from the `Content-Range` total of a range response
+    /// when it is unknown, or correct it upward if it is distrusted.
+    fn learn_length(&mut self, resp: &reqwest::Response) {
+        if resp.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+            return;
+        }
+        let total = content_range_total(resp.headers());
+        if self.content_length.is_none() {
+            self.content_length = total;
+        } else if let Some(total) = total {
+            self.grow_length(total);
+        }
+    }
+
+    /// Range to request to read from `pos` up to `end`, widened to whole
+    /// chunks when they are verified against a Merkle tree.
+    fn request_range(&self, pos: u64, end: Option<u64>) -> (u64, Option<u64>) {
+        let Some((_, chunk_size)) = self.options.chunk_merkle else {
+            return (pos, end);
+        };
+        let end = end.map(|end| {
+            let end = (end / chunk_size + 1).saturating_mul(chunk_size) - 1;
+            self.content_length
+                .map_or(end, |len| end.min(len.saturating_sub(1)))
+        });
+        (pos - pos % chunk_size, end)
+    }
+
+    /// A verifier for a response starting at `start`, if chunks are verified.
+    fn verifier_from(&self, start: u64) -> Option<merkle::Verifier> {
+        let (tree, chunk_size) = self.options.chunk_merkle.as_ref()?;
+        Some(merkle::Verifier::new(tree.clone(), *chunk_size, start))
+    }
+
+    /// Discard the bytes still to be skipped from the front of `chunk`.
+    fn take_skip(&mut self, chunk: bytes::Bytes) -> bytes::Bytes {
+        let skipped = self.skip.min(chunk.len() as u64);
+        self.skip -= skipped;
+        chunk.slice(skipped as usize..)
+    }
+
+    /// End of the range to request from `pos`: a bounded window once the slow
+    /// stream policy kicked in, open otherwise.
+    fn range_end(&self, pos: u64) -> Option<u64> {
+        match self.options.slow_stream_policy {
+            Some((_, window)) if self.downgraded => {
+                let end = pos + window.max(self.options.min_fetch).max(1) - 1;
+                Some(
+                    self.content_length
+                        .map_or(end, |len| end.min(len.saturating_sub(1))),
+                )
+            }
+            _ => self.options.open_range_end,
+        }
+    }
+
+    /// Account for `received` bytes on the open stream, returning whether its
+    /// throughput over the last measurement interval fell below the policy.
+    fn stream_too_slow(&mut self, received: u64) -> bool {
+        let Some((min_bps, window)) = self.options.slow_stream_policy else {
+            return false;
+        };
+        // bounded windows need a length to know when to stop
+        if self.downgraded || self.content_length.is_none() {
+            return false;
+        }
+        let now = self.options.now();
+        let (start, bytes) = self.throughput.get_or_insert((now, 0));
+        *bytes += received;
+        let elapsed = now - *start;
+        if elapsed < SLOW_STREAM_INTERVAL {
+            return false;
+        }
+        let bps = *bytes as f64 / elapsed.as_secs_f64();
+        self.throughput = Some((now, 0));
+        if bps >= min_bps as f64 {
+            return false;
+        }
+        log::warn!(
+            bytes_from = self.pos ;
+            "stream at {:.0} B/s, switching to ranges of {} bytes",
+            bps,
+            window
+        );
+        true
+    }
+
+    /// Fail once the average throughput since the first data arrived stays
+    /// below the configured minimum past the grace period.
+    fn check_min_throughput(&mut self, received: u64) -> Result<(), HttpFileError> {
+        let Some((min, grace)) = self.options.min_throughput else {
+            return Ok(());
+        };
+        let now = self.options.now();
+        let (start, bytes) = self.transferred.get_or_insert((now, 0));
+        *bytes += received;
+        let elapsed = now - *start;
+        if elapsed < grace || elapsed.is_zero() {
+            return Ok(());
+        }
+        let bytes_per_sec = (*bytes as f64 / elapsed.as_secs_f64()) as u64;
+        if bytes_per_sec < min {
+            return Err(HttpFileError::TooSlow { bytes_per_sec, min });
+        }
+        Ok(())
+    }
 
-/// An remote file accessed over HTTP.
-/// Implements `AsyncRead` and `AsyncSeek` traits.
-///
-/// * Supports seeking and reading at arbitrary positions.
-/// * Uses HTTP Range requests to fetch data.
-/// * Handles transient network errors with retries.
-/// * `stream_position()` is cheap, as it is tracked locally.
-///
-pub struct HttpFile {
-    client: reqwest::Client,
+    /// Check the trailer of the response just read to the end, learning the
+    /// length and etag from it when they were not known.
+    fn finish_response(&mut self) -> Result<(), HttpFileError> {
+        self.grow_length(self.pos);
+        self.check_checksum(true)?;
+        let Some(trailer) = self.trailer.take().and_then(|t| t.lock().unwrap().take()) else {
+            return Ok(());
+        };
+        if trailer.validate()?.is_some() && self.content_length.is_none() && self.skip == 0 {
+            self.content_length = Some(self.pos);
+        }
+        if self.etag.is_none() {
+            self.etag = trailer.etag();
+        }
+        Ok(())
+    }
 
-    // info
-    url: reqwest::Url,
-    content_length: Option<NonZeroU64>,
-    etag: Option<String>,
-    mime: Option<String>,
+    /// Check the body of the open response against its advertised digest,
+    /// once all of it was hashed, or whatever was when the body `ended`.
+    fn check_checksum(&mut self, ended: bool) -> Result<(), HttpFileError> {
+        match self.checksum.take() {
+            Some(checksum) if ended || checksum.is_complete() => checksum.verify(),
+            checksum => {
+                self.checksum = checksum;
+                Ok(())
+            }
+        }
+    }
 
-    // inner states
-    pos: u64,
-    request: Option<(u64, RequestFuture)>,
-    response: Option<ResponseStream>,
-    last_chunk: Option<bytes::Bytes>,
-    seek: Option<u64>,
-    retry_attempt: u8,
-}
+    /// Serve everything from `bytes`, the whole file, from now on.
+    fn set_local(&mut self, bytes: bytes::Bytes) {
+        self.content_length = Some(bytes.len() as u64);
+        self.local = Some(bytes);
+        self.request = None;
+        self.response = None;
+        self.last_chunk = None;
+        self.skip = 0;
+    }
 
-impl std::fmt::Debug for HttpFile {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("HttpFile")
-            .field("client", &self.client)
-            .field("url", &self.url)
-            .field("content_length", &self.content_length)
-            .field("etag", &self.etag)
-            .field("pos", &self.pos)
-            .field(
-                "request",
-                &self
-                    .request
-                    .as_ref()
-                    .map(|(pos, _)| format!("request at {}", pos)),
-            )
-            .field("response", &"[response stream]")
-            .field("last_chunk", &self.last_chunk)
-            .field("seek", &self.seek)
-            .finish()
+    /// Keep the whole file, received in one piece, in memory.
+    fn receive_whole(&mut self, bytes: bytes::Bytes) -> std::io::Result<()> {
+        if let Some(checksum) = self.checksum.as_mut() {
+            checksum.push(&bytes);
+            self.check_checksum(true)?;
+        }
+        let bytes = self.receive_chunk(bytes)?;
+        if let Some(mut verifier) = self.verifier_from(0) {
+            verifier.push(&bytes)?;
+            verifier.finish()?;
+        }
+        self.set_local(bytes);
+        Ok(())
     }
-}
 
-impl HttpFile {
-    /// url of the file
-    pub fn url(&self) -> &reqwest::Url {
-        &self.url
+    /// Drive the full download started by the fallback, if any.
+    fn poll_download(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        let Some(download) = self.download.as_mut() else {
+            return std::task::Poll::Ready(Ok(()));
+        };
+        let result = ready!(download.poll_unpin(cx));
+        self.download = None;
+        let bytes = result.map_err(|e| std::io::Error::from(HttpFileError::from(e)))?;
+        self.receive_whole(bytes)?;
+        std::task::Poll::Ready(Ok(()))
     }
-    /// content length of the file(in bytes), if present
-    pub fn content_length(&self) -> Option<u64> {
-        self.content_length.map(|v| v.get())
+
+    /// Read from the block `pos` falls in, fetching it first unless cached.
+    /// `len` is the length of the file.
+    fn poll_read_block(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+        len: u64,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        let Some(blocks) = self.blocks.as_mut() else {
+            return std::task::Poll::Ready(Ok(()));
+        };
+        let size = blocks.block_size();
+        let index = self.pos / size;
+        let block = match blocks.get(index) {
+            Some(block) => block,
+            None => {
+                if !matches!(&self.block_fetch, Some((i, _)) if *i == index) {
+                    let start = index * size;
+                    let fetch = self.fetch_block(start..start.saturating_add(size).min(len));
+                    self.block_fetch = Some((index, fetch));
+                }
+                let (_, fetch) = self.block_fetch.as_mut().expect("just set");
+                let block = ready!(fetch.poll_unpin(cx));
+                self.block_fetch = None;
+                let block = block?;
+                if let Some(blocks) = self.blocks.as_mut() {
+                    blocks.insert(index, block.clone());
+                }
+                block
+            }
+        };
+        let offset = (self.pos - index * size) as usize;
+        std::task::Poll::Ready(self.deliver(block.slice(offset..), buf))
     }
-    /// etag of the file, if present
-    pub fn etag(&self) -> Option<&str> {
-        self.etag.as_deref()
+
+    /// Fetch `range` of the file for the block cache, through a slice whose
+    /// file caches nothing itself and counts into the stats of this one.
+    fn fetch_block(&self, range: std::ops::Range<u64>) -> BlockFuture {
+        let mut part = self.slice(range);
+        part.file.blocks = None;
+        part.file.options.progress = None;
+        part.file.counters = self.counters.clone();
+        let mut block = self.options.buffer(part.len() as usize);
+        async move {
+            block.resize(part.len() as usize, 0);
+            tokio::io::AsyncReadExt::read_exact(&mut part, &mut block[..]).await?;
+            Ok(block.freeze())
+        }
+        .boxed()
     }
-    /// Mime type of the file, if present
-    pub fn mime(&self) -> Option<&str> {
-        self.mime.as_deref()
+
+    fn read_local(&mut self, buf: &mut tokio::io::ReadBuf<'_>) -> std::io::Result<()> {
+        let Some(local) = &self.local else {
+            return Ok(());
+        };
+        let start = (self.pos as usize).min(local.len());
+        let size = (local.len() - start).min(buf.remaining());
+        buf.put_slice(&local[start..start + size]);
+        self.pos += size as u64;
+        if let Some(throttle) = self.throttle.as_mut() {
+            throttle.consume(size);
+        }
+        self.delivered(size);
+        Ok(())
     }
-}
 
-impl HttpFile {
-    /// Create a new `HttpFile` from a `reqwest::Client` and a file URL.
-    ///
-    /// Arguments:
-    /// * `client`: A `reqwest::Client` instance to make HTTP requests.
-    /// * `url`: The URL of the file to access.
-    ///
-    pub async fn new(client: reqwest::Client, url: &str) -> reqwest::Result<Self> {
-        log::debug!("HEAD {}", url);
-        let resp = client.head(url).send().await?.error_for_status()?;
-        let etag = resp
-            .headers()
-            .get(reqwest::header::ETAG)
-            .and_then(|v| v.to_str().ok())
-            .map(|s| s.to_string());
+    /// Fail if opening another response would go over `max_connections`.
+    fn check_connection_budget(&self) -> Result<(), HttpFileError> {
+        if let Some(limit) = self.options.max_connections
+            && self.connections_opened >= limit
+        {
+            return Err(HttpFileError::TooManyConnections { limit });
+        }
+        Ok(())
+    }
 
-        let content_length = resp
-            .headers()
-            .get(reqwest::header::CONTENT_LENGTH)
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.parse::<NonZeroU64>().ok());
+    /// Move to `target` by discarding bytes from the open response instead of
+    /// opening a new one. Returns `false` if the response can't reach `target`,
+    /// or only by discarding more than `limit` bytes.
+    fn skip_in_stream(&mut self, target: u64, limit: u64) -> bool {
+        if self.response.is_none() {
+            return false;
+        }
+        let buffered = self.last_chunk.as_ref().map_or(0, |c| c.len() as u64);
+        // position of the next byte the response will yield
+        let stream_pos = self.pos + buffered - self.skip;
+        if target >= self.pos && target < self.pos + buffered {
+            let last_chunk = self.last_chunk.take().unwrap();
+            self.last_chunk = Some(last_chunk.slice((target - self.pos) as usize..));
+        } else if target >= stream_pos && target - stream_pos <= limit {
+            self.last_chunk = None;
+            self.skip = target - stream_pos;
+        } else {
+            return false;
+        }
+        log::debug!("skipping to {} within the open response", target);
+        // the response open is the one at `pos`, a request in flight for an
+        // abandoned seek is no longer needed
+        self.request = None;
+        self.pos = target;
+        true
+    }
 
-        let mime = resp
-            .headers()
-            .get(reqwest::header::CONTENT_TYPE)
-            .and_then(|v| v.to_str().ok())
-            .map(|s| s.to_string());
+    /// Keep `chunk`, about to be delivered at `pos`, in the read buffer.
+    fn retain(&mut self, chunk: &bytes::Bytes) {
+        if let Some(buffer) = self.read_buffer.as_mut()
+            && !chunk.is_empty()
+        {
+            buffer.push(self.pos, chunk.clone());
+        }
+    }
 
-        let url = resp.url().clone();
-        let pos = 0;
+    /// Move to `target` within the read buffer, if it holds it. The open
+    /// response is kept when it goes on right after the buffered bytes, and
+    /// dropped otherwise, so reads past them make a new request.
+    fn seek_in_buffer(&mut self, target: u64) -> bool {
+        let Some(buffer) = self.read_buffer.as_ref() else {
+            return false;
+        };
+        let Some(chunk) = buffer.chunk_at(target) else {
+            return false;
+        };
+        let buffered = self.last_chunk.as_ref().map_or(0, |c| c.len() as u64);
+        let continues =
+            self.request.is_none() && self.skip == 0 && self.pos + buffered == buffer.end();
+        if !continues {
+            self.request = None;
+            self.response = None;
+            self.skip = 0;
+        }
+        log::debug!("seeking to {} within the read buffer", target);
+        self.last_chunk = Some(chunk);
+        self.pos = target;
+        true
+    }
 
-        Ok(Self {
-            client,
-            content_length,
-            url,
-            pos,
-            request: None,
-            response: None,
-            last_chunk: None,
-            seek: None,
-            etag,
-            retry_attempt: 3,
-            mime,
-        })
+    /// Account for a chunk received from the network and apply the configured
+    /// chunk transform, which must not change the length.
+    fn receive_chunk(&mut self, chunk: bytes::Bytes) -> std::io::Result<bytes::Bytes> {
+        self.counters.received(chunk.len() as u64);
+        if let Some(metrics) = self.options.metrics() {
+            metrics.bytes_received(chunk.len() as u64);
+        }
+        self.check_min_throughput(chunk.len() as u64)?;
+        let Some(transform) = &self.options.chunk_transform else {
+            return Ok(chunk);
+        };
+        let len = chunk.len();
+        let chunk = (transform.0)(chunk);
+        if chunk.len() != len {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "chunk transform changed the chunk length",
+            ));
+        }
+        Ok(chunk)
     }
 
-    fn reset_retry(&mut self) {
-        self.retry_attempt = 3;
+    /// Account for `bytes` handed out by a read, which moved the position.
+    fn delivered(&mut self, bytes: usize) {
+        if bytes > 0 {
+            self.counters.read(bytes as u64);
+            self.span.delivered(bytes);
+            self.report_progress();
+        }
+    }
+
+    /// Tell the progress callback, if any, where reading got to.
+    fn report_progress(&self) {
+        if let Some(progress) = &self.options.progress {
+            (progress.0)(self.pos, self.content_length);
+        }
+    }
+
+    /// Copy as much of `chunk` as fits into `buf`, keeping the rest for the next read.
+    fn deliver(
+        &mut self,
+        chunk: bytes::Bytes,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::io::Result<()> {
+        let mut size = chunk.len().min(buf.remaining());
+        if self.content_length.is_none()
+            && let Some(limit) = self.options.max_body_bytes
+        {
+            let allowed = limit.saturating_sub(self.pos);
+            if allowed == 0 && !chunk.is_empty() {
+                return Err(HttpFileError::BodyTooLarge { limit }.into());
+            }
+            size = size.min(allowed.try_into().unwrap_or(usize::MAX));
+        }
+        buf.put_slice(&chunk[..size]);
+        self.pos += size as u64;
+        if let Some(throttle) = self.throttle.as_mut() {
+            throttle.consume(size);
+        }
+        self.delivered(size);
+        if size < chunk.len() {
+            self.last_chunk = Some(chunk.slice(size..));
+        }
+        Ok(())
+    }
+}
+
+/// The buffered chunk of an [`HttpFile`], see [`HttpFile::chunk_as_buf`].
+struct ChunkBuf<'a> {
+    file: &'a mut HttpFile,
+}
+
+impl bytes::Buf for ChunkBuf<'_> {
+    fn remaining(&self) -> usize {
+        self.file.last_chunk.as_ref().map_or(0, |c| c.len())
+    }
+
+    fn chunk(&self) -> &[u8] {
+        self.file.last_chunk.as_deref().unwrap_or_default()
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        let remaining = self.remaining();
+        assert!(
+            cnt <= remaining,
+            "cannot advance past `remaining`: {:?} <= {:?}",
+            cnt,
+            remaining,
+        );
+        if let Some(chunk) = self.file.last_chunk.as_mut() {
+            chunk.advance(cnt);
+            if chunk.is_empty() {
+                self.file.last_chunk = None;
+            }
+        }
+        self.file.pos += cnt as u64;
+        if let Some(throttle) = self.file.throttle.as_mut() {
+            throttle.consume(cnt);
+        }
+        self.file.delivered(cnt);
     }
 }
 
@@ -147,80 +2694,259 @@ impl AsyncRead for HttpFile {
         cx: &mut std::task::Context<'_>,
         buf: &mut tokio::io::ReadBuf<'_>,
     ) -> std::task::Poll<std::io::Result<()>> {
-        // Check if we're at or beyond the end of file
-        if let Some(content_length) = self.content_length {
-            if self.pos >= content_length.get() {
+        if let Err(e) = self.check_deadline(cx) {
+            return std::task::Poll::Ready(Err(e));
+        }
+        if let Some(throttle) = self.throttle.as_mut() {
+            ready!(throttle.poll_ready(cx));
+        }
+        // retries and follow-up requests go around again rather than recursing
+        'request: loop {
+            // Check if we're at or beyond the end of file, unless the length is
+            // distrusted and the response goes on
+            if let Some(content_length) = self.content_length
+                && self.pos >= content_length
+                && !(self.options.distrust_content_length && self.response.is_some())
+            {
+                // reads stop at the length, maybe before the body is seen to end
+                if let Err(e) = self.check_checksum(false) {
+                    return std::task::Poll::Ready(Err(e.into()));
+                }
+                let pos = self.pos;
+                self.span.eof(pos);
                 return std::task::Poll::Ready(Ok(()));
             }
-        }
 
-        if let Some(last_chunk) = self.last_chunk.take() {
-            let size = last_chunk.len().min(buf.remaining());
-            buf.put_slice(&last_chunk[..size]);
-            self.pos += size as u64;
-            if size < last_chunk.len() {
-                self.last_chunk = Some(last_chunk.slice(size..));
+            if let Some(last_chunk) = self.last_chunk.take() {
+                return std::task::Poll::Ready(self.deliver(last_chunk, buf));
             }
-            return std::task::Poll::Ready(Ok(()));
-        }
 
-        let no_response = self.response.is_none();
-        let no_request = self.request.is_none();
+            // back within the read buffer, its chunks are handed out in turn
+            if let Some(chunk) = self
+                .read_buffer
+                .as_ref()
+                .and_then(|buffer| buffer.chunk_at(self.pos))
+            {
+                return std::task::Poll::Ready(self.deliver(chunk, buf));
+            }
 
-        if no_response && no_request {
-            log::debug!(bytes_from = self.pos ; "GET {}", self.url);
-            let request = new_request(&self.client, self.url.clone(), self.pos);
-            self.request = Some((self.pos, request));
-        }
+            if self.local.is_some() {
+                return std::task::Poll::Ready(self.read_local(buf));
+            }
 
-        if let Some((_pos, request)) = self.request.as_mut() {
-            match ready!(request.poll_unpin(cx)) {
-                Ok(stream) => {
-                    // put response stream
-                    self.response = Some(stream);
-                    self.request = None;
-                }
-                Err(err) => {
-                    self.request = None;
-                    return std::task::Poll::Ready(Err(std::io::Error::other(Box::new(err))));
-                }
+            if self.blocks.is_some()
+                && let Some(len) = self.content_length
+            {
+                return self.poll_read_block(cx, buf, len);
             }
-        }
 
-        let Some(response) = self.response.as_mut() else {
-            panic!("response should be Some after polled")
-        };
+            let no_response = self.response.is_none();
+            let no_request = self.request.is_none();
 
-        let Some(stream_chunks) = ready!(response.poll_next_unpin(cx)) else {
-            return std::task::Poll::Ready(Ok(()));
-        };
+            if no_response && no_request && self.download.is_none() {
+                ready!(self.poll_retry_wait(cx));
+                if let Err(e) = self.check_connection_budget() {
+                    return std::task::Poll::Ready(Err(e.into()));
+                }
+                log::debug!(bytes_from = self.pos ; "GET {}", self.url);
+                let (start, end) = self.request_range(self.pos, self.range_end(self.pos));
+                let request = new_request(self.get(), start, end, &self.options);
+                self.request = Some((self.pos, request));
+                self.deadline = None;
+                self.start_span(start);
+            }
 
-        match stream_chunks {
-            Ok(chunk) => {
-                let size = chunk.len().min(buf.remaining());
-                buf.put_slice(&chunk[..size]);
-                self.pos += size as u64;
-                if size < chunk.len() {
-                    self.last_chunk = Some(chunk.slice(size..));
+            if let Some((pos, request)) = self.request.as_mut() {
+                let pos = *pos;
+                let resp = match request.poll_unpin(cx) {
+                    std::task::Poll::Ready(resp) => resp,
+                    std::task::Poll::Pending => {
+                        let timeout = self.options.request_timeout;
+                        if let Err(e) = ready!(self.poll_deadline(cx, timeout, response_timeout)) {
+                            return std::task::Poll::Ready(Err(e));
+                        }
+                        continue 'request;
+                    }
+                };
+                match resp {
+                    Ok(resp) => {
+                        self.request = None;
+                        self.span.status(resp.status());
+                        let wait = retry::retry_after(resp.headers());
+                        let resp = match resp.error_for_status() {
+                            Ok(resp) => resp,
+                            Err(err) => {
+                                if let Err(e) = self.retry_request(err, wait) {
+                                    return std::task::Poll::Ready(Err(e));
+                                }
+                                continue 'request;
+                            }
+                        };
+                        if let Err(e) = self.accept_response(resp, pos) {
+                            return std::task::Poll::Ready(Err(e.into()));
+                        }
+                    }
+                    Err(err) => {
+                        self.request = None;
+                        if let Err(e) = self.retry_request(err, None) {
+                            return std::task::Poll::Ready(Err(e));
+                        }
+                        continue 'request;
+                    }
                 }
-                self.reset_retry();
-                std::task::Poll::Ready(Ok(()))
             }
-            Err(e) => {
-                if self.retry_attempt == 0 {
-                    return std::task::Poll::Ready(Err(std::io::Error::other(Box::new(e))));
+
+            if self.download.is_some() {
+                if let Err(e) = ready!(self.poll_download(cx)) {
+                    return std::task::Poll::Ready(Err(e));
                 }
+                return std::task::Poll::Ready(self.read_local(buf));
+            }
+
+            loop {
+                let Some(response) = self.response.as_mut() else {
+                    // a bug in the state above, but not worth taking the task down for
+                    return std::task::Poll::Ready(Err(std::io::Error::other(
+                        "no response to read from after the request completed",
+                    )));
+                };
+
+                let next = match response.poll_next_unpin(cx) {
+                    std::task::Poll::Ready(next) => next,
+                    std::task::Poll::Pending => {
+                        let timeout = self.options.stall_timeout;
+                        let stalled = |timeout| HttpFileError::Stalled { timeout };
+                        if let Err(e) = ready!(self.poll_deadline(cx, timeout, stalled)) {
+                            return std::task::Poll::Ready(Err(e));
+                        }
+                        continue 'request;
+                    }
+                };
+                self.deadline = None;
+                let Some(stream_chunks) = next else {
+                    // a last short chunk is only verified once the response ends
+                    if let Some(verifier) = self.verifier.as_mut() {
+                        let rest = match verifier.finish() {
+                            Ok(rest) => rest,
+                            Err(e) => return std::task::Poll::Ready(Err(e.into())),
+                        };
+                        let rest = self.take_skip(rest);
+                        if !rest.is_empty() {
+                            self.retain(&rest);
+                            self.reset_retry();
+                            return std::task::Poll::Ready(self.deliver(rest, buf));
+                        }
+                    }
+                    if let Err(e) = self.finish_response() {
+                        return std::task::Poll::Ready(Err(e.into()));
+                    }
+                    let pos = self.pos;
+                    self.span.eof(pos);
+                    // a bounded window ended, go on with the next one
+                    if self.downgraded && self.content_length.is_some_and(|len| self.pos < len) {
+                        self.response = None;
+                        continue 'request;
+                    }
+                    return std::task::Poll::Ready(Ok(()));
+                };
 
-                if e.is_timeout() || e.status().is_some_and(|s| s.is_server_error()) {
-                    log::warn!("timeout, retrying... attempts left: {}", self.retry_attempt);
-                    self.retry_attempt -= 1;
-                    self.response = None;
-                    return self.poll_read(cx, buf);
+                match stream_chunks {
+                    Ok(chunk) => {
+                        let slow = self.stream_too_slow(chunk.len() as u64);
+                        if let Some(checksum) = self.checksum.as_mut() {
+                            checksum.push(&chunk);
+                        }
+                        let mut chunk = match self.receive_chunk(chunk) {
+                            Ok(chunk) => chunk,
+                            Err(e) => return std::task::Poll::Ready(Err(e)),
+                        };
+                        if let Some(verifier) = self.verifier.as_mut() {
+                            chunk = match verifier.push(&chunk) {
+                                Ok(chunk) => chunk,
+                                Err(e) => return std::task::Poll::Ready(Err(e.into())),
+                            };
+                        }
+                        let chunk = self.take_skip(chunk);
+                        self.retain(&chunk);
+                        if slow {
+                            // this chunk is still delivered, later reads make bounded requests
+                            self.downgraded = true;
+                            self.response = None;
+                            self.skip = 0;
+                        }
+                        if chunk.is_empty() {
+                            if self.response.is_none() {
+                                continue 'request;
+                            }
+                            continue;
+                        }
+                        self.reset_retry();
+                        return std::task::Poll::Ready(self.deliver(chunk, buf));
+                    }
+                    Err(e) => {
+                        // a retry resumes from `pos`, keeping what was already delivered
+                        if let Err(e) = self.retry_request(e, None) {
+                            return std::task::Poll::Ready(Err(e));
+                        }
+                        continue 'request;
+                    }
                 }
+            }
+        }
+    }
+}
+
+/// Reads through [`AsyncBufRead`] hand out the chunks received as they are,
+/// without copying them into a separate buffer, so `read_until`, `lines` and
+/// the like can be used directly.
+impl AsyncBufRead for HttpFile {
+    fn poll_fill_buf(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if let Some(throttle) = this.throttle.as_mut() {
+            ready!(throttle.poll_ready(cx));
+        }
+        if this.last_chunk.as_ref().is_none_or(|c| c.is_empty()) && this.local.is_none() {
+            // reading into no room at all leaves the next chunk buffered whole
+            let mut empty = tokio::io::ReadBuf::new(&mut []);
+            ready!(std::pin::Pin::new(&mut *this).poll_read(cx, &mut empty))?;
+        }
+        if let Some(chunk) = this.last_chunk.as_deref() {
+            let mut size = chunk.len();
+            if this.content_length.is_none()
+                && let Some(limit) = this.options.max_body_bytes
+            {
+                size = size.min(
+                    limit
+                        .saturating_sub(this.pos)
+                        .try_into()
+                        .unwrap_or(usize::MAX),
+                );
+            }
+            return std::task::Poll::Ready(Ok(&chunk[..size]));
+        }
+        if let Some(local) = this.local.as_deref() {
+            let start = (this.pos as usize).min(local.len());
+            return std::task::Poll::Ready(Ok(&local[start..]));
+        }
+        std::task::Poll::Ready(Ok(&[]))
+    }
 
-                std::task::Poll::Ready(Err(std::io::Error::other(Box::new(e))))
+    fn consume(self: std::pin::Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        if let Some(chunk) = this.last_chunk.as_mut() {
+            bytes::Buf::advance(chunk, amt);
+            if chunk.is_empty() {
+                this.last_chunk = None;
             }
         }
+        this.pos += amt as u64;
+        if let Some(throttle) = this.throttle.as_mut() {
+            throttle.consume(amt);
+        }
+        this.delivered(amt);
     }
 }
 
@@ -229,8 +2955,8 @@ impl AsyncSeek for HttpFile {
         mut self: std::pin::Pin<&mut Self>,
         position: std::io::SeekFrom,
     ) -> std::io::Result<()> {
+        self.seek_from_end = None;
         if let Some(content_length) = self.content_length {
-            let content_length = content_length.get();
             let effective_pos = match position {
                 std::io::SeekFrom::Start(n) => n,
                 std::io::SeekFrom::End(n) => {
@@ -260,15 +2986,14 @@ impl AsyncSeek for HttpFile {
             self.seek = Some(effective_pos);
             Ok(())
         } else {
-            if matches!(position, std::io::SeekFrom::End(_)) {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    "cannot seek from end without known content length",
-                ));
-            }
-
             let effective_pos = match position {
                 std::io::SeekFrom::Start(n) => n,
+                // the length is learned from a suffix range request
+                std::io::SeekFrom::End(n) if n <= 0 => {
+                    self.seek = None;
+                    self.seek_from_end = Some((n.unsigned_abs(), None));
+                    return Ok(());
+                }
                 std::io::SeekFrom::End(_) => {
                     return Err(std::io::Error::new(
                         std::io::ErrorKind::InvalidInput,
@@ -296,7 +3021,42 @@ impl AsyncSeek for HttpFile {
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<std::io::Result<u64>> {
+        let settled = self.seek.is_some() || self.seek_from_end.is_some();
+        let result = ready!(self.as_mut().poll_seek(cx));
+        if settled && result.is_ok() {
+            self.report_progress();
+        }
+        std::task::Poll::Ready(result)
+    }
+}
+
+impl HttpFile {
+    /// Carry out the seek started by `start_seek`, for `poll_complete`.
+    fn poll_seek(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<u64>> {
+        if let Err(e) = self.check_deadline(cx) {
+            return std::task::Poll::Ready(Err(e));
+        }
+        if self.seek_from_end.is_some() {
+            let result = ready!(self.poll_seek_from_end(cx));
+            self.seek_from_end = None;
+            if let Err(e) = result {
+                return std::task::Poll::Ready(Err(e));
+            }
+        }
+
         if self.seek == Some(self.pos) {
+            // a request still in flight for an abandoned seek elsewhere must
+            // not be read from here
+            if self
+                .request
+                .as_ref()
+                .is_some_and(|(pos, _)| *pos != self.pos)
+            {
+                self.request = None;
+            }
             self.seek = None;
             return std::task::Poll::Ready(Ok(self.pos));
         }
@@ -305,36 +3065,104 @@ impl AsyncSeek for HttpFile {
             return std::task::Poll::Ready(Ok(self.pos));
         };
 
-        // If seeking to or beyond EOF, just update position without making a request
-        if let Some(content_length) = self.content_length {
-            if seek_pos >= content_length.get() {
-                self.pos = seek_pos;
-                self.seek = None;
-                self.request = None;
-                self.response = None;
-                self.last_chunk = None;
-                return std::task::Poll::Ready(Ok(self.pos));
-            }
+        if self.download.is_some()
+            && let Err(e) = ready!(self.poll_download(cx))
+        {
+            return std::task::Poll::Ready(Err(e));
         }
 
-        if self.request.is_none() || self.request.as_ref().unwrap().0 != seek_pos {
-            log::debug!(bytes_from = self.pos ; "GET {}", self.url);
-            let request = new_request(&self.client, self.url.clone(), seek_pos);
-            self.request = Some((seek_pos, request));
+        // everything is in memory, or fetched block by block on read
+        if self.local.is_some() || (self.blocks.is_some() && self.content_length.is_some()) {
+            self.pos = seek_pos;
+            self.last_chunk = None;
+            self.seek = None;
+            return std::task::Poll::Ready(Ok(self.pos));
         }
 
-        match ready!(self.request.as_mut().unwrap().1.poll_unpin(cx)) {
-            Ok(stream) => {
-                self.response = Some(stream);
-                self.pos = seek_pos;
-                self.seek = None;
-                self.request = None;
-                self.last_chunk = None;
-                std::task::Poll::Ready(Ok(self.pos))
+        if self.seek_in_buffer(seek_pos) {
+            self.seek = None;
+            return std::task::Poll::Ready(Ok(self.pos));
+        }
+
+        // If seeking to or beyond EOF, just update position without making a request
+        if let Some(content_length) = self.content_length
+            && seek_pos >= content_length
+        {
+            self.pos = seek_pos;
+            self.seek = None;
+            self.request = None;
+            self.response = None;
+            self.last_chunk = None;
+            self.skip = 0;
+            return std::task::Poll::Ready(Ok(self.pos));
+        }
+
+        loop {
+            if self.request.is_none() || self.request.as_ref().unwrap().0 != seek_pos {
+                ready!(self.poll_retry_wait(cx));
+                // a short hop forward is cheaper to read through than to reconnect
+                let max_inline_skip = self.options.max_inline_skip();
+                if self.skip_in_stream(seek_pos, max_inline_skip) {
+                    self.seek = None;
+                    return std::task::Poll::Ready(Ok(self.pos));
+                }
+                if let Err(e) = self.check_connection_budget() {
+                    // out of connections, the open response is the only way there
+                    if self.skip_in_stream(seek_pos, u64::MAX) {
+                        self.seek = None;
+                        return std::task::Poll::Ready(Ok(self.pos));
+                    }
+                    return std::task::Poll::Ready(Err(e.into()));
+                }
+                log::debug!(bytes_from = self.pos ; "GET {}", self.url);
+                let (start, end) = self.request_range(seek_pos, self.range_end(seek_pos));
+                let request = new_request(self.get(), start, end, &self.options);
+                self.request = Some((seek_pos, request));
+                self.deadline = None;
+                self.start_span(start);
             }
-            Err(err) => {
-                self.request = None;
-                std::task::Poll::Ready(Err(std::io::Error::other(Box::new(err))))
+
+            let resp = match self.request.as_mut().unwrap().1.poll_unpin(cx) {
+                std::task::Poll::Ready(resp) => resp,
+                std::task::Poll::Pending => {
+                    let timeout = self.options.request_timeout;
+                    if let Err(e) = ready!(self.poll_deadline(cx, timeout, response_timeout)) {
+                        self.seek = None;
+                        return std::task::Poll::Ready(Err(e));
+                    }
+                    continue;
+                }
+            };
+            let resp = match resp {
+                Ok(resp) => {
+                    self.span.status(resp.status());
+                    let wait = retry::retry_after(resp.headers());
+                    resp.error_for_status().map_err(|err| (err, wait))
+                }
+                Err(err) => Err((err, None)),
+            };
+            match resp {
+                Ok(resp) => {
+                    self.request = None;
+                    if let Err(e) = self.accept_response(resp, seek_pos) {
+                        self.seek = None;
+                        return std::task::Poll::Ready(Err(e.into()));
+                    }
+                    self.pos = seek_pos;
+                    if self.download.is_some() {
+                        // poll the fallback download to completion before settling
+                        return self.poll_seek(cx);
+                    }
+                    self.seek = None;
+                    return std::task::Poll::Ready(Ok(self.pos));
+                }
+                Err((err, wait)) => {
+                    self.request = None;
+                    if let Err(e) = self.retry_request(err, wait) {
+                        self.seek = None;
+                        return std::task::Poll::Ready(Err(e));
+                    }
+                }
             }
         }
     }
@@ -0,0 +1,96 @@
+use std::{
+    io::SeekFrom,
+    path::{Path, PathBuf},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+/// A file on the local filesystem, opened from a `file://` URL by
+/// [`AnyFile::open`](crate::AnyFile::open).
+#[derive(Debug)]
+pub struct LocalFile {
+    file: tokio::fs::File,
+    path: PathBuf,
+    len: u64,
+    mime: Option<String>,
+}
+
+impl LocalFile {
+    /// Open the file at `path` for reading.
+    pub async fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = tokio::fs::File::open(&path).await?;
+        let len = file.metadata().await?.len();
+        let mime = guess_mime(&path).map(str::to_string);
+        Ok(Self {
+            file,
+            path,
+            len,
+            mime,
+        })
+    }
+
+    /// path of the file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// length of the file when it was opened, in bytes
+    pub fn content_length(&self) -> u64 {
+        self.len
+    }
+
+    /// mime type guessed from the file extension, if known
+    pub fn mime(&self) -> Option<&str> {
+        self.mime.as_deref()
+    }
+}
+
+/// Mime type of a few common file extensions, as a server would report it.
+fn guess_mime(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "txt" => "text/plain",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "js" => "text/javascript",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "wasm" => "application/wasm",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        _ => return None,
+    })
+}
+
+impl AsyncRead for LocalFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.file).poll_read(cx, buf)
+    }
+}
+
+impl AsyncSeek for LocalFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        Pin::new(&mut self.file).start_seek(position)
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        Pin::new(&mut self.file).poll_complete(cx)
+    }
+}
@@ -0,0 +1,123 @@
+use std::{
+    io::{Error, ErrorKind, SeekFrom},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncSeek, ReadBuf};
+
+use crate::RemoteFile;
+
+/// A file held in memory, with the same surface as an
+/// [`HttpFile`](crate::HttpFile) through [`RemoteFile`], e.g. to test code
+/// reading files without a server.
+///
+/// As with an `HttpFile`, seeking past the end fails, and reads at the end
+/// find EOF.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFile {
+    data: Bytes,
+    etag: Option<String>,
+    mime: Option<String>,
+    pos: u64,
+}
+
+impl MemoryFile {
+    /// A file holding `data`, at position 0.
+    pub fn new(data: impl Into<Bytes>) -> Self {
+        Self {
+            data: data.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Report `etag` as the etag of the file.
+    pub fn with_etag(mut self, etag: impl Into<String>) -> Self {
+        self.etag = Some(etag.into());
+        self
+    }
+
+    /// Report `mime` as the mime type of the file.
+    pub fn with_mime(mut self, mime: impl Into<String>) -> Self {
+        self.mime = Some(mime.into());
+        self
+    }
+
+    /// content of the file
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+
+    /// current position in the file
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// The bytes from the current position on.
+    fn rest(&self) -> &[u8] {
+        &self.data[self.pos as usize..]
+    }
+}
+
+impl RemoteFile for MemoryFile {
+    fn content_length(&self) -> Option<u64> {
+        Some(self.data.len() as u64)
+    }
+
+    fn etag(&self) -> Option<&str> {
+        self.etag.as_deref()
+    }
+
+    fn mime(&self) -> Option<&str> {
+        self.mime.as_deref()
+    }
+}
+
+impl AsyncRead for MemoryFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let rest = self.rest();
+        let size = rest.len().min(buf.remaining());
+        buf.put_slice(&rest[..size]);
+        self.pos += size as u64;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncBufRead for MemoryFile {
+    fn poll_fill_buf(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<&[u8]>> {
+        Poll::Ready(Ok(self.get_mut().rest()))
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        self.pos += amt as u64;
+    }
+}
+
+impl AsyncSeek for MemoryFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        let pos = match position {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(n) => (self.data.len() as u64).checked_add_signed(n),
+            SeekFrom::Current(n) => self.pos.checked_add_signed(n),
+        };
+        let pos =
+            pos.ok_or_else(|| Error::new(ErrorKind::InvalidInput, "invalid seek before start"))?;
+        if pos > self.data.len() as u64 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "invalid seek beyond end",
+            ));
+        }
+        self.pos = pos;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        Poll::Ready(Ok(self.pos))
+    }
+}
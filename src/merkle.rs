@@ -0,0 +1,148 @@
+use std::sync::Arc;
+
+use bytes::{Bytes, BytesMut};
+use sha2::{Digest, Sha256};
+
+use crate::HttpFileError;
+
+/// A SHA-256 hash.
+type Hash = [u8; 32];
+
+/// A Merkle tree over the fixed-size chunks of a file, for verifiable random
+/// access, see [`HttpFileBuilder::with_chunk_merkle`](crate::HttpFileBuilder::with_chunk_merkle).
+///
+/// Hashes follow RFC 6962: a leaf is `SHA-256(0x00 || chunk)` and a node is
+/// `SHA-256(0x01 || left || right)`, with the left subtree covering the
+/// largest power of two of leaves smaller than the whole.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleTree {
+    leaves: Vec<Hash>,
+}
+
+impl MerkleTree {
+    /// A tree of the given leaf hashes, checked against a trusted `root`.
+    pub fn new(root: [u8; 32], leaves: Vec<[u8; 32]>) -> Result<Self, HttpFileError> {
+        let tree = Self { leaves };
+        if tree.root() != root {
+            return Err(HttpFileError::MerkleRootMismatch);
+        }
+        Ok(tree)
+    }
+
+    /// Hash the chunks of a file into a tree, e.g. to publish its root.
+    pub fn from_chunks<'a>(chunks: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        Self {
+            leaves: chunks.into_iter().map(leaf_hash).collect(),
+        }
+    }
+
+    /// Root hash of the tree.
+    pub fn root(&self) -> [u8; 32] {
+        subtree_root(&self.leaves)
+    }
+
+    /// Leaf hashes, one per chunk in file order.
+    pub fn leaves(&self) -> &[[u8; 32]] {
+        &self.leaves
+    }
+}
+
+fn leaf_hash(chunk: &[u8]) -> Hash {
+    Sha256::new()
+        .chain_update([0x00])
+        .chain_update(chunk)
+        .finalize()
+        .into()
+}
+
+fn subtree_root(leaves: &[Hash]) -> Hash {
+    match leaves {
+        [] => Sha256::digest([]).into(),
+        [leaf] => *leaf,
+        _ => {
+            // largest power of two below the number of leaves
+            let split = 1 << (leaves.len() - 1).ilog2();
+            Sha256::new()
+                .chain_update([0x01])
+                .chain_update(subtree_root(&leaves[..split]))
+                .chain_update(subtree_root(&leaves[split..]))
+                .finalize()
+                .into()
+        }
+    }
+}
+
+/// Buffers a response until whole chunks are received, and only lets through
+/// those matching their leaf.
+pub(crate) struct Verifier {
+    tree: Arc<MerkleTree>,
+    chunk_size: u64,
+    /// index of the chunk being buffered
+    index: u64,
+    buf: BytesMut,
+    /// the chunk at `index` didn't match, after earlier ones were returned
+    mismatch: bool,
+}
+
+impl Verifier {
+    /// Verify a response starting at the chunk-aligned offset `start`.
+    pub(crate) fn new(tree: Arc<MerkleTree>, chunk_size: u64, start: u64) -> Self {
+        Self {
+            tree,
+            chunk_size,
+            index: start / chunk_size,
+            buf: BytesMut::new(),
+            mismatch: false,
+        }
+    }
+
+    /// Take `data` from the response, returning the chunks it completed.
+    ///
+    /// Chunks verified before a mismatch are still returned, the error comes
+    /// from the next call.
+    pub(crate) fn push(&mut self, data: &[u8]) -> Result<Bytes, HttpFileError> {
+        self.check_mismatch()?;
+        self.buf.extend_from_slice(data);
+        let chunk_size = self.chunk_size as usize;
+        let mut verified = 0;
+        while self.buf.len() - verified >= chunk_size {
+            if let Err(e) = self.verify(verified..verified + chunk_size) {
+                if verified == 0 {
+                    return Err(e);
+                }
+                self.mismatch = true;
+                break;
+            }
+            verified += chunk_size;
+        }
+        Ok(self.buf.split_to(verified).freeze())
+    }
+
+    /// The last, possibly short, chunk once the response ended.
+    pub(crate) fn finish(&mut self) -> Result<Bytes, HttpFileError> {
+        self.check_mismatch()?;
+        if self.buf.is_empty() {
+            return Ok(Bytes::new());
+        }
+        self.verify(0..self.buf.len())?;
+        Ok(self.buf.split().freeze())
+    }
+
+    fn check_mismatch(&self) -> Result<(), HttpFileError> {
+        if self.mismatch {
+            return Err(HttpFileError::ChunkHashMismatch { index: self.index });
+        }
+        Ok(())
+    }
+
+    fn verify(&mut self, range: std::ops::Range<usize>) -> Result<(), HttpFileError> {
+        let leaf = usize::try_from(self.index)
+            .ok()
+            .and_then(|i| self.tree.leaves.get(i));
+        if leaf != Some(&leaf_hash(&self.buf[range])) {
+            return Err(HttpFileError::ChunkHashMismatch { index: self.index });
+        }
+        self.index += 1;
+        Ok(())
+    }
+}
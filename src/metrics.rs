@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+/// Hooks to record metrics about the requests made by an [`HttpFile`](crate::HttpFile).
+///
+/// Every method has a no-op default, so implementations only override what
+/// they need and bridge it to their metrics backend (Prometheus,
+/// OpenTelemetry, ...). Installed with
+/// [`HttpFileBuilder::with_metrics`](crate::HttpFileBuilder::with_metrics).
+/// The methods are called from within `poll_read`/`poll_complete`, so they
+/// should be cheap and must not block.
+pub trait Metrics: Send + Sync {
+    /// A request got its response headers, or failed, after `latency`.
+    ///
+    /// `status` is `None` when no response was received at all.
+    fn request(
+        &self,
+        _method: &reqwest::Method,
+        _status: Option<reqwest::StatusCode>,
+        _latency: Duration,
+    ) {
+    }
+
+    /// `bytes` of response body were received from the network.
+    fn bytes_received(&self, _bytes: u64) {}
+
+    /// A failed read is retried with a new request.
+    fn retry(&self) {}
+}
@@ -0,0 +1,105 @@
+use std::ops::Range;
+
+use bytes::Bytes;
+
+/// The boundary of a `multipart/byteranges` content type, if it is one.
+pub(crate) fn boundary(content_type: &str) -> Option<String> {
+    let mut params = content_type.split(';');
+    let mime = params.next()?.trim();
+    if !mime.eq_ignore_ascii_case("multipart/byteranges") {
+        return None;
+    }
+    params.find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("boundary")
+            .then(|| value.trim().trim_matches('"').to_string())
+            .filter(|boundary| !boundary.is_empty())
+    })
+}
+
+/// The start and inclusive end of a `Content-Range: bytes start-end/total`.
+pub(crate) fn content_range(value: &str) -> Option<(u64, u64)> {
+    let (range, _total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    let start = start.trim().parse().ok()?;
+    let end = end.trim().parse().ok()?;
+    (start <= end).then_some((start, end))
+}
+
+/// The parts of a `multipart/byteranges` body, each with the offset in the
+/// file it starts at, or `None` if the body is malformed.
+///
+/// A part is as long as its `Content-Range` says, so a body that happens to
+/// contain the boundary doesn't cut it short.
+pub(crate) fn parse(body: &Bytes, boundary: &str) -> Option<Vec<(u64, Bytes)>> {
+    let delimiter = format!("--{}", boundary);
+    let delimiter = delimiter.as_bytes();
+    let mut pos = find(body, delimiter, 0)?;
+    let mut parts = vec![];
+    loop {
+        pos += delimiter.len();
+        if body[pos..].starts_with(b"--") {
+            return Some(parts);
+        }
+        // the rest of the delimiter line, then the headers of the part
+        pos = line_end(body, pos)?;
+        let mut range = None;
+        loop {
+            let end = line_end(body, pos)?;
+            let line = std::str::from_utf8(&body[pos..end]).ok()?.trim();
+            pos = end;
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':')
+                && name.trim().eq_ignore_ascii_case("content-range")
+            {
+                range = content_range(value);
+            }
+        }
+        let (start, end) = range?;
+        let len = usize::try_from(end - start + 1).ok()?;
+        let data_end = pos.checked_add(len).filter(|&n| n <= body.len())?;
+        parts.push((start, body.slice(pos..data_end)));
+        pos = find(body, delimiter, data_end)?;
+    }
+}
+
+/// The answer to a multi-range request.
+pub(crate) enum Answer {
+    /// parts with the offset each starts at, maybe not all of those asked for
+    Parts(Vec<(u64, Bytes)>),
+    /// the whole file, the ranges ignored
+    Whole(Bytes),
+}
+
+/// The bytes of `range` out of `whole`, the whole file, short or empty past
+/// its end.
+pub(crate) fn slice_whole(whole: &Bytes, range: &Range<u64>) -> Bytes {
+    let end = range.end.min(whole.len() as u64) as usize;
+    whole.slice((range.start as usize).min(end)..end)
+}
+
+/// The bytes of `range` out of the first of `parts` covering all of it.
+pub(crate) fn find_range(parts: &[(u64, Bytes)], range: &Range<u64>) -> Option<Bytes> {
+    parts.iter().find_map(|(start, data)| {
+        let offset = range.start.checked_sub(*start)?;
+        let end = offset + (range.end - range.start);
+        (end <= data.len() as u64).then(|| data.slice(offset as usize..end as usize))
+    })
+}
+
+/// Position of the first `needle` in `haystack` from `from`.
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|i| from + i)
+}
+
+/// Position just past the end of the line `pos` is in.
+fn line_end(body: &[u8], pos: usize) -> Option<usize> {
+    find(body, b"\n", pos).map(|i| i + 1)
+}
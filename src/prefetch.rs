@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use futures_util::{StreamExt, future::Either};
+use tokio::sync::{Semaphore, mpsc};
+
+use crate::ResponseStream;
+
+/// Read `stream` ahead on a task of its own, see
+/// [`HttpFile::set_prefetch`](crate::HttpFile::set_prefetch).
+///
+/// Chunks not taken from the returned stream yet hold up to `budget` bytes,
+/// plus the chunk received last, waiting for room. Dropping the returned
+/// stream stops the task and drops `stream`.
+pub(crate) fn prefetch(mut stream: ResponseStream, budget: u64) -> ResponseStream {
+    let budget = u32::try_from(budget).unwrap_or(u32::MAX).max(1);
+    let room = Arc::new(Semaphore::new(budget as usize));
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        loop {
+            let next =
+                match futures_util::future::select(Box::pin(tx.closed()), stream.next()).await {
+                    Either::Left(_) => return,
+                    Either::Right((next, _)) => next,
+                };
+            let Some(item) = next else {
+                return;
+            };
+            // a chunk over the budget takes all of it, and no more
+            let size = item.as_ref().map_or(0, |chunk| {
+                u32::try_from(chunk.len()).unwrap_or(u32::MAX).min(budget)
+            });
+            let Ok(permit) = room.clone().acquire_many_owned(size).await else {
+                return;
+            };
+            let failed = item.is_err();
+            if tx.send((item, permit)).is_err() || failed {
+                return;
+            }
+        }
+    });
+    futures_util::stream::poll_fn(move |cx| {
+        // the room a chunk took is given back as it is taken
+        rx.poll_recv(cx)
+            .map(|next| next.map(|(item, _permit)| item))
+    })
+    .boxed()
+}
@@ -0,0 +1,100 @@
+use std::collections::VecDeque;
+
+use bytes::Bytes;
+
+/// The most recent contiguous bytes received, up to a capacity, kept to
+/// serve reads and seeks back into them, see
+/// [`HttpFileBuilder::with_buffer_size`](crate::HttpFileBuilder::with_buffer_size).
+#[derive(Debug)]
+pub(crate) struct ReadBuffer {
+    /// file offset of the first byte of `chunks`
+    start: u64,
+    chunks: VecDeque<Bytes>,
+    len: usize,
+    capacity: usize,
+}
+
+impl ReadBuffer {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            start: 0,
+            chunks: VecDeque::new(),
+            len: 0,
+            capacity,
+        }
+    }
+
+    /// Offset right after the last byte held.
+    pub(crate) fn end(&self) -> u64 {
+        self.start + self.len as u64
+    }
+
+    /// Keep `chunk`, received for offset `pos`. Bytes that don't follow the
+    /// ones held replace them, and the oldest bytes are dropped once over
+    /// capacity.
+    pub(crate) fn push(&mut self, pos: u64, chunk: Bytes) {
+        if pos != self.end() {
+            self.clear();
+            self.start = pos;
+        }
+        self.len += chunk.len();
+        self.chunks.push_back(chunk);
+        while self.len > self.capacity {
+            let excess = self.len - self.capacity;
+            let front = self.chunks.front_mut().unwrap();
+            let dropped = excess.min(front.len());
+            if dropped == front.len() {
+                self.chunks.pop_front();
+            } else {
+                *front = front.slice(dropped..);
+            }
+            self.start += dropped as u64;
+            self.len -= dropped;
+        }
+    }
+
+    fn clear(&mut self) {
+        self.chunks.clear();
+        self.len = 0;
+    }
+
+    /// The bytes held from `pos` to the end of the chunk they are in, if
+    /// `pos` is within them.
+    pub(crate) fn chunk_at(&self, pos: u64) -> Option<Bytes> {
+        if pos < self.start || pos >= self.end() {
+            return None;
+        }
+        let mut skip = (pos - self.start) as usize;
+        for chunk in &self.chunks {
+            if skip < chunk.len() {
+                return Some(chunk.slice(skip..));
+            }
+            skip -= chunk.len();
+        }
+        None
+    }
+
+    /// Fill `buf` with the bytes at `pos`, if they are all held.
+    pub(crate) fn read_at(&self, pos: u64, buf: &mut [u8]) -> bool {
+        let end = pos.saturating_add(buf.len() as u64);
+        if pos < self.start || end > self.end() {
+            return false;
+        }
+        let mut skip = (pos - self.start) as usize;
+        let mut filled = 0;
+        for chunk in &self.chunks {
+            if filled == buf.len() {
+                break;
+            }
+            if skip >= chunk.len() {
+                skip -= chunk.len();
+                continue;
+            }
+            let n = (chunk.len() - skip).min(buf.len() - filled);
+            buf[filled..filled + n].copy_from_slice(&chunk[skip..skip + n]);
+            filled += n;
+            skip = 0;
+        }
+        true
+    }
+}
@@ -0,0 +1,40 @@
+use reqwest::redirect::Policy;
+
+use crate::HttpFileError;
+
+/// Follow up to `max` redirects, failing with
+/// [`TooManyRedirects`](HttpFileError::TooManyRedirects) past that, or with
+/// [`RedirectLoop`](HttpFileError::RedirectLoop) as soon as a url comes back.
+pub(crate) fn policy(max: usize) -> Policy {
+    Policy::custom(move |attempt| {
+        let previous = attempt.previous();
+        if let Some(first) = previous.iter().position(|url| url == attempt.url()) {
+            let cycle = previous[first..]
+                .iter()
+                .chain([attempt.url()])
+                .map(ToString::to_string)
+                .collect();
+            return attempt.error(HttpFileError::RedirectLoop { cycle });
+        }
+        if previous.len() > max {
+            return attempt.error(HttpFileError::TooManyRedirects { limit: max });
+        }
+        attempt.follow()
+    })
+}
+
+/// The error a redirect policy from [`policy`] stopped `e` with, if it did.
+pub(crate) fn error(e: &reqwest::Error) -> Option<HttpFileError> {
+    if !e.is_redirect() {
+        return None;
+    }
+    match std::error::Error::source(e)?.downcast_ref::<HttpFileError>()? {
+        HttpFileError::RedirectLoop { cycle } => Some(HttpFileError::RedirectLoop {
+            cycle: cycle.clone(),
+        }),
+        HttpFileError::TooManyRedirects { limit } => {
+            Some(HttpFileError::TooManyRedirects { limit: *limit })
+        }
+        _ => None,
+    }
+}
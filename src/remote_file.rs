@@ -0,0 +1,104 @@
+use tokio::io::{AsyncRead, AsyncSeek};
+
+use crate::{AnyFile, HttpFile, HttpFileSlice, LocalFile};
+
+/// A seekable byte source, whatever serves it, so code reading files can be
+/// written once for [`HttpFile`] and the other sources alike, and for
+/// backends implemented outside of this crate.
+pub trait RemoteFile: AsyncRead + AsyncSeek {
+    /// length of the file in bytes, if known
+    fn content_length(&self) -> Option<u64>;
+
+    /// etag of the file, if any
+    fn etag(&self) -> Option<&str> {
+        None
+    }
+
+    /// mime type of the file, if known
+    fn mime(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl<F: RemoteFile + Unpin + ?Sized> RemoteFile for Box<F> {
+    fn content_length(&self) -> Option<u64> {
+        (**self).content_length()
+    }
+
+    fn etag(&self) -> Option<&str> {
+        (**self).etag()
+    }
+
+    fn mime(&self) -> Option<&str> {
+        (**self).mime()
+    }
+}
+
+impl<F: RemoteFile + Unpin + ?Sized> RemoteFile for &mut F {
+    fn content_length(&self) -> Option<u64> {
+        (**self).content_length()
+    }
+
+    fn etag(&self) -> Option<&str> {
+        (**self).etag()
+    }
+
+    fn mime(&self) -> Option<&str> {
+        (**self).mime()
+    }
+}
+
+impl RemoteFile for HttpFile {
+    fn content_length(&self) -> Option<u64> {
+        HttpFile::content_length(self)
+    }
+
+    fn etag(&self) -> Option<&str> {
+        HttpFile::etag(self)
+    }
+
+    fn mime(&self) -> Option<&str> {
+        HttpFile::mime(self)
+    }
+}
+
+impl RemoteFile for HttpFileSlice {
+    fn content_length(&self) -> Option<u64> {
+        Some(self.len())
+    }
+
+    fn etag(&self) -> Option<&str> {
+        self.file.etag()
+    }
+
+    fn mime(&self) -> Option<&str> {
+        self.file.mime()
+    }
+}
+
+impl RemoteFile for LocalFile {
+    fn content_length(&self) -> Option<u64> {
+        Some(LocalFile::content_length(self))
+    }
+
+    fn mime(&self) -> Option<&str> {
+        LocalFile::mime(self)
+    }
+}
+
+impl RemoteFile for AnyFile {
+    fn content_length(&self) -> Option<u64> {
+        AnyFile::content_length(self)
+    }
+
+    fn etag(&self) -> Option<&str> {
+        match self {
+            Self::Http(file) => file.etag(),
+            Self::Local(_) => None,
+        }
+    }
+
+    fn mime(&self) -> Option<&str> {
+        AnyFile::mime(self)
+    }
+}
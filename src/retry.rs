@@ -0,0 +1,104 @@
+use std::{
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use reqwest::StatusCode;
+
+/// What to do when a request is answered with a given status, see
+/// [`HttpFileBuilder::with_status_retry_policy`](crate::HttpFileBuilder::with_status_retry_policy).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryAction {
+    /// Retry after the backoff delay.
+    Retry,
+    /// Retry once the delay in the `Retry-After` header has passed, or after
+    /// the backoff delay when there is no such header or it can't be parsed.
+    /// The delay is capped at 5 minutes, or the maximum backoff delay if
+    /// longer.
+    RetryAfter,
+    /// Surface the error from the read.
+    Fail,
+}
+
+/// Decides whether a failed request is retried, and after how long, see
+/// [`HttpFileBuilder::with_retry_policy`](crate::HttpFileBuilder::with_retry_policy).
+///
+/// It is asked about every failure: error statuses, timeouts, connection
+/// errors and responses dropped mid-body alike. The number of attempts is
+/// still bounded by [`retries`](crate::HttpFileBuilder::retries).
+pub trait RetryPolicy: Send + Sync {
+    /// Retry after the returned delay, or give up with `err` on `None`.
+    /// `attempt` is the number of retries already made since the last
+    /// successful read, starting at 0.
+    fn should_retry(&self, err: &reqwest::Error, attempt: u8) -> Option<Duration>;
+}
+
+/// Action taken when no policy is set, or the status isn't listed in it.
+pub(crate) fn default_action(status: StatusCode) -> RetryAction {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => RetryAction::RetryAfter,
+        _ if status.is_server_error() => RetryAction::Retry,
+        _ => RetryAction::Fail,
+    }
+}
+
+/// Longest wait a `Retry-After` header is followed for, unless the backoff
+/// allows longer, so a bogus value can't park a reader for years.
+pub(crate) const MAX_RETRY_AFTER: Duration = Duration::from_secs(5 * 60);
+
+/// The delay asked for by a `Retry-After` header, given either in seconds or
+/// as an HTTP date. A date in the past means no delay.
+pub(crate) fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(secs) = value.parse() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(std::time::SystemTime::now())
+            .unwrap_or_default(),
+    )
+}
+
+/// Delay before each retry, see
+/// [`HttpFileBuilder::with_backoff`](crate::HttpFileBuilder::with_backoff).
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Backoff {
+    pub(crate) base: Duration,
+    pub(crate) multiplier: f64,
+    pub(crate) max: Duration,
+    pub(crate) jitter: bool,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            base: Duration::from_millis(200),
+            multiplier: 2.0,
+            max: Duration::from_secs(5),
+            jitter: true,
+        }
+    }
+}
+
+impl Backoff {
+    /// Delay before the retry following `retried` earlier ones.
+    pub(crate) fn delay(&self, retried: u8) -> Duration {
+        let factor = self.multiplier.max(1.0).powi(retried.into());
+        let delay = Duration::try_from_secs_f64(self.base.as_secs_f64() * factor)
+            .unwrap_or(self.max)
+            .min(self.max);
+        if !self.jitter {
+            return delay;
+        }
+        // anywhere in the upper half, so concurrent clients spread out
+        let random = std::collections::hash_map::RandomState::new()
+            .build_hasher()
+            .finish();
+        delay.mul_f64(0.5 + (random as f64 / u64::MAX as f64) / 2.0)
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    io::{Error, ErrorKind, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncSeek, ReadBuf};
+
+use crate::HttpFile;
+
+/// A reader over a byte range of an [`HttpFile`], see [`HttpFile::slice`]
+/// and [`HttpFile::split`].
+///
+/// Positions are relative to the start of the range, reads find EOF at its
+/// end and seeks outside of it fail. Range requests stop at the end of the
+/// range too.
+#[derive(Debug)]
+pub struct HttpFileSlice {
+    pub(crate) file: HttpFile,
+    start: u64,
+    end: u64,
+}
+
+impl HttpFileSlice {
+    /// Cover `range` of the file with a cursor of its own at its start.
+    fn new(file: &HttpFile, range: Range<u64>) -> Self {
+        let mut file = file.fork();
+        file.pos = range.start;
+        if !range.is_empty() {
+            let last = range.end - 1;
+            file.options.open_range_end = Some(
+                file.options
+                    .open_range_end
+                    .map_or(last, |end| end.min(last)),
+            );
+        }
+        Self {
+            file,
+            start: range.start,
+            end: range.end,
+        }
+    }
+
+    /// The range of the file covered.
+    pub fn range(&self) -> Range<u64> {
+        self.start..self.end
+    }
+
+    /// Number of bytes covered.
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Whether the slice covers no bytes at all.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+impl HttpFile {
+    /// A reader over `range` of the file only, as if it were a file of its
+    /// own, e.g. for one resource inside a concatenated archive.
+    ///
+    /// Position 0 of the slice is `range.start` in the file, and reads end
+    /// at `range.end`, or earlier at the end of the file. The slice has a
+    /// cursor of its own and shares what is known about the file, without
+    /// any request.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range.start` is greater than `range.end`.
+    pub fn slice(&self, range: Range<u64>) -> HttpFileSlice {
+        assert!(range.start <= range.end, "slice starts after its end");
+        HttpFileSlice::new(self, range)
+    }
+
+    /// Split the file into `n` slices of about the same size, each an
+    /// independent reader over its contiguous part, e.g. to process them in
+    /// parallel.
+    ///
+    /// Slices start at position 0 of their part and share what is known
+    /// about the file, without any request. The length of the file must be
+    /// known. When it is smaller than `n`, the last slices are empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn split(&self, n: usize) -> std::io::Result<Vec<HttpFileSlice>> {
+        assert!(n > 0, "number of slices must be positive");
+        let len = self.content_length.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "cannot split without known content length",
+            )
+        })?;
+        let n = n as u64;
+        let (size, rest) = (len / n, len % n);
+        // the first `rest` slices take one more byte each
+        let bounds = (0..=n).map(|i| i * size + i.min(rest));
+        let starts = bounds.clone();
+        Ok(starts
+            .zip(bounds.skip(1))
+            .map(|(start, end)| HttpFileSlice::new(self, start..end))
+            .collect())
+    }
+}
+
+impl AsyncBufRead for HttpFileSlice {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<&[u8]>> {
+        let this = self.get_mut();
+        let remaining = this.end.saturating_sub(this.file.pos);
+        if remaining == 0 {
+            return Poll::Ready(Ok(&[]));
+        }
+        let chunk = ready!(Pin::new(&mut this.file).poll_fill_buf(cx))?;
+        let size = chunk.len().min(remaining.try_into().unwrap_or(usize::MAX));
+        Poll::Ready(Ok(&chunk[..size]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        Pin::new(&mut self.get_mut().file).consume(amt);
+    }
+}
+
+impl AsyncRead for HttpFileSlice {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let chunk = ready!(self.as_mut().poll_fill_buf(cx))?;
+        let size = chunk.len().min(buf.remaining());
+        buf.put_slice(&chunk[..size]);
+        self.consume(size);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncSeek for HttpFileSlice {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        let target = match position {
+            SeekFrom::Start(n) => self.start.checked_add(n),
+            SeekFrom::End(n) => self.end.checked_add_signed(n),
+            SeekFrom::Current(n) => self.file.pos.checked_add_signed(n),
+        };
+        let target = target
+            .filter(|target| self.range().contains(target) || *target == self.end)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "seek outside of the slice"))?;
+        Pin::new(&mut self.file).start_seek(SeekFrom::Start(target))
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        let pos = ready!(Pin::new(&mut self.file).poll_complete(cx))?;
+        Poll::Ready(Ok(pos - self.start))
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// What an [`HttpFile`](crate::HttpFile) did so far, see
+/// [`HttpFile::stats`](crate::HttpFile::stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HttpFileStats {
+    /// `GET` requests sent, retries included, not counting the `HEAD` made
+    /// when opening
+    pub requests: u64,
+    /// bytes of response body received from the network
+    pub bytes_received: u64,
+    /// bytes handed out through `AsyncRead` and `AsyncBufRead`
+    pub bytes_read: u64,
+    /// failed requests and interrupted responses that were retried
+    pub retries: u64,
+    /// responses opened after the first one, to seek or to resume
+    pub reconnects: u64,
+    /// times reads moved on to the next mirror
+    pub failovers: u64,
+    /// index of the mirror reads come from, in the order given, 0 without
+    /// mirrors
+    pub mirror: usize,
+}
+
+/// Running counts behind [`HttpFileStats`], shared with the temporary forks
+/// that serve positioned reads.
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    requests: AtomicU64,
+    bytes_received: AtomicU64,
+    bytes_read: AtomicU64,
+    retries: AtomicU64,
+    failovers: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) fn request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn received(&self, bytes: u64) {
+        self.bytes_received.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn read(&self, bytes: u64) {
+        self.bytes_read.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn retry(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn fail_over(&self) {
+        self.failovers.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self, connections_opened: u64, mirror: usize) -> HttpFileStats {
+        HttpFileStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            reconnects: connections_opened.saturating_sub(1),
+            failovers: self.failovers.load(Ordering::Relaxed),
+            mirror,
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::task::{Context, Poll, ready};
+
+use futures_util::FutureExt;
+use tokio::time::{Instant, Sleep};
+
+/// A token bucket capping the rate bytes are delivered at, see
+/// [`HttpFile::set_max_rate`](crate::HttpFile::set_max_rate).
+///
+/// Delivered bytes are taken out after the fact and may leave the bucket in
+/// debt, which the next read waits out.
+#[derive(Debug)]
+pub(crate) struct Throttle {
+    /// bytes per second
+    rate: u64,
+    /// most bytes the bucket holds, delivered at once after an idle period
+    burst: u64,
+    tokens: f64,
+    refilled: Instant,
+    wait: Option<std::pin::Pin<Box<Sleep>>>,
+}
+
+impl Throttle {
+    pub(crate) fn new(rate: u64, burst: u64) -> Self {
+        Self {
+            rate,
+            burst,
+            tokens: burst as f64,
+            refilled: Instant::now(),
+            wait: None,
+        }
+    }
+
+    pub(crate) fn set_burst(&mut self, burst: u64) {
+        self.burst = burst;
+        self.tokens = self.tokens.min(burst as f64);
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = (now - self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate as f64).min(self.burst as f64);
+        self.refilled = now;
+    }
+
+    /// Take `bytes` just delivered out of the bucket.
+    pub(crate) fn consume(&mut self, bytes: usize) {
+        self.refill();
+        self.tokens -= bytes as f64;
+    }
+
+    /// Wait until the bucket is out of debt.
+    pub(crate) fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        loop {
+            if let Some(wait) = self.wait.as_mut() {
+                ready!(wait.poll_unpin(cx));
+                self.wait = None;
+            }
+            self.refill();
+            if self.tokens >= 0.0 {
+                return Poll::Ready(());
+            }
+            let debt = std::time::Duration::from_secs_f64(-self.tokens / self.rate as f64);
+            self.wait = Some(Box::pin(tokio::time::sleep(debt)));
+        }
+    }
+}
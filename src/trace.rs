@@ -0,0 +1,93 @@
+//! `tracing` instrumentation of range requests, behind the `tracing`
+//! feature. Without it everything here compiles down to nothing.
+
+use std::time::Duration;
+
+/// The span of one range request, recording the response status and the
+/// bytes delivered from it when it closes.
+#[derive(Debug, Default)]
+pub(crate) struct RequestSpan {
+    #[cfg(feature = "tracing")]
+    span: Option<(tracing::Span, u64)>,
+}
+
+#[cfg(feature = "tracing")]
+impl RequestSpan {
+    /// Open the span of a request for `url` from `start`, the `attempt`th
+    /// at that position.
+    pub(crate) fn new(url: &reqwest::Url, start: u64, attempt: u8) -> Self {
+        let span = tracing::debug_span!(
+            "range_request",
+            url = %url,
+            start,
+            attempt,
+            status = tracing::field::Empty,
+            bytes = tracing::field::Empty,
+        );
+        Self {
+            span: Some((span, 0)),
+        }
+    }
+
+    pub(crate) fn status(&self, status: reqwest::StatusCode) {
+        if let Some((span, _)) = &self.span {
+            span.record("status", status.as_u16());
+        }
+    }
+
+    pub(crate) fn delivered(&mut self, bytes: usize) {
+        if let Some((_, delivered)) = self.span.as_mut() {
+            *delivered += bytes as u64;
+        }
+    }
+
+    /// Record the bytes delivered and close the span.
+    pub(crate) fn close(&mut self) {
+        if let Some((span, delivered)) = self.span.take() {
+            span.record("bytes", delivered);
+        }
+    }
+
+    pub(crate) fn reconnect(&self, pos: u64) {
+        let _entered = self.span.as_ref().map(|(span, _)| span.enter());
+        tracing::debug!(pos, "reconnecting");
+    }
+
+    pub(crate) fn retry(&self, pos: u64, attempts_left: u8, delay: Duration) {
+        let _entered = self.span.as_ref().map(|(span, _)| span.enter());
+        tracing::warn!(pos, attempts_left, ?delay, "retrying");
+    }
+
+    /// Note that the response reached EOF at `pos`, and close the span.
+    pub(crate) fn eof(&mut self, pos: u64) {
+        if let Some((span, delivered)) = self.span.take() {
+            span.in_scope(|| tracing::debug!(pos, "end of response"));
+            span.record("bytes", delivered);
+        }
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+impl RequestSpan {
+    pub(crate) fn new(_url: &reqwest::Url, _start: u64, _attempt: u8) -> Self {
+        Self {}
+    }
+
+    pub(crate) fn status(&self, _status: reqwest::StatusCode) {}
+
+    pub(crate) fn delivered(&mut self, _bytes: usize) {}
+
+    pub(crate) fn close(&mut self) {}
+
+    pub(crate) fn reconnect(&self, _pos: u64) {}
+
+    pub(crate) fn retry(&self, _pos: u64, _attempts_left: u8, _delay: Duration) {}
+
+    pub(crate) fn eof(&mut self, _pos: u64) {}
+}
+
+impl Drop for RequestSpan {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
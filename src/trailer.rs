@@ -0,0 +1,126 @@
+use std::sync::{Arc, Mutex};
+
+use base64::Engine;
+use futures_util::StreamExt;
+use http_body_util::BodyExt;
+use md5::Digest;
+use reqwest::header::{CONTENT_LENGTH, ETAG, HeaderMap, HeaderName, TRAILER};
+
+use crate::{HttpFileError, ResponseStream, header_string};
+
+const CONTENT_MD5: HeaderName = HeaderName::from_static("content-md5");
+
+/// The trailer fields of a response read to the end, and what was received.
+#[derive(Debug)]
+pub(crate) struct Trailer {
+    fields: HeaderMap,
+    /// body bytes received
+    len: u64,
+    /// base64 MD5 of the body, only computed when `Content-MD5` is announced
+    md5: Option<String>,
+}
+
+/// Filled in by the body stream once it ends.
+pub(crate) type TrailerSlot = Arc<Mutex<Option<Trailer>>>;
+
+/// Stream the body of `resp`, recording its trailer in the returned slot at the end.
+pub(crate) fn body_stream(resp: reqwest::Response) -> (ResponseStream, TrailerSlot) {
+    let announced_md5 = resp
+        .headers()
+        .get_all(TRAILER)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|name| name.trim().eq_ignore_ascii_case(CONTENT_MD5.as_str()));
+
+    let slot = TrailerSlot::default();
+    let state = BodyState {
+        body: http::Response::from(resp).into_body(),
+        fields: HeaderMap::new(),
+        len: 0,
+        hasher: announced_md5.then(md5::Md5::new),
+        slot: slot.clone(),
+    };
+    let stream = futures_util::stream::unfold(Some(state), |state| async move {
+        let mut state = state?;
+        loop {
+            let frame = match state.body.frame().await {
+                Some(Ok(frame)) => frame,
+                Some(Err(e)) => return Some((Err(e), None)),
+                None => {
+                    state.finish();
+                    return None;
+                }
+            };
+            match frame.into_data() {
+                Ok(data) => {
+                    state.len += data.len() as u64;
+                    if let Some(hasher) = &mut state.hasher {
+                        hasher.update(&data);
+                    }
+                    return Some((Ok(data), Some(state)));
+                }
+                Err(frame) => {
+                    if let Ok(fields) = frame.into_trailers() {
+                        state.fields.extend(fields);
+                    }
+                }
+            }
+        }
+    });
+    (stream.fuse().boxed(), slot)
+}
+
+struct BodyState {
+    body: reqwest::Body,
+    fields: HeaderMap,
+    len: u64,
+    hasher: Option<md5::Md5>,
+    slot: TrailerSlot,
+}
+
+impl BodyState {
+    /// Hand the trailer over once the body has ended.
+    fn finish(self) {
+        let engine = base64::engine::general_purpose::STANDARD;
+        *self.slot.lock().unwrap() = Some(Trailer {
+            fields: self.fields,
+            len: self.len,
+            md5: self.hasher.map(|hasher| engine.encode(hasher.finalize())),
+        });
+    }
+}
+
+impl Trailer {
+    /// Check the body received against the trailer, returning the body
+    /// length it confirms, if any.
+    pub(crate) fn validate(&self) -> Result<Option<u64>, HttpFileError> {
+        if let (Some(expected), Some(actual)) =
+            (header_string(&self.fields, CONTENT_MD5), &self.md5)
+            && expected.trim() != actual
+        {
+            return Err(HttpFileError::TrailerMismatch {
+                name: CONTENT_MD5.to_string(),
+                expected,
+                actual: actual.clone(),
+            });
+        }
+
+        let Some(expected) = header_string(&self.fields, CONTENT_LENGTH) else {
+            return Ok(None);
+        };
+        match expected.trim().parse::<u64>() {
+            Ok(len) if len == self.len => Ok(Some(len)),
+            _ => Err(HttpFileError::TrailerMismatch {
+                name: CONTENT_LENGTH.to_string(),
+                expected,
+                actual: self.len.to_string(),
+            }),
+        }
+    }
+
+    /// The etag sent in the trailer, if any.
+    pub(crate) fn etag(&self) -> Option<String> {
+        header_string(&self.fields, ETAG)
+    }
+}
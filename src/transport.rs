@@ -0,0 +1,17 @@
+use futures_util::future::BoxFuture;
+
+/// Sends the requests of an [`HttpFile`](crate::HttpFile), see
+/// [`HttpFileBuilder::with_transport`](crate::HttpFileBuilder::with_transport).
+///
+/// Every request goes through it, the `HEAD` made when opening and every
+/// range `GET` alike, so it is the place for middleware such as refreshing
+/// an expired token and sending the request again on a `401`, or logging.
+pub trait Transport: Send + Sync {
+    /// Send `request`, built for `client`, and return its response.
+    /// Plain `client.execute(request)` is what happens without a transport.
+    fn execute(
+        &self,
+        client: &reqwest::Client,
+        request: reqwest::Request,
+    ) -> BoxFuture<'static, reqwest::Result<reqwest::Response>>;
+}
@@ -0,0 +1,204 @@
+use std::io::{Error, ErrorKind};
+
+use crate::HttpFile;
+
+const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+const EOCD_LEN: usize = 22;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x0706_4b50;
+const ZIP64_LOCATOR_LEN: usize = 20;
+const ZIP64_EOCD_SIGNATURE: u32 = 0x0606_4b50;
+const ZIP64_EOCD_LEN: usize = 56;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const CENTRAL_HEADER_LEN: usize = 46;
+const ZIP64_EXTRA_ID: u16 = 0x0001;
+/// the archive comment is at most `u16::MAX` bytes
+const MAX_TAIL: u64 = (ZIP64_LOCATOR_LEN + EOCD_LEN + u16::MAX as usize) as u64;
+
+/// An entry listed in the central directory of a ZIP archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ZipEntry {
+    /// path of the entry within the archive, directories end with `/`
+    pub name: String,
+    /// compression method, e.g. `0` for stored and `8` for deflate
+    pub compression_method: u16,
+    /// CRC-32 of the uncompressed data
+    pub crc32: u32,
+    /// size of the data as stored in the archive
+    pub compressed_size: u64,
+    /// size of the data once decompressed
+    pub uncompressed_size: u64,
+    /// offset of the entry's local header from the start of the archive
+    pub local_header_offset: u64,
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("invalid zip archive: {}", msg),
+    )
+}
+
+fn u16_at(buf: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes(buf[at..at + 2].try_into().unwrap())
+}
+
+fn u32_at(buf: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(buf[at..at + 4].try_into().unwrap())
+}
+
+fn u64_at(buf: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(buf[at..at + 8].try_into().unwrap())
+}
+
+/// Where the central directory is, and how many entries it lists.
+struct Directory {
+    offset: u64,
+    size: u64,
+    entries: u64,
+}
+
+impl HttpFile {
+    /// List the entries of a remote ZIP archive without downloading it.
+    ///
+    /// The end of central directory record is located by scanning backwards
+    /// over a bounded suffix of the file, which also covers the archive
+    /// comment, then the central directory itself is fetched and parsed.
+    /// ZIP64 archives are supported. This takes two or three range requests,
+    /// made on an independent cursor so the position of `self` is unchanged.
+    ///
+    /// The content length must be known. Names are decoded as UTF-8, with
+    /// invalid sequences replaced.
+    pub async fn zip_central_directory(&self) -> std::io::Result<Vec<ZipEntry>> {
+        let len = self.content_length().ok_or_else(|| {
+            Error::new(
+                ErrorKind::Unsupported,
+                "listing a zip archive needs a known content length",
+            )
+        })?;
+        let mut reader = self.fork();
+
+        let tail_start = len.saturating_sub(MAX_TAIL);
+        let mut tail = vec![0u8; (len - tail_start) as usize];
+        reader.read_exact_at(tail_start, &mut tail).await?;
+        let eocd = find_eocd(&tail).ok_or_else(|| invalid("end of central directory not found"))?;
+
+        let mut directory = Directory {
+            entries: u16_at(&tail, eocd + 10) as u64,
+            size: u32_at(&tail, eocd + 12) as u64,
+            offset: u32_at(&tail, eocd + 16) as u64,
+        };
+        if let Some(locator) = eocd.checked_sub(ZIP64_LOCATOR_LEN)
+            && u32_at(&tail, locator) == ZIP64_LOCATOR_SIGNATURE
+        {
+            let record_offset = u64_at(&tail, locator + 8);
+            if record_offset
+                .checked_add(ZIP64_EOCD_LEN as u64)
+                .is_none_or(|end| end > len)
+            {
+                return Err(invalid("zip64 end of central directory out of bounds"));
+            }
+            let mut record = [0u8; ZIP64_EOCD_LEN];
+            reader.read_exact_at(record_offset, &mut record).await?;
+            if u32_at(&record, 0) != ZIP64_EOCD_SIGNATURE {
+                return Err(invalid("bad zip64 end of central directory signature"));
+            }
+            directory = Directory {
+                entries: u64_at(&record, 32),
+                size: u64_at(&record, 40),
+                offset: u64_at(&record, 48),
+            };
+        }
+
+        // the size is untrusted, only allocate what the file can hold
+        let available = len
+            .checked_sub(directory.offset)
+            .ok_or_else(|| invalid("central directory out of bounds"))?;
+        if directory
+            .offset
+            .checked_add(directory.size)
+            .is_none_or(|end| end > len)
+            || directory.size > available
+        {
+            return Err(invalid("central directory out of bounds"));
+        }
+        let size =
+            usize::try_from(directory.size).map_err(|_| invalid("central directory too large"))?;
+        let mut central = vec![0u8; size];
+        reader.read_exact_at(directory.offset, &mut central).await?;
+        parse_central_directory(&central, directory.entries)
+    }
+}
+
+/// Offset of the end of central directory record in `tail`, the end of the file.
+fn find_eocd(tail: &[u8]) -> Option<usize> {
+    (0..=tail.len().checked_sub(EOCD_LEN)?).rev().find(|&at| {
+        let comment_len = u16_at(tail, at + 20) as usize;
+        u32_at(tail, at) == EOCD_SIGNATURE && at + EOCD_LEN + comment_len == tail.len()
+    })
+}
+
+fn parse_central_directory(central: &[u8], entries: u64) -> std::io::Result<Vec<ZipEntry>> {
+    // don't trust `entries` for the allocation, each takes at least a header
+    let mut list = Vec::with_capacity((entries as usize).min(central.len() / CENTRAL_HEADER_LEN));
+    let mut at = 0;
+    for _ in 0..entries {
+        let header = central
+            .get(at..at + CENTRAL_HEADER_LEN)
+            .ok_or_else(|| invalid("truncated central directory"))?;
+        if u32_at(header, 0) != CENTRAL_HEADER_SIGNATURE {
+            return Err(invalid("bad central directory header signature"));
+        }
+        let name_len = u16_at(header, 28) as usize;
+        let extra_len = u16_at(header, 30) as usize;
+        let comment_len = u16_at(header, 32) as usize;
+        let name_start = at + CENTRAL_HEADER_LEN;
+        let extra_start = name_start + name_len;
+        let next = extra_start + extra_len + comment_len;
+        if next > central.len() {
+            return Err(invalid("truncated central directory"));
+        }
+
+        let mut entry = ZipEntry {
+            name: String::from_utf8_lossy(&central[name_start..extra_start]).into_owned(),
+            compression_method: u16_at(header, 10),
+            crc32: u32_at(header, 16),
+            compressed_size: u32_at(header, 20) as u64,
+            uncompressed_size: u32_at(header, 24) as u64,
+            local_header_offset: u32_at(header, 42) as u64,
+        };
+        apply_zip64_extra(&mut entry, &central[extra_start..extra_start + extra_len])?;
+        list.push(entry);
+        at = next;
+    }
+    Ok(list)
+}
+
+/// Replace the saturated 32-bit fields of `entry` with their values from the
+/// ZIP64 extra field, which lists only those, in this order.
+fn apply_zip64_extra(entry: &mut ZipEntry, mut extra: &[u8]) -> std::io::Result<()> {
+    while extra.len() >= 4 {
+        let id = u16_at(extra, 0);
+        let size = u16_at(extra, 2) as usize;
+        let data = extra
+            .get(4..4 + size)
+            .ok_or_else(|| invalid("truncated extra field"))?;
+        if id == ZIP64_EXTRA_ID {
+            let mut values = data.chunks_exact(8).map(|v| u64_at(v, 0));
+            for field in [
+                &mut entry.uncompressed_size,
+                &mut entry.compressed_size,
+                &mut entry.local_header_offset,
+            ] {
+                if *field == u32::MAX as u64 {
+                    *field = values
+                        .next()
+                        .ok_or_else(|| invalid("truncated zip64 extra field"))?;
+                }
+            }
+            return Ok(());
+        }
+        extra = &extra[4 + size..];
+    }
+    Ok(())
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use std::{
+    io::SeekFrom,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use bytes::BytesMut;
+use common::{MockFile, random_bytes};
+use remote_file::{BufferAllocator, HttpFile, HttpFileBuilder};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Hands out the buffers given back to it again, counting the calls, the
+/// bytes asked for, the buffers released and those handed out again.
+#[derive(Default)]
+struct PoolAllocator {
+    free: Mutex<Vec<BytesMut>>,
+    calls: AtomicUsize,
+    bytes: AtomicUsize,
+    released: AtomicUsize,
+    reused: AtomicUsize,
+}
+
+impl BufferAllocator for PoolAllocator {
+    fn allocate(&self, capacity: usize) -> BytesMut {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.bytes.fetch_add(capacity, Ordering::SeqCst);
+        let mut free = self.free.lock().unwrap();
+        match free.iter().position(|buf| buf.capacity() >= capacity) {
+            Some(i) => {
+                self.reused.fetch_add(1, Ordering::SeqCst);
+                let mut buf = free.swap_remove(i);
+                buf.clear();
+                buf
+            }
+            None => BytesMut::with_capacity(capacity),
+        }
+    }
+
+    fn release(&self, buf: BytesMut) {
+        self.released.fetch_add(1, Ordering::SeqCst);
+        self.free.lock().unwrap().push(buf);
+    }
+}
+
+async fn open(data: &[u8], builder: HttpFileBuilder) -> (HttpFile, Arc<PoolAllocator>) {
+    let url = MockFile::new(data.to_vec()).serve().await;
+    let allocator = Arc::new(PoolAllocator::default());
+    let file = builder
+        .with_allocator(allocator.clone())
+        .build(&url)
+        .await
+        .unwrap();
+    (file, allocator)
+}
+
+#[tokio::test]
+async fn block_cache_allocates_its_blocks() {
+    let data = random_bytes(64 * 1024);
+    let (mut file, allocator) = open(&data, HttpFile::builder().with_block_cache(4096, 4)).await;
+
+    let mut buf = vec![0u8; 100];
+    file.seek(SeekFrom::Start(5000)).await.unwrap();
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[5000..5100]);
+    assert_eq!(allocator.calls.load(Ordering::SeqCst), 1);
+    assert_eq!(allocator.bytes.load(Ordering::SeqCst), 4096);
+
+    // served from the cached block
+    file.seek(SeekFrom::Start(4096)).await.unwrap();
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[4096..4196]);
+    assert_eq!(allocator.calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn evicted_blocks_are_released_and_reused() {
+    let data = random_bytes(64 * 1024);
+    let (mut file, allocator) = open(&data, HttpFile::builder().with_block_cache(4096, 1)).await;
+
+    let mut buf = vec![0u8; 100];
+    for pos in [0, 8192, 16384, 24576] {
+        file.seek(SeekFrom::Start(pos)).await.unwrap();
+        file.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, data[pos as usize..pos as usize + 100]);
+    }
+    assert_eq!(allocator.calls.load(Ordering::SeqCst), 4);
+    // each block is given back once evicted, and the next but one takes it
+    assert!(allocator.released.load(Ordering::SeqCst) >= 2);
+    assert!(allocator.reused.load(Ordering::SeqCst) >= 2);
+}
+
+#[tokio::test]
+async fn coalesced_read_buffers_come_back() {
+    let data = random_bytes(64 * 1024);
+    let (mut file, allocator) =
+        open(&data, HttpFile::builder().with_request_coalescing(true)).await;
+
+    let mut buf = vec![0u8; 1000];
+    file.read_exact_at(5000, &mut buf).await.unwrap();
+    assert_eq!(buf, data[5000..6000]);
+    assert_eq!(allocator.calls.load(Ordering::SeqCst), 1);
+    assert_eq!(allocator.bytes.load(Ordering::SeqCst), 1000);
+    assert_eq!(allocator.released.load(Ordering::SeqCst), 1);
+
+    file.read_exact_at(9000, &mut buf).await.unwrap();
+    assert_eq!(buf, data[9000..10_000]);
+    assert_eq!(allocator.reused.load(Ordering::SeqCst), 1);
+    assert_eq!(allocator.released.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn received_chunks_are_not_copied() {
+    let data = random_bytes(64 * 1024);
+    let builder = HttpFile::builder()
+        .with_prefetch(16 * 1024)
+        .with_buffer_size(8 * 1024);
+    let (mut file, allocator) = open(&data, builder).await;
+
+    let mut buf = vec![0u8; 1000];
+    file.read_exact(&mut buf).await.unwrap();
+    // seeking back is served out of the chunks the read buffer holds
+    file.seek(SeekFrom::Start(200)).await.unwrap();
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[200..1200]);
+    let mut rest = vec![];
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[1200..]);
+    assert_eq!(allocator.calls.load(Ordering::SeqCst), 0);
+}
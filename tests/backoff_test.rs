@@ -0,0 +1,108 @@
+mod common;
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Router,
+    http::{Method, StatusCode, header},
+    response::IntoResponse,
+    routing::any,
+};
+use remote_file::HttpFile;
+use tokio::io::AsyncReadExt;
+
+const DATA: &[u8] = b"eventually served";
+
+/// Fail the first `failures` `GET`s with `503`, then serve the file.
+async fn serve_flaky(failures: usize) -> (String, Arc<AtomicUsize>) {
+    let gets = Arc::new(AtomicUsize::new(0));
+    let counter = gets.clone();
+    let app = Router::new().route(
+        "/file",
+        any(move |method: Method| {
+            let counter = counter.clone();
+            async move {
+                if method == Method::HEAD {
+                    return [(header::CONTENT_LENGTH, DATA.len().to_string())].into_response();
+                }
+                if counter.fetch_add(1, Ordering::SeqCst) < failures {
+                    return StatusCode::SERVICE_UNAVAILABLE.into_response();
+                }
+                DATA.into_response()
+            }
+        }),
+    );
+    let addr = common::serve(app).await;
+    (format!("http://{}/file", addr), gets)
+}
+
+/// Read the file through `builder`, returning how long it took.
+async fn timed_read(url: &str, builder: remote_file::HttpFileBuilder) -> Duration {
+    let mut file = builder.build(url).await.unwrap();
+    let start = Instant::now();
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, DATA);
+    start.elapsed()
+}
+
+#[tokio::test]
+async fn delays_grow_between_retries() {
+    let (url, gets) = serve_flaky(3).await;
+    let builder = HttpFile::builder().with_backoff(
+        Duration::from_millis(100),
+        2.0,
+        Duration::from_secs(5),
+        false,
+    );
+    let elapsed = timed_read(&url, builder).await;
+    // 100 + 200 + 400
+    assert!(elapsed >= Duration::from_millis(700), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(1500), "{elapsed:?}");
+    assert_eq!(gets.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn delays_are_capped() {
+    let (url, _) = serve_flaky(3).await;
+    let builder = HttpFile::builder().with_backoff(
+        Duration::from_millis(100),
+        10.0,
+        Duration::from_millis(150),
+        false,
+    );
+    let elapsed = timed_read(&url, builder).await;
+    // 100 + 150 + 150
+    assert!(elapsed >= Duration::from_millis(400), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(1000), "{elapsed:?}");
+}
+
+#[tokio::test]
+async fn jitter_stays_within_the_delay() {
+    let (url, _) = serve_flaky(2).await;
+    let builder = HttpFile::builder().with_backoff(
+        Duration::from_millis(200),
+        1.0,
+        Duration::from_secs(5),
+        true,
+    );
+    let elapsed = timed_read(&url, builder).await;
+    assert!(elapsed >= Duration::from_millis(200), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(800), "{elapsed:?}");
+}
+
+#[tokio::test]
+async fn zero_delay_retries_immediately() {
+    let (url, gets) = serve_flaky(3).await;
+    let builder =
+        HttpFile::builder().with_backoff(Duration::ZERO, 2.0, Duration::from_secs(5), true);
+    let elapsed = timed_read(&url, builder).await;
+    assert!(elapsed < Duration::from_millis(100), "{elapsed:?}");
+    assert_eq!(gets.load(Ordering::SeqCst), 4);
+}
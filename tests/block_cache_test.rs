@@ -0,0 +1,77 @@
+mod common;
+
+use std::io::SeekFrom;
+
+use common::{MockFile, random_bytes};
+use remote_file::HttpFile;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Read `len` bytes at `pos`.
+async fn read_at(file: &mut HttpFile, pos: u64, len: usize) -> Vec<u8> {
+    file.seek(SeekFrom::Start(pos)).await.unwrap();
+    let mut buf = vec![0u8; len];
+    file.read_exact(&mut buf).await.unwrap();
+    buf
+}
+
+#[tokio::test]
+async fn hot_blocks_are_served_from_memory() {
+    let data = random_bytes(10_000);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::builder()
+        .with_block_cache(1_000, 2)
+        .build(&url)
+        .await
+        .unwrap();
+
+    // seeks around the same region fetch its block once
+    for pos in [5_100, 5_900, 5_000, 5_500, 5_250] {
+        assert_eq!(
+            read_at(&mut file, pos, 100).await,
+            data[pos as usize..][..100]
+        );
+    }
+    assert_eq!(mock.gets(), 1);
+    let (_, headers) = mock.requests().pop().unwrap();
+    assert_eq!(headers["range"], "bytes=5000-5999");
+
+    // a read across blocks fetches both
+    assert_eq!(read_at(&mut file, 6_950, 100).await, data[6_950..7_050]);
+    assert_eq!(mock.gets(), 3);
+    // the least recently used block, 5, was dropped
+    assert_eq!(read_at(&mut file, 7_500, 10).await, data[7_500..7_510]);
+    assert_eq!(mock.gets(), 3);
+    assert_eq!(read_at(&mut file, 5_500, 10).await, data[5_500..5_510]);
+    assert_eq!(mock.gets(), 4);
+
+    // the last block is short
+    file.seek(SeekFrom::End(-50)).await.unwrap();
+    let mut rest = vec![];
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[9_950..]);
+    let stats = file.stats();
+    assert_eq!(stats.requests, 5);
+    assert_eq!(stats.bytes_received, 5_000);
+}
+
+#[tokio::test]
+async fn changed_file_empties_the_cache() {
+    let mock = MockFile::new(random_bytes(3_000)).with_etag("\"v1\"");
+    let url = mock.serve().await;
+    let mut file = HttpFile::builder()
+        .with_block_cache(1_000, 4)
+        .build(&url)
+        .await
+        .unwrap();
+    read_at(&mut file, 0, 10).await;
+    file.refresh().await.unwrap();
+    read_at(&mut file, 0, 10).await;
+    assert_eq!(mock.gets(), 1, "unchanged, the block stays cached");
+
+    let data = random_bytes(3_000);
+    mock.set(data.clone(), Some("\"v2\""));
+    file.refresh().await.unwrap();
+    assert_eq!(read_at(&mut file, 0, 10).await, data[..10]);
+    assert_eq!(mock.gets(), 2);
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use axum::{Router, body::Body, http::Method, routing::any};
+use bytes::Bytes;
+use remote_file::{HttpFile, HttpFileError};
+use tokio::io::AsyncReadExt;
+
+/// Serve `chunks` chunks of 1KB each without a `Content-Length`.
+async fn serve_unsized(chunks: usize) -> String {
+    let app = Router::new().route(
+        "/stream",
+        any(move |method: Method| async move {
+            if method == Method::HEAD {
+                return common::unsized_empty();
+            }
+            let stream = futures_util::stream::iter(
+                (0..chunks).map(|_| Ok::<_, std::io::Error>(Bytes::from(vec![7u8; 1024]))),
+            );
+            Body::from_stream(stream)
+        }),
+    );
+    let addr = common::serve(app).await;
+    format!("http://{}/stream", addr)
+}
+
+#[tokio::test]
+async fn unsized_body_over_limit_errors() {
+    let url = serve_unsized(64).await;
+    let mut file = HttpFile::builder()
+        .with_max_body_bytes(10 * 1024)
+        .build(&url)
+        .await
+        .unwrap();
+    assert_eq!(file.content_length(), None);
+
+    let mut buf = vec![];
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    let err = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<HttpFileError>())
+        .expect("should be an HttpFileError");
+    assert!(matches!(err, HttpFileError::BodyTooLarge { limit: 10240 }));
+    // everything up to the cap is still delivered
+    assert_eq!(buf.len(), 10 * 1024);
+}
+
+#[tokio::test]
+async fn unsized_body_within_limit_reads_to_eof() {
+    let url = serve_unsized(8).await;
+    let mut file = HttpFile::builder()
+        .with_max_body_bytes(8 * 1024)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, vec![7u8; 8 * 1024]);
+}
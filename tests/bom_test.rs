@@ -0,0 +1,52 @@
+mod common;
+
+use common::MockFile;
+use remote_file::HttpFile;
+use tokio::io::AsyncSeekExt;
+
+async fn read_text(data: Vec<u8>) -> std::io::Result<String> {
+    let mock = MockFile::new(data);
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let text = file.read_to_string_strip_bom().await;
+    if text.is_ok() {
+        let len = file.content_length().unwrap_or(0);
+        assert_eq!(file.stream_position().await.unwrap(), len);
+    }
+    text
+}
+
+#[tokio::test]
+async fn utf8_bom_is_stripped() {
+    let mut data = b"\xEF\xBB\xBF".to_vec();
+    data.extend_from_slice("héllo, wörld".as_bytes());
+    assert_eq!(read_text(data).await.unwrap(), "héllo, wörld");
+}
+
+#[tokio::test]
+async fn utf16le_bom_is_stripped() {
+    let mut data = b"\xFF\xFE".to_vec();
+    data.extend("héllo, wörld".encode_utf16().flat_map(u16::to_le_bytes));
+    assert_eq!(read_text(data).await.unwrap(), "héllo, wörld");
+}
+
+#[tokio::test]
+async fn text_without_bom() {
+    assert_eq!(read_text(b"plain".to_vec()).await.unwrap(), "plain");
+    // shorter than any BOM
+    assert_eq!(read_text(b"a".to_vec()).await.unwrap(), "a");
+
+    let err = read_text(b"\xEF\xBB".to_vec()).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let err = read_text(b"\xFF\xFEa".to_vec()).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[tokio::test]
+async fn bom_is_only_stripped_at_start() {
+    let mock = MockFile::new(b"ab\xEF\xBB\xBFcd".to_vec());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    file.seek(std::io::SeekFrom::Start(2)).await.unwrap();
+    assert_eq!(file.read_to_string_strip_bom().await.unwrap(), "\u{FEFF}cd");
+}
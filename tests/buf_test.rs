@@ -0,0 +1,63 @@
+mod common;
+
+use bytes::Buf;
+use common::{MockFile, random_bytes};
+use remote_file::HttpFile;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+#[tokio::test]
+async fn parse_header_through_buf() {
+    // magic, version, flags, entry count, then payload
+    let mut data = b"RMTF".to_vec();
+    data.extend_from_slice(&3u16.to_be_bytes());
+    data.extend_from_slice(&0x0102u16.to_le_bytes());
+    data.extend_from_slice(&123_456u32.to_be_bytes());
+    data.extend_from_slice(&random_bytes(8192));
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    assert!(file.chunk_as_buf().is_none(), "nothing buffered yet");
+
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic).await.unwrap();
+    assert_eq!(&magic, b"RMTF");
+
+    let mut buf = file.chunk_as_buf().expect("rest of the chunk is buffered");
+    let version = buf.get_u16();
+    let flags = buf.get_u16_le();
+    let count = buf.get_u32();
+    drop(buf);
+
+    // same fields read manually
+    let mut manual = [0u8; 8];
+    file.read_exact_at(4, &mut manual).await.unwrap();
+    assert_eq!(version, u16::from_be_bytes([manual[0], manual[1]]));
+    assert_eq!(flags, u16::from_le_bytes([manual[2], manual[3]]));
+    assert_eq!(
+        count,
+        u32::from_be_bytes([manual[4], manual[5], manual[6], manual[7]])
+    );
+    assert_eq!((version, flags, count), (3, 0x0102, 123_456));
+}
+
+#[tokio::test]
+async fn buf_advance_keeps_position() {
+    let data = random_bytes(8192);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let mut byte = [0u8; 1];
+    file.read_exact(&mut byte).await.unwrap();
+    let mut buf = file.chunk_as_buf().unwrap();
+    assert_eq!(buf.chunk()[0], data[1]);
+    buf.advance(99);
+    drop(buf);
+
+    assert_eq!(file.stream_position().await.unwrap(), 100);
+    let mut next = [0u8; 100];
+    file.read_exact(&mut next).await.unwrap();
+    assert_eq!(next, data[100..200]);
+    assert_eq!(mock.gets(), 1);
+}
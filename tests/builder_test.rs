@@ -0,0 +1,231 @@
+mod common;
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use axum::{
+    Router,
+    http::{HeaderMap, HeaderValue, Method, StatusCode, header},
+    routing::any,
+};
+use common::{MockFile, random_bytes};
+use remote_file::{HttpFile, RetryAction};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+#[tokio::test]
+async fn builder_settings_are_reachable() {
+    let mock = MockFile::new(random_bytes(1024));
+    let url = mock.serve().await;
+
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert_eq!(file.max_retries(), 3);
+    assert!(file.extra_headers().is_empty());
+
+    let mut headers = HeaderMap::new();
+    headers.insert("x-api-key", HeaderValue::from_static("secret"));
+    let file = HttpFile::builder()
+        .client(reqwest::Client::new())
+        .retries(7)
+        .extra_headers(headers.clone())
+        .build(&url)
+        .await
+        .unwrap();
+    assert_eq!(file.max_retries(), 7);
+    assert_eq!(file.extra_headers(), &headers);
+    assert_eq!(file.content_length(), Some(1024));
+}
+
+#[tokio::test]
+async fn extra_headers_are_sent() {
+    let mock = MockFile::new(random_bytes(1024));
+    let url = mock.serve().await;
+    let mut headers = HeaderMap::new();
+    headers.insert("x-api-key", HeaderValue::from_static("secret"));
+    let mut file = HttpFile::builder()
+        .extra_headers(headers)
+        .build(&url)
+        .await
+        .unwrap();
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 2);
+    for (_, headers) in requests {
+        assert_eq!(headers["x-api-key"], "secret");
+    }
+}
+
+#[tokio::test]
+async fn extra_range_header_is_ignored() {
+    let data = random_bytes(1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut headers = HeaderMap::new();
+    headers.insert(header::RANGE, HeaderValue::from_static("bytes=0-9"));
+    headers.insert(header::COOKIE, HeaderValue::from_static("session=1"));
+    let mut file = HttpFile::builder()
+        .extra_headers(headers)
+        .build(&url)
+        .await
+        .unwrap();
+    assert_eq!(file.extra_headers().len(), 1);
+
+    file.seek(std::io::SeekFrom::Start(1000)).await.unwrap();
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data[1000..]);
+    let (_, headers) = mock.requests().pop().unwrap();
+    assert_eq!(
+        headers.get_all(header::RANGE).iter().collect::<Vec<_>>(),
+        ["bytes=1000-"]
+    );
+    assert_eq!(headers[header::COOKIE], "session=1");
+}
+
+#[tokio::test]
+async fn set_extra_headers_applies_to_later_requests() {
+    let mock = MockFile::new(random_bytes(1024));
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let mut headers = HeaderMap::new();
+    headers.insert("x-api-key", HeaderValue::from_static("renewed"));
+    file.set_extra_headers(headers);
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    let (_, headers) = mock.requests().pop().unwrap();
+    assert_eq!(headers["x-api-key"], "renewed");
+}
+
+#[tokio::test]
+async fn skip_head_opens_without_a_request() {
+    let data = random_bytes(1024);
+    let mock = MockFile::new(data.clone()).with_etag("\"v1\"");
+    let url = mock.serve().await;
+    let mut file = HttpFile::builder()
+        .skip_head(data.len() as u64)
+        .build(&url)
+        .await
+        .unwrap();
+    assert!(mock.requests().is_empty());
+    assert_eq!(file.content_length(), Some(1024));
+    assert_eq!(file.etag(), None);
+
+    file.seek(std::io::SeekFrom::End(-24)).await.unwrap();
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data[1000..]);
+    let methods: Vec<_> = mock.requests().into_iter().map(|(m, _)| m).collect();
+    assert_eq!(methods, [Method::GET]);
+}
+
+#[tokio::test]
+async fn retries_bound_the_attempts() {
+    for retries in [0, 5, u8::MAX] {
+        let (url, gets) = serve_unavailable().await;
+        let mut file = HttpFile::builder()
+            .retries(retries)
+            .with_backoff(Duration::ZERO, 2.0, Duration::ZERO, false)
+            .with_status_retry_policy(HashMap::from([(
+                StatusCode::SERVICE_UNAVAILABLE,
+                RetryAction::Retry,
+            )]))
+            .skip_head(100)
+            .build(&url)
+            .await
+            .unwrap();
+        let mut buf = vec![];
+        file.read_to_end(&mut buf).await.unwrap_err();
+        assert_eq!(gets.load(Ordering::SeqCst), retries as usize + 1);
+    }
+}
+
+#[tokio::test]
+async fn max_retries_can_be_changed_later() {
+    let (url, gets) = serve_unavailable().await;
+    let mut file = HttpFile::builder()
+        .skip_head(100)
+        .with_backoff(Duration::ZERO, 2.0, Duration::ZERO, false)
+        .build(&url)
+        .await
+        .unwrap();
+    file.set_max_retries(0);
+    assert_eq!(file.max_retries(), 0);
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap_err();
+    assert_eq!(gets.load(Ordering::SeqCst), 1);
+
+    file.set_max_retries(10);
+    assert_eq!(file.max_retries(), 10);
+    file.read_to_end(&mut buf).await.unwrap_err();
+    assert_eq!(gets.load(Ordering::SeqCst), 12);
+}
+
+#[tokio::test]
+async fn failed_open_reports_the_status() {
+    let (url, _) = serve_unavailable().await;
+    let err = HttpFile::builder().build(&url).await.unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+}
+
+/// Answer every request with `503 Service Unavailable`, counting them.
+async fn serve_unavailable() -> (String, Arc<AtomicUsize>) {
+    let gets = Arc::new(AtomicUsize::new(0));
+    let counter = gets.clone();
+    let app = Router::new().route(
+        "/file",
+        any(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { StatusCode::SERVICE_UNAVAILABLE }
+        }),
+    );
+    let addr = common::serve(app).await;
+    (format!("http://{}/file", addr), gets)
+}
+
+#[tokio::test]
+async fn headers_from_options_are_merged() {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::USER_AGENT, HeaderValue::from_static("test"));
+    let mock = MockFile::new(random_bytes(10));
+    let url = mock.serve().await;
+    let file = HttpFile::builder()
+        .with_sse_customer_key("AES256", &[7u8; 32])
+        .unwrap()
+        .extra_headers(headers)
+        .build(&url)
+        .await
+        .unwrap();
+    assert_eq!(file.extra_headers().len(), 4);
+    assert_eq!(file.extra_headers()[header::USER_AGENT], "test");
+}
+
+#[tokio::test]
+async fn len_and_remaining_follow_the_position() {
+    let mock = MockFile::new(random_bytes(1024));
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert_eq!(file.len(), Some(1024));
+    assert_eq!(file.is_empty(), Some(false));
+    assert_eq!(file.remaining(), Some(1024));
+
+    let mut buf = vec![0u8; 100];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(file.position(), 100);
+    assert_eq!(file.remaining(), Some(924));
+    file.seek(std::io::SeekFrom::End(0)).await.unwrap();
+    assert_eq!(file.position(), 1024);
+    assert_eq!(file.remaining(), Some(0));
+
+    let empty = MockFile::new(Vec::new());
+    let url = empty.serve().await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert_eq!(file.is_empty(), Some(true));
+}
@@ -0,0 +1,152 @@
+mod common;
+
+use std::io::SeekFrom;
+
+use axum::{
+    Router,
+    body::{Body, Bytes},
+    extract::Request,
+    http::{Method, header::HeaderName},
+    response::Response,
+    routing::any,
+};
+use base64::Engine;
+use common::{random_bytes, range_response};
+use remote_file::{HttpFile, HttpFileError};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Headers computed from the whole file and the body of a response.
+type Digests = fn(&[u8], &[u8]) -> Vec<(&'static str, String)>;
+
+fn base64(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+/// Serve `data` with ranges, adding the headers `digests` computes to every
+/// response. A `HEAD` passes the whole file as its body.
+async fn serve_with(data: Vec<u8>, digests: Digests) -> String {
+    let data = Bytes::from(data);
+    let app = Router::new().route(
+        "/file",
+        any(move |req: Request| async move {
+            let resp = range_response(req.method(), req.headers(), data.clone(), None);
+            let (mut parts, body) = resp.into_parts();
+            let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+            let hashed = if req.method() == Method::HEAD {
+                &data
+            } else {
+                &body
+            };
+            for (name, value) in digests(&data, hashed) {
+                let name = HeaderName::from_static(name);
+                parts.headers.insert(name, value.parse().unwrap());
+            }
+            Response::from_parts(parts, Body::from(body))
+        }),
+    );
+    format!("http://{}/file", common::serve(app).await)
+}
+
+async fn open(url: &str) -> HttpFile {
+    HttpFile::builder()
+        .with_checksum_verification(true)
+        .build(url)
+        .await
+        .unwrap()
+}
+
+fn assert_mismatch(err: std::io::Error, header: &str) {
+    let err = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<HttpFileError>())
+        .expect("should be an HttpFileError");
+    assert!(
+        matches!(err, HttpFileError::ChecksumMismatch { header: h, .. } if h == header),
+        "unexpected error: {err}"
+    );
+}
+
+#[tokio::test]
+async fn whole_file_matches_digest() {
+    let data = random_bytes(100_000);
+    let url = serve_with(data.clone(), |file, _| {
+        vec![(
+            "digest",
+            format!("unknown=abc, SHA-256={}", base64(&Sha256::digest(file))),
+        )]
+    })
+    .await;
+    let mut file = open(&url).await;
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+}
+
+#[tokio::test]
+async fn whole_file_mismatching_digest_fails() {
+    let data = random_bytes(10_000);
+    let url = serve_with(data.clone(), |_, _| {
+        vec![(
+            "digest",
+            format!("sha-256={}", base64(&Sha256::digest(b"other"))),
+        )]
+    })
+    .await;
+    let mut file = open(&url).await;
+    let mut buf = vec![];
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    assert_mismatch(err, "Digest");
+    assert_eq!(buf, data, "the body is delivered before the check at EOF");
+
+    // off by default
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+}
+
+#[tokio::test]
+async fn ranged_reads_skip_whole_file_digest() {
+    let data = random_bytes(10_000);
+    let url = serve_with(data.clone(), |_, _| {
+        vec![(
+            "digest",
+            format!("sha-256={}", base64(&Sha256::digest(b"other"))),
+        )]
+    })
+    .await;
+    let mut file = open(&url).await;
+    file.seek(SeekFrom::Start(1_000)).await.unwrap();
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data[1_000..]);
+}
+
+#[tokio::test]
+async fn ranged_reads_check_their_content_md5() {
+    let data = random_bytes(10_000);
+    let url = serve_with(data.clone(), |_, body| {
+        vec![("content-md5", base64(&md5::Md5::digest(body)))]
+    })
+    .await;
+    let mut file = open(&url).await;
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+    file.seek(SeekFrom::Start(2_500)).await.unwrap();
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data[2_500..]);
+
+    // the digest of the whole file doesn't match a range
+    let url = serve_with(data.clone(), |file, _| {
+        vec![("content-md5", base64(&md5::Md5::digest(file)))]
+    })
+    .await;
+    let mut file = open(&url).await;
+    file.seek(SeekFrom::Start(2_500)).await.unwrap();
+    let mut buf = vec![];
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    assert_mismatch(err, "Content-MD5");
+}
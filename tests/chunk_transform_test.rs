@@ -0,0 +1,65 @@
+mod common;
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use common::{MockFile, random_bytes};
+use remote_file::HttpFile;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+#[tokio::test]
+async fn identity_transform() {
+    let data = random_bytes(256 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::builder()
+        .with_chunk_transform(Arc::new(|chunk| chunk))
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+}
+
+#[tokio::test]
+async fn xor_transform() {
+    let data = random_bytes(256 * 1024);
+    let masked: Vec<u8> = data.iter().map(|b| b ^ 0x5a).collect();
+    let mock = MockFile::new(masked);
+    let url = mock.serve().await;
+    let mut file = HttpFile::builder()
+        .with_chunk_transform(Arc::new(|chunk: Bytes| {
+            chunk.iter().map(|b| b ^ 0x5a).collect()
+        }))
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+
+    let mut buf = vec![0u8; 1000];
+    file.seek(std::io::SeekFrom::Start(12345)).await.unwrap();
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[12345..13345]);
+    file.read_exact_at(100, &mut buf).await.unwrap();
+    assert_eq!(buf, data[100..1100]);
+}
+
+#[tokio::test]
+async fn length_changing_transform_fails() {
+    let mock = MockFile::new(random_bytes(1024));
+    let url = mock.serve().await;
+    let mut file = HttpFile::builder()
+        .with_chunk_transform(Arc::new(|chunk: Bytes| chunk.slice(1..)))
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![];
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use common::{MockFile, random_bytes};
+use futures_util::FutureExt;
+use remote_file::HttpFile;
+use tokio::io::AsyncSeekExt;
+
+#[tokio::test]
+async fn concurrent_identical_reads_share_a_request() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let file = HttpFile::builder()
+        .with_request_coalescing(true)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut readers = vec![];
+    for _ in 0..4 {
+        readers.push(file.try_fork().await.unwrap());
+    }
+    let reads = readers.iter_mut().map(|reader| async move {
+        let mut buf = vec![0u8; 1000];
+        reader.read_exact_at(5000, &mut buf).await.unwrap();
+        buf
+    });
+    for buf in futures_util::future::join_all(reads).await {
+        assert_eq!(buf, data[5000..6000]);
+    }
+    assert_eq!(mock.gets(), 1);
+    for reader in &mut readers {
+        assert_eq!(reader.stream_position().await.unwrap(), 6000);
+    }
+
+    // once done, the same range is fetched again
+    let mut buf = vec![0u8; 1000];
+    readers[0].read_exact_at(5000, &mut buf).await.unwrap();
+    assert_eq!(mock.gets(), 2);
+}
+
+#[tokio::test]
+async fn cancelled_waiter_leaves_the_fetch_running() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::builder()
+        .with_request_coalescing(true)
+        .build(&url)
+        .await
+        .unwrap();
+    let mut other = file.try_fork().await.unwrap();
+
+    let mut buf = vec![0u8; 1000];
+    let mut other_buf = vec![0u8; 1000];
+    let mut other_read = Box::pin(other.read_exact_at(100, &mut other_buf));
+    // both polled once, starting the fetch and joining it, then one dropped
+    assert!(file.read_exact_at(100, &mut buf).now_or_never().is_none());
+    assert!((&mut other_read).now_or_never().is_none());
+
+    other_read.await.unwrap();
+    assert_eq!(other_buf, data[100..1100]);
+    assert_eq!(mock.gets(), 1);
+}
+
+#[tokio::test]
+async fn cancelling_every_waiter_drops_the_fetch() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let file = HttpFile::builder()
+        .with_request_coalescing(true)
+        .build(&url)
+        .await
+        .unwrap();
+    let mut a = file.try_fork().await.unwrap();
+    let mut b = file.try_fork().await.unwrap();
+
+    let mut buf_a = vec![0u8; 1000];
+    let mut buf_b = vec![0u8; 1000];
+    let mut read_a = Box::pin(a.read_exact_at(100, &mut buf_a));
+    let mut read_b = Box::pin(b.read_exact_at(100, &mut buf_b));
+    assert!((&mut read_a).now_or_never().is_none());
+    assert!((&mut read_b).now_or_never().is_none());
+    assert!(format!("{:?}", file).contains("InFlight { reads: 1 }"));
+
+    drop(read_a);
+    assert!(format!("{:?}", file).contains("InFlight { reads: 1 }"));
+    drop(read_b);
+    assert!(format!("{:?}", file).contains("InFlight { reads: 0 }"));
+}
+
+#[tokio::test]
+async fn different_ranges_are_not_coalesced() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let file = HttpFile::builder()
+        .with_request_coalescing(true)
+        .build(&url)
+        .await
+        .unwrap();
+    let mut a = file.try_fork().await.unwrap();
+    let mut b = file.try_fork().await.unwrap();
+
+    let mut buf_a = vec![0u8; 1000];
+    let mut buf_b = vec![0u8; 500];
+    let (ra, rb) = tokio::join!(
+        a.read_exact_at(0, &mut buf_a),
+        b.read_exact_at(0, &mut buf_b)
+    );
+    ra.unwrap();
+    rb.unwrap();
+    assert_eq!(buf_a, data[..1000]);
+    assert_eq!(buf_b, data[..500]);
+    assert_eq!(mock.gets(), 2);
+}
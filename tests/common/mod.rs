@@ -0,0 +1,245 @@
+#![allow(dead_code)]
+
+use std::{
+    net::SocketAddr,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Router,
+    body::Body,
+    extract::{Request, State},
+    http::{HeaderMap, Method, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::any,
+};
+use bytes::Bytes;
+use remote_file::{Clock, HttpFile, HttpFileBuilder};
+
+/// A file served from memory with `Range` support.
+///
+/// The content can be swapped while clients are reading it, and every request
+/// received is recorded so tests can assert on what went over the wire.
+#[derive(Clone)]
+pub struct MockFile {
+    inner: Arc<Mutex<Inner>>,
+}
+
+struct Inner {
+    data: Bytes,
+    etag: Option<String>,
+    requests: Vec<(Method, HeaderMap)>,
+    /// answer `GET`s with `503 Service Unavailable`
+    failing: bool,
+}
+
+impl MockFile {
+    pub fn new(data: impl Into<Bytes>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                data: data.into(),
+                etag: None,
+                requests: vec![],
+                failing: false,
+            })),
+        }
+    }
+
+    pub fn with_etag(self, etag: &str) -> Self {
+        self.inner.lock().unwrap().etag = Some(etag.to_string());
+        self
+    }
+
+    /// Replace the served content and etag.
+    pub fn set(&self, data: impl Into<Bytes>, etag: Option<&str>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.data = data.into();
+        inner.etag = etag.map(|s| s.to_string());
+    }
+
+    /// Answer `GET`s with `503 Service Unavailable` while `failing`.
+    pub fn set_failing(&self, failing: bool) {
+        self.inner.lock().unwrap().failing = failing;
+    }
+
+    /// All requests received so far.
+    pub fn requests(&self) -> Vec<(Method, HeaderMap)> {
+        self.inner.lock().unwrap().requests.clone()
+    }
+
+    /// Number of `GET` requests received so far.
+    pub fn gets(&self) -> usize {
+        self.requests()
+            .iter()
+            .filter(|(m, _)| m == Method::GET)
+            .count()
+    }
+
+    /// Start serving on a random local port, returning the file URL.
+    pub async fn serve(&self) -> String {
+        let app = Router::new()
+            .route("/file", any(handle))
+            .with_state(self.clone());
+        let addr = serve(app).await;
+        format!("http://{}/file", addr)
+    }
+}
+
+async fn handle(State(file): State<MockFile>, req: Request) -> Response {
+    let (data, etag) = {
+        let mut inner = file.inner.lock().unwrap();
+        inner
+            .requests
+            .push((req.method().clone(), req.headers().clone()));
+        if inner.failing && req.method() == Method::GET {
+            return StatusCode::SERVICE_UNAVAILABLE.into_response();
+        }
+        (inner.data.clone(), inner.etag.clone())
+    };
+    range_response(req.method(), req.headers(), data, etag)
+}
+
+/// Answer a request for `data` as a server supporting ranges would.
+pub fn range_response(
+    method: &Method,
+    headers: &HeaderMap,
+    data: Bytes,
+    etag: Option<String>,
+) -> Response {
+    let mut builder = Response::builder().header(header::ACCEPT_RANGES, "bytes");
+    if let Some(etag) = &etag {
+        builder = builder.header(header::ETAG, etag);
+    }
+
+    // a stale `If-Range` gets the whole, current file
+    let if_range_matches = match headers.get(header::IF_RANGE) {
+        Some(v) => etag.as_deref().is_some_and(|etag| v == etag),
+        None => true,
+    };
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .filter(|_| if_range_matches)
+        .and_then(|v| parse_range(v, data.len() as u64));
+
+    let (status, body) = match range {
+        Some(Ok((start, end))) => {
+            builder = builder.header(
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, data.len()),
+            );
+            (
+                StatusCode::PARTIAL_CONTENT,
+                data.slice(start as usize..=end as usize),
+            )
+        }
+        Some(Err(())) => {
+            let resp = builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", data.len()))
+                .body(Body::empty())
+                .unwrap();
+            return resp;
+        }
+        None => (StatusCode::OK, data),
+    };
+
+    builder = builder
+        .status(status)
+        .header(header::CONTENT_LENGTH, body.len());
+    if method == Method::HEAD {
+        builder.body(Body::empty()).unwrap()
+    } else {
+        builder.body(Body::from(body)).unwrap()
+    }
+}
+
+/// Parse a single `bytes=` range into an inclusive `(start, end)` pair.
+///
+/// Returns `None` for headers that should be ignored and `Some(Err(()))` for
+/// ranges that can't be satisfied.
+pub fn parse_range(value: &str, len: u64) -> Option<Result<(u64, u64), ()>> {
+    let spec = value.strip_prefix("bytes=")?;
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = if start.is_empty() {
+        let n: u64 = end.parse().ok()?;
+        (len.saturating_sub(n), len.checked_sub(1)?)
+    } else {
+        let start: u64 = start.parse().ok()?;
+        let end = match end {
+            "" => len.saturating_sub(1),
+            end => end.parse::<u64>().ok()?.min(len.saturating_sub(1)),
+        };
+        (start, end)
+    };
+    if start >= len || start > end {
+        return Some(Err(()));
+    }
+    Some(Ok((start, end)))
+}
+
+/// Serve `app` on a random local port.
+pub async fn serve(app: Router) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    addr
+}
+
+/// An empty body of unknown size, so a `HEAD` answered with it carries no
+/// `Content-Length`.
+pub fn unsized_empty() -> Body {
+    Body::from_stream(futures_util::stream::empty::<Result<Bytes, std::io::Error>>())
+}
+
+pub fn random_bytes(len: usize) -> Vec<u8> {
+    let mut buf = vec![0u8; len];
+    rand::fill(&mut buf[..]);
+    buf
+}
+
+/// A clock that only moves when told to.
+pub struct ManualClock {
+    start: Instant,
+    elapsed_ms: AtomicU64,
+}
+
+impl ManualClock {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            start: Instant::now(),
+            elapsed_ms: AtomicU64::new(0),
+        })
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.elapsed_ms
+            .fetch_add(by.as_millis() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + Duration::from_millis(self.elapsed_ms.load(Ordering::SeqCst))
+    }
+}
+
+/// A builder whose clock moves as data arrives, as if the link ran at
+/// `bytes_per_sec`.
+pub fn builder_at_rate(bytes_per_sec: u64) -> HttpFileBuilder {
+    let clock = ManualClock::new();
+    let ticker = clock.clone();
+    HttpFile::builder()
+        .with_clock(clock)
+        .with_chunk_transform(Arc::new(move |chunk| {
+            let ms = chunk.len() as u64 * 1000 / bytes_per_sec;
+            ticker.advance(Duration::from_millis(ms));
+            chunk
+        }))
+}
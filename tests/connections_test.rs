@@ -0,0 +1,172 @@
+mod common;
+
+use common::{MockFile, random_bytes};
+use remote_file::{HttpFile, HttpFileError};
+use tokio::io::{AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+#[tokio::test]
+async fn connections_opened_counts_range_requests() {
+    let data = random_bytes(256 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert_eq!(file.connections_opened(), 0);
+
+    let mut buf = vec![0u8; 1024];
+    for pos in [0, 100_000, 50_000, 200_000] {
+        file.seek(std::io::SeekFrom::Start(pos)).await.unwrap();
+        file.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, data[pos as usize..pos as usize + 1024]);
+    }
+    file.read_exact_at(10, &mut buf).await.unwrap();
+
+    assert_eq!(file.connections_opened(), 5);
+    assert_eq!(file.connections_opened(), mock.gets() as u64);
+}
+
+#[tokio::test]
+async fn stats_count_requests_and_bytes() {
+    let data = random_bytes(256 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert_eq!(file.stats(), Default::default());
+
+    let mut buf = vec![0u8; 1024];
+    for pos in [0, 100_000, 200_000] {
+        file.seek(std::io::SeekFrom::Start(pos)).await.unwrap();
+        file.read_exact(&mut buf).await.unwrap();
+    }
+    file.read_exact_at(10, &mut buf).await.unwrap();
+
+    let stats = file.stats();
+    assert_eq!(stats.requests, 4);
+    assert_eq!(stats.requests, mock.gets() as u64);
+    assert_eq!(stats.reconnects, 3);
+    assert_eq!(stats.bytes_read, 3 * 1024);
+    assert!(stats.bytes_received >= stats.bytes_read);
+    assert_eq!(stats.retries, 0);
+}
+
+#[tokio::test]
+async fn max_connections_reuses_the_open_stream() {
+    let data = random_bytes(256 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::builder()
+        .with_max_connections(1)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![0u8; 1024];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[..1024]);
+
+    // forward seeks are served from the same response
+    for pos in [1500, 3000, 100_000] {
+        file.seek(std::io::SeekFrom::Start(pos)).await.unwrap();
+        file.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, data[pos as usize..pos as usize + 1024]);
+    }
+    assert_eq!(file.connections_opened(), 1);
+    assert_eq!(mock.gets(), 1);
+
+    // going back would need a new connection
+    let err = file.seek(std::io::SeekFrom::Start(0)).await.unwrap_err();
+    let err = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<HttpFileError>())
+        .expect("should be an HttpFileError");
+    assert!(matches!(
+        err,
+        HttpFileError::TooManyConnections { limit: 1 }
+    ));
+}
+
+#[tokio::test]
+async fn short_forward_seeks_read_through() {
+    let data = random_bytes(256 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::builder()
+        .with_max_inline_skip(8 * 1024)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![0u8; 1024];
+    file.read_exact(&mut buf).await.unwrap();
+
+    // within the limit, the open response is read through
+    for pos in [2000, 9000, 17_000] {
+        file.seek(std::io::SeekFrom::Start(pos)).await.unwrap();
+        file.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, data[pos as usize..pos as usize + 1024]);
+    }
+    assert_eq!(mock.gets(), 1);
+
+    // beyond it, a new response is opened
+    file.seek(std::io::SeekFrom::Start(100_000)).await.unwrap();
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[100_000..101_024]);
+    assert_eq!(mock.gets(), 2);
+}
+
+#[tokio::test]
+async fn max_inline_skip_can_be_changed_later() {
+    let data = random_bytes(256 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert_eq!(file.max_inline_skip(), 16 * 1024);
+    file.set_max_inline_skip(64 * 1024);
+    assert_eq!(file.max_inline_skip(), 64 * 1024);
+
+    let mut buf = vec![0u8; 1024];
+    file.read_exact(&mut buf).await.unwrap();
+    file.seek(std::io::SeekFrom::Start(50_000)).await.unwrap();
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[50_000..51_024]);
+    assert_eq!(mock.gets(), 1);
+
+    file.set_max_inline_skip(0);
+    file.seek(std::io::SeekFrom::Start(60_000)).await.unwrap();
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[60_000..61_024]);
+    assert_eq!(mock.gets(), 2);
+}
+
+#[tokio::test]
+async fn abandoned_seek_keeps_the_open_response() {
+    let data = random_bytes(256 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let mut buf = vec![0u8; 1024];
+    file.read_exact(&mut buf).await.unwrap();
+
+    for target in [1024, 3000] {
+        // start a far seek and give up on it before its response arrives
+        let mut pinned = std::pin::Pin::new(&mut file);
+        pinned
+            .as_mut()
+            .start_seek(std::io::SeekFrom::Start(200_000))
+            .unwrap();
+        let polled =
+            futures_util::poll!(std::future::poll_fn(|cx| pinned.as_mut().poll_complete(cx)));
+        assert!(polled.is_pending());
+
+        pinned
+            .as_mut()
+            .start_seek(std::io::SeekFrom::Start(target))
+            .unwrap();
+        let pos = std::future::poll_fn(|cx| pinned.as_mut().poll_complete(cx))
+            .await
+            .unwrap();
+        assert_eq!(pos, target);
+        file.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, data[target as usize..target as usize + 1024]);
+    }
+    assert_eq!(file.connections_opened(), 1);
+}
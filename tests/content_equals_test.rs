@@ -0,0 +1,55 @@
+mod common;
+
+use common::{MockFile, random_bytes};
+use remote_file::HttpFile;
+
+async fn open(mock: &MockFile) -> HttpFile {
+    let url = mock.serve().await;
+    HttpFile::new(reqwest::Client::new(), &url).await.unwrap()
+}
+
+#[tokio::test]
+async fn equal_files() {
+    let data = random_bytes(300 * 1024);
+    let a = MockFile::new(data.clone());
+    let b = MockFile::new(data);
+    let (file_a, file_b) = (open(&a).await, open(&b).await);
+
+    assert!(file_a.content_equals(&file_b).await.unwrap());
+    assert_eq!(a.gets(), 1);
+    assert_eq!(b.gets(), 1);
+}
+
+#[tokio::test]
+async fn different_length_files() {
+    let data = random_bytes(1024);
+    let a = MockFile::new(data.clone());
+    let b = MockFile::new(data[..1000].to_vec());
+    let (file_a, file_b) = (open(&a).await, open(&b).await);
+
+    assert!(!file_a.content_equals(&file_b).await.unwrap());
+    assert_eq!(a.gets() + b.gets(), 0, "length mismatch needs no body");
+}
+
+#[tokio::test]
+async fn same_length_different_content() {
+    let data = random_bytes(300 * 1024);
+    let mut other = data.clone();
+    *other.last_mut().unwrap() ^= 0xff;
+    let a = MockFile::new(data);
+    let b = MockFile::new(other);
+    let (file_a, file_b) = (open(&a).await, open(&b).await);
+
+    assert!(!file_a.content_equals(&file_b).await.unwrap());
+}
+
+#[tokio::test]
+async fn same_strong_etag() {
+    let data = random_bytes(1024);
+    let a = MockFile::new(data.clone()).with_etag("\"abc\"");
+    let b = MockFile::new(data).with_etag("\"abc\"");
+    let (file_a, file_b) = (open(&a).await, open(&b).await);
+
+    assert!(file_a.content_equals(&file_b).await.unwrap());
+    assert_eq!(a.gets() + b.gets(), 0, "etag match needs no body");
+}
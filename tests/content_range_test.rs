@@ -0,0 +1,101 @@
+mod common;
+
+use axum::{
+    Router,
+    body::Body,
+    http::{HeaderMap, Method, StatusCode, header},
+    response::Response,
+    routing::any,
+};
+use bytes::Bytes;
+use remote_file::HttpFile;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Serve `data` without a length on `HEAD`, answering every `GET` with a 206
+/// carrying `content_range` verbatim.
+async fn serve_with_content_range(data: Bytes, content_range: &'static str) -> String {
+    let app = Router::new().route(
+        "/file",
+        any(move |method: Method, headers: HeaderMap| async move {
+            if method == Method::HEAD {
+                return Response::new(common::unsized_empty());
+            }
+            let start = headers[header::RANGE]
+                .to_str()
+                .unwrap()
+                .trim_start_matches("bytes=")
+                .split('-')
+                .next()
+                .unwrap()
+                .parse::<usize>()
+                .unwrap();
+            Response::builder()
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_RANGE, content_range)
+                .body(Body::from(data.slice(start..)))
+                .unwrap()
+        }),
+    );
+    let addr = common::serve(app).await;
+    format!("http://{}/file", addr)
+}
+
+#[tokio::test]
+async fn length_learned_from_content_range() {
+    let data = Bytes::from(common::random_bytes(100));
+    let url = serve_with_content_range(data.clone(), "bytes 0-99/100").await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert_eq!(file.content_length(), None);
+
+    let mut buf = [0u8; 10];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(file.content_length(), Some(100));
+}
+
+#[tokio::test]
+async fn bogus_content_range_totals_are_ignored() {
+    let data = Bytes::from(common::random_bytes(100));
+    for content_range in [
+        "bytes 0-0/0",
+        "bytes 0-99/0",
+        "bytes 0-99/50",
+        "bytes 0-99/abc",
+        "bytes 0-99/-1",
+        "bytes 99-0/100",
+        "bytes x-99/100",
+        "bytes 0-99/*",
+        "0-99/100",
+        "garbage",
+    ] {
+        let url = serve_with_content_range(data.clone(), content_range).await;
+        let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+        let mut buf = vec![];
+        file.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, data, "whole body is read for {:?}", content_range);
+        assert_eq!(
+            file.content_length(),
+            None,
+            "length stays unknown for {:?}",
+            content_range
+        );
+    }
+}
+
+#[tokio::test]
+async fn length_learned_by_read_exact_at() {
+    let data = Bytes::from(common::random_bytes(100));
+    let url = serve_with_content_range(data.clone(), "bytes 10-99/100").await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let mut buf = [0u8; 10];
+    file.read_exact_at(10, &mut buf).await.unwrap();
+    assert_eq!(buf, data[10..20]);
+    assert_eq!(file.content_length(), Some(100));
+
+    // seeks from the end and EOF now work from the learned length
+    file.seek(std::io::SeekFrom::End(-10)).await.unwrap();
+    assert_eq!(file.position(), 90);
+    file.seek(std::io::SeekFrom::End(0)).await.unwrap();
+    assert_eq!(file.read(&mut buf).await.unwrap(), 0);
+}
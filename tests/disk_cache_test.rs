@@ -0,0 +1,110 @@
+mod common;
+
+use std::{io::SeekFrom, path::PathBuf};
+
+use common::{MockFile, random_bytes};
+use remote_file::{DiskCache, HttpFile};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// An empty cache directory for the test `name`.
+fn cache_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("remote-file-cache-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// Number of blocks cached under `dir`.
+fn cached_blocks(dir: &PathBuf) -> usize {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .flat_map(|entry| std::fs::read_dir(entry.unwrap().path()).unwrap())
+        .filter(|file| file.as_ref().unwrap().file_name() != "meta")
+        .count()
+}
+
+async fn read_all(cache: &DiskCache, url: &str) -> Vec<u8> {
+    let file = HttpFile::new(reqwest::Client::new(), url).await.unwrap();
+    let mut file = cache.open(file).await.unwrap();
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    buf
+}
+
+#[tokio::test]
+async fn cached_blocks_are_read_from_disk() {
+    let data = random_bytes(10_000);
+    let mock = MockFile::new(data.clone()).with_etag("\"v1\"");
+    let url = mock.serve().await;
+    let dir = cache_dir("reuse");
+    let cache = DiskCache::new(&dir, 1 << 20).with_block_size(1_000);
+
+    assert_eq!(read_all(&cache, &url).await, data);
+    assert_eq!(mock.gets(), 10);
+    assert_eq!(cached_blocks(&dir), 10);
+
+    // random access on a new file, without any request
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let mut file = cache.open(file).await.unwrap();
+    assert!(file.is_cached());
+    file.seek(SeekFrom::Start(4_500)).await.unwrap();
+    let mut buf = [0u8; 1_000];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[4_500..5_500]);
+    file.seek(SeekFrom::End(-10)).await.unwrap();
+    let mut rest = vec![];
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[9_990..]);
+    assert_eq!(mock.gets(), 10);
+}
+
+#[tokio::test]
+async fn changed_etag_invalidates_the_entry() {
+    let mock = MockFile::new(random_bytes(3_000)).with_etag("\"v1\"");
+    let url = mock.serve().await;
+    let dir = cache_dir("invalidate");
+    let cache = DiskCache::new(&dir, 1 << 20).with_block_size(1_000);
+    read_all(&cache, &url).await;
+    assert_eq!(mock.gets(), 3);
+
+    let data = random_bytes(2_000);
+    mock.set(data.clone(), Some("\"v2\""));
+    assert_eq!(read_all(&cache, &url).await, data);
+    assert_eq!(mock.gets(), 5);
+    assert_eq!(cached_blocks(&dir), 2);
+}
+
+#[tokio::test]
+async fn least_recently_used_blocks_are_evicted() {
+    let data = random_bytes(10_000);
+    let mock = MockFile::new(data.clone()).with_etag("\"v1\"");
+    let url = mock.serve().await;
+    let dir = cache_dir("evict");
+    let cache = DiskCache::new(&dir, 3_000).with_block_size(1_000);
+
+    assert_eq!(read_all(&cache, &url).await, data);
+    assert_eq!(cached_blocks(&dir), 3);
+    // the last blocks were kept
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let mut file = cache.open(file).await.unwrap();
+    file.seek(SeekFrom::Start(7_000)).await.unwrap();
+    let mut rest = vec![];
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[7_000..]);
+    assert_eq!(mock.gets(), 10);
+}
+
+#[tokio::test]
+async fn files_without_etag_are_not_cached() {
+    let data = random_bytes(2_000);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let dir = cache_dir("no-etag");
+    let cache = DiskCache::new(&dir, 1 << 20).with_block_size(1_000);
+
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert!(!cache.open(file).await.unwrap().is_cached());
+    assert_eq!(read_all(&cache, &url).await, data);
+    assert_eq!(read_all(&cache, &url).await, data);
+    assert_eq!(mock.gets(), 4);
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Method, header},
+    routing::any,
+};
+use common::{MockFile, random_bytes};
+use remote_file::HttpFile;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+#[tokio::test]
+async fn download_to_writer_copies_the_whole_file() {
+    let data = random_bytes(1024 * 1024 + 17);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let mut out = vec![];
+    let written = file.download_to_writer(&mut out).await.unwrap();
+    assert_eq!(Some(written), file.content_length());
+    assert_eq!(out, data);
+    assert_eq!(mock.gets(), 1);
+    assert_eq!(file.stream_position().await.unwrap(), data.len() as u64);
+}
+
+#[tokio::test]
+async fn download_to_writer_starts_at_the_cursor() {
+    let data = random_bytes(100_000);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let mut header = [0u8; 10];
+    file.read_exact(&mut header).await.unwrap();
+
+    let mut out = std::io::Cursor::new(vec![]);
+    let written = file.download_to_writer(&mut out).await.unwrap();
+    assert_eq!(written, 99_990);
+    assert_eq!(out.into_inner(), data[10..]);
+    assert_eq!(file.download_to_writer(&mut vec![]).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn download_parallel_writes_chunks_at_their_offsets() {
+    let data = random_bytes(100_000);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let mut out = std::io::Cursor::new(vec![]);
+    file.download_parallel(&mut out, 4, 16 * 1024)
+        .await
+        .unwrap();
+    assert_eq!(out.into_inner(), data);
+    assert_eq!(mock.gets(), 7);
+    let mut ranges: Vec<_> = mock
+        .requests()
+        .into_iter()
+        .filter_map(|(_, headers)| Some(headers.get(header::RANGE)?.to_str().ok()?.to_string()))
+        .collect();
+    ranges.sort();
+    assert_eq!(ranges[0], "bytes=0-16383");
+    assert!(ranges.contains(&"bytes=98304-99999".to_string()));
+}
+
+#[tokio::test]
+async fn download_parallel_needs_a_length() {
+    let app = Router::new().route(
+        "/file",
+        any(|method: Method| async move {
+            if method == Method::HEAD {
+                return common::unsized_empty();
+            }
+            Body::from(vec![0u8; 10])
+        }),
+    );
+    let addr = common::serve(app).await;
+    let file = HttpFile::new(reqwest::Client::new(), &format!("http://{}/file", addr))
+        .await
+        .unwrap();
+
+    let mut out = std::io::Cursor::new(vec![]);
+    let err = file.download_parallel(&mut out, 4, 1024).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
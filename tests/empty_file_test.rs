@@ -0,0 +1,23 @@
+mod common;
+
+use common::MockFile;
+use remote_file::HttpFile;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+#[tokio::test]
+async fn empty_file_has_a_known_length() {
+    let mock = MockFile::new(vec![]);
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert_eq!(file.content_length(), Some(0));
+
+    assert_eq!(file.seek(std::io::SeekFrom::End(0)).await.unwrap(), 0);
+    let mut buf = vec![];
+    assert_eq!(file.read_to_end(&mut buf).await.unwrap(), 0);
+    let mut buf = [0u8; 1];
+    assert_eq!(
+        file.read_exact_at(0, &mut buf).await.unwrap_err().kind(),
+        std::io::ErrorKind::UnexpectedEof
+    );
+    assert_eq!(mock.gets(), 0, "nothing to fetch");
+}
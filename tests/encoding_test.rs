@@ -0,0 +1,162 @@
+mod common;
+
+use axum::{
+    Router,
+    http::{HeaderMap, Method, StatusCode, header},
+    response::IntoResponse,
+    routing::any,
+};
+use common::{parse_range, random_bytes};
+use remote_file::{HttpFile, HttpFileError};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// `HEAD` describes `head_len` bytes in `head_encoding`, while `GET` serves
+/// `data` in `get_encoding`, with a `Content-Range` if `with_range`.
+async fn serve_mismatched(
+    head_encoding: Option<&'static str>,
+    head_len: usize,
+    get_encoding: Option<&'static str>,
+    data: Vec<u8>,
+    with_range: bool,
+) -> String {
+    let app = Router::new().route(
+        "/file",
+        any(move |method: Method, headers: HeaderMap| {
+            let data = data.clone();
+            async move {
+                let mut resp = if method == Method::HEAD {
+                    [(header::CONTENT_LENGTH, head_len.to_string())].into_response()
+                } else {
+                    let range = headers[header::RANGE].to_str().unwrap();
+                    let (start, end) = parse_range(range, data.len() as u64).unwrap().unwrap();
+                    let body = data[start as usize..=end as usize].to_vec();
+                    if with_range {
+                        let content_range = format!("bytes {}-{}/{}", start, end, data.len());
+                        (
+                            StatusCode::PARTIAL_CONTENT,
+                            [(header::CONTENT_RANGE, content_range)],
+                            body,
+                        )
+                            .into_response()
+                    } else {
+                        (StatusCode::PARTIAL_CONTENT, body).into_response()
+                    }
+                };
+                let encoding = if method == Method::HEAD {
+                    head_encoding
+                } else {
+                    get_encoding
+                };
+                if let Some(encoding) = encoding {
+                    resp.headers_mut()
+                        .insert(header::CONTENT_ENCODING, encoding.parse().unwrap());
+                }
+                resp
+            }
+        }),
+    );
+    let addr = common::serve(app).await;
+    format!("http://{}/file", addr)
+}
+
+#[tokio::test]
+async fn gzip_head_identity_get() {
+    let data = random_bytes(10_000);
+    let url = serve_mismatched(Some("gzip"), 4_000, None, data.clone(), true).await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert_eq!(file.content_length(), Some(4_000));
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+    assert_eq!(file.content_length(), Some(10_000));
+}
+
+#[tokio::test]
+async fn identity_head_gzip_get() {
+    // stands in for the compressed body, passed through as is
+    let gzipped = random_bytes(3_000);
+    let url = serve_mismatched(None, 10_000, Some("gzip"), gzipped.clone(), true).await;
+    let mut file = HttpFile::builder()
+        .with_identity_encoding(false)
+        .build(&url)
+        .await
+        .unwrap();
+    assert_eq!(file.content_length(), Some(10_000));
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, gzipped);
+    assert_eq!(file.content_length(), Some(3_000));
+}
+
+#[tokio::test]
+async fn length_unknown_without_content_range() {
+    let data = random_bytes(10_000);
+    let url = serve_mismatched(Some("gzip"), 4_000, None, data.clone(), false).await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let mut buf = vec![0u8; 100];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(file.content_length(), None);
+    let mut rest = vec![];
+    file.read_to_end(&mut rest).await.unwrap();
+    buf.extend(rest);
+    assert_eq!(buf, data);
+}
+
+#[tokio::test]
+async fn matching_encodings_keep_the_head_length() {
+    let data = random_bytes(10_000);
+    let url = serve_mismatched(Some("gzip"), 10_000, Some("GZIP"), data.clone(), false).await;
+    let mut file = HttpFile::builder()
+        .with_identity_encoding(false)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+    assert_eq!(file.content_length(), Some(10_000));
+}
+
+#[tokio::test]
+async fn range_requests_ask_for_identity() {
+    let mock = common::MockFile::new(random_bytes(1_000));
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    let (method, headers) = mock.requests().pop().unwrap();
+    assert_eq!(method, Method::GET);
+    assert_eq!(headers[header::ACCEPT_ENCODING], "identity");
+
+    let mut file = HttpFile::builder()
+        .with_identity_encoding(false)
+        .build(&url)
+        .await
+        .unwrap();
+    file.read_to_end(&mut buf).await.unwrap();
+    let (_, headers) = mock.requests().pop().unwrap();
+    assert!(!headers.contains_key(header::ACCEPT_ENCODING));
+}
+
+#[tokio::test]
+async fn encoded_range_response_fails() {
+    let data = random_bytes(10_000);
+    let url = serve_mismatched(None, 10_000, Some("gzip"), data, true).await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let err = file
+        .seek(std::io::SeekFrom::Start(5_000))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("gzip"));
+
+    let err = file.read_exact_at(0, &mut [0u8; 10]).await.unwrap_err();
+    let err = err.get_ref().unwrap().downcast_ref::<HttpFileError>();
+    assert!(matches!(
+        err,
+        Some(HttpFileError::EncodedResponse { encoding }) if encoding == "gzip"
+    ));
+}
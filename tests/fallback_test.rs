@@ -0,0 +1,210 @@
+mod common;
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use axum::{Router, http::Method, routing::any};
+use bytes::Bytes;
+use common::random_bytes;
+use remote_file::{HttpFile, HttpFileError};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Serve `data` ignoring any `Range` header, counting `GET`s.
+async fn serve_rangeless(data: Bytes, gets: Arc<AtomicUsize>) -> String {
+    let app = Router::new().route(
+        "/file",
+        any(move |method: Method| async move {
+            if method == Method::GET {
+                gets.fetch_add(1, Ordering::SeqCst);
+            }
+            data
+        }),
+    );
+    let addr = common::serve(app).await;
+    format!("http://{}/file", addr)
+}
+
+#[tokio::test]
+async fn random_access_via_full_download() {
+    let data = Bytes::from(random_bytes(512 * 1024));
+    let gets = Arc::new(AtomicUsize::new(0));
+    let url = serve_rangeless(data.clone(), gets.clone()).await;
+
+    let mut file = HttpFile::builder()
+        .with_full_download_fallback(true)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![0u8; 4096];
+    for _ in 0..20 {
+        let pos = rand::random::<u64>() % (data.len() - buf.len()) as u64;
+        file.seek(std::io::SeekFrom::Start(pos)).await.unwrap();
+        file.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, data[pos as usize..pos as usize + buf.len()]);
+    }
+
+    file.read_exact_at(100, &mut buf).await.unwrap();
+    assert_eq!(buf, data[100..100 + buf.len()]);
+
+    let mut rest = vec![];
+    file.seek(std::io::SeekFrom::End(-1000)).await.unwrap();
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[data.len() - 1000..]);
+
+    assert_eq!(gets.load(Ordering::SeqCst), 1, "file is downloaded once");
+}
+
+#[tokio::test]
+async fn fallback_on_first_read() {
+    let data = Bytes::from(random_bytes(64 * 1024));
+    let gets = Arc::new(AtomicUsize::new(0));
+    let url = serve_rangeless(data.clone(), gets.clone()).await;
+
+    let mut file = HttpFile::builder()
+        .with_full_download_fallback(true)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+    file.seek(std::io::SeekFrom::Start(10)).await.unwrap();
+    let mut buf = vec![0u8; 10];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[10..20]);
+    assert_eq!(gets.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn ignored_range_is_skipped_after_seek() {
+    let data = Bytes::from(random_bytes(64 * 1024));
+    let gets = Arc::new(AtomicUsize::new(0));
+    let url = serve_rangeless(data.clone(), gets.clone()).await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    file.seek(std::io::SeekFrom::Start(50_000)).await.unwrap();
+    let mut buf = vec![0u8; 1000];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[50_000..51_000]);
+
+    file.read_exact_at(10_000, &mut buf).await.unwrap();
+    assert_eq!(buf, data[10_000..11_000]);
+
+    let mut rest = vec![];
+    file.seek(std::io::SeekFrom::End(-10)).await.unwrap();
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[data.len() - 10..]);
+}
+
+#[tokio::test]
+async fn byte_stream_trims_an_ignored_range() {
+    let data = Bytes::from(random_bytes(64 * 1024));
+    let gets = Arc::new(AtomicUsize::new(0));
+    let url = serve_rangeless(data.clone(), gets.clone()).await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let chunks: Vec<_> = futures_util::TryStreamExt::try_collect(file.byte_stream(30_000..30_100))
+        .await
+        .unwrap();
+    assert_eq!(chunks.concat(), data[30_000..30_100]);
+}
+
+#[tokio::test]
+async fn buf_read_from_the_full_download() {
+    let data = Bytes::from(random_bytes(64 * 1024));
+    let gets = Arc::new(AtomicUsize::new(0));
+    let url = serve_rangeless(data.clone(), gets.clone()).await;
+    let mut file = HttpFile::builder()
+        .with_full_download_fallback(true)
+        .build(&url)
+        .await
+        .unwrap();
+
+    file.seek(std::io::SeekFrom::Start(1000)).await.unwrap();
+    let buf = tokio::io::AsyncBufReadExt::fill_buf(&mut file)
+        .await
+        .unwrap();
+    assert_eq!(buf, &data[1000..]);
+    tokio::io::AsyncBufReadExt::consume(&mut file, 24);
+    assert_eq!(file.stream_position().await.unwrap(), 1024);
+    let mut rest = vec![];
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[1024..]);
+}
+
+#[tokio::test]
+async fn supports_range_follows_accept_ranges() {
+    let mock = common::MockFile::new(random_bytes(1024));
+    let url = mock.serve().await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert_eq!(file.supports_range(), Some(true));
+
+    let url = serve_rangeless(Bytes::from(random_bytes(1024)), Default::default()).await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert_eq!(file.supports_range(), None);
+
+    let app = Router::new().route(
+        "/file",
+        any(|| async { ([(axum::http::header::ACCEPT_RANGES, "none")], "data") }),
+    );
+    let addr = common::serve(app).await;
+    let url = format!("http://{}/file", addr);
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert_eq!(file.supports_range(), Some(false));
+}
+
+#[tokio::test]
+async fn require_range_fails_to_open_without_ranges() {
+    let gets = Arc::new(AtomicUsize::new(0));
+    let url = serve_rangeless(Bytes::from(random_bytes(1024)), gets.clone()).await;
+    let err = HttpFile::builder()
+        .require_range(true)
+        .build(&url)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        HttpFileError::RangesNotSupported {
+            accept_ranges: None
+        }
+    ));
+    assert_eq!(gets.load(Ordering::SeqCst), 1, "a one byte probe is sent");
+
+    let app = Router::new().route(
+        "/file",
+        any(|| async { ([(axum::http::header::ACCEPT_RANGES, "none")], "data") }),
+    );
+    let addr = common::serve(app).await;
+    let url = format!("http://{}/file", addr);
+    let err = HttpFile::builder()
+        .require_range(true)
+        .build(&url)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, HttpFileError::RangesNotSupported { .. }));
+}
+
+#[tokio::test]
+async fn require_range_trusts_accept_ranges_or_probes() {
+    let mock = common::MockFile::new(random_bytes(1024));
+    let url = mock.serve().await;
+    HttpFile::builder()
+        .require_range(true)
+        .build(&url)
+        .await
+        .unwrap();
+    assert_eq!(mock.gets(), 0, "Accept-Ranges: bytes needs no probe");
+
+    HttpFile::builder()
+        .require_range(true)
+        .skip_head(1024)
+        .build(&url)
+        .await
+        .unwrap();
+    let (_, headers) = mock.requests().pop().unwrap();
+    assert_eq!(headers[axum::http::header::RANGE], "bytes=0-0");
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use common::{MockFile, random_bytes};
+use remote_file::{HttpFile, HttpFileError};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+#[tokio::test]
+async fn try_fork_reads_same_content() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone()).with_etag("\"v1\"");
+    let url = mock.serve().await;
+
+    let mut parent = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    parent.seek(std::io::SeekFrom::Start(1000)).await.unwrap();
+
+    let mut fork = parent.try_fork().await.unwrap();
+    assert_eq!(parent.content_length(), Some(data.len() as u64));
+    assert_eq!(fork.content_length(), parent.content_length());
+    assert_eq!(fork.etag(), Some("\"v1\""));
+    assert_eq!(fork.stream_position().await.unwrap(), 0);
+
+    let mut buf = vec![0u8; data.len()];
+    fork.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+
+    // the parent cursor is untouched
+    assert_eq!(parent.stream_position().await.unwrap(), 1000);
+}
+
+#[tokio::test]
+async fn try_fork_fails_when_file_changed() {
+    let mock = MockFile::new(random_bytes(1024)).with_etag("\"v1\"");
+    let url = mock.serve().await;
+
+    let parent = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    mock.set(random_bytes(1024), Some("\"v2\""));
+
+    let err = parent.try_fork().await.unwrap_err();
+    match err {
+        HttpFileError::FileChanged { expected, actual } => {
+            assert_eq!(expected.as_deref(), Some("\"v1\""));
+            assert_eq!(actual.as_deref(), Some("\"v2\""));
+        }
+        e => panic!("unexpected error: {e}"),
+    }
+}
+
+#[tokio::test]
+async fn try_clone_reads_concurrently_without_head() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone()).with_etag("\"v1\"");
+    let url = mock.serve().await;
+
+    let mut parent = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    parent.seek(std::io::SeekFrom::Start(1000)).await.unwrap();
+    let heads = mock.requests().len() - mock.gets();
+
+    let readers = (0..4u64).map(|i| {
+        let mut file = parent.try_clone();
+        assert_eq!(file.etag(), Some("\"v1\""));
+        assert_eq!(file.content_length(), Some(data.len() as u64));
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 1024];
+            file.seek(std::io::SeekFrom::Start(i * 16 * 1024))
+                .await
+                .unwrap();
+            file.read_exact(&mut buf).await.unwrap();
+            (i, buf)
+        })
+    });
+    for reader in readers.collect::<Vec<_>>() {
+        let (i, buf) = reader.await.unwrap();
+        let start = (i * 16 * 1024) as usize;
+        assert_eq!(buf, data[start..start + 1024]);
+    }
+
+    assert_eq!(mock.requests().len() - mock.gets(), heads, "no new HEAD");
+    assert_eq!(parent.stream_position().await.unwrap(), 1000);
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use axum::http::{Method, header};
+use common::{MockFile, random_bytes};
+use remote_file::{HttpFile, HttpFileError};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+#[tokio::test]
+async fn from_known_makes_no_head_request() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone()).with_etag("\"v1\"");
+    let url = mock.serve().await.parse().unwrap();
+    let mut file = HttpFile::from_known(
+        reqwest::Client::new(),
+        url,
+        data.len() as u64,
+        Some("\"v1\"".into()),
+    );
+    assert!(mock.requests().is_empty());
+
+    let mut buf = vec![0u8; 1000];
+    file.seek(std::io::SeekFrom::End(-1000)).await.unwrap();
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[data.len() - 1000..]);
+
+    let requests = mock.requests();
+    assert!(requests.iter().all(|(method, _)| method == Method::GET));
+    assert_eq!(requests[0].1[header::IF_RANGE], "\"v1\"");
+}
+
+#[tokio::test]
+async fn from_known_detects_a_different_object() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone()).with_etag("\"v2\"");
+    let url = mock.serve().await.parse().unwrap();
+    let mut file = HttpFile::from_known(
+        reqwest::Client::new(),
+        url,
+        data.len() as u64,
+        Some("\"v1\"".into()),
+    );
+
+    let mut buf = vec![0u8; 1000];
+    let err = file.read_exact(&mut buf).await.unwrap_err();
+    let err = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<HttpFileError>())
+        .expect("should be an HttpFileError");
+    assert!(matches!(err, HttpFileError::FileChanged { .. }));
+}
@@ -0,0 +1,82 @@
+use bytes::Bytes;
+use futures_util::StreamExt;
+use remote_file::{FileMeta, HttpFile, ResponseStream};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// `data` from `start` on, in chunks of `size` bytes.
+fn chunked(data: &Bytes, start: usize, size: usize) -> ResponseStream {
+    let chunks: Vec<_> = data[start..]
+        .chunks(size)
+        .map(|c| Ok(Bytes::copy_from_slice(c)))
+        .collect();
+    futures_util::stream::iter(chunks).boxed()
+}
+
+// nothing listens there, so any request made would fail
+fn unreachable_url() -> reqwest::Url {
+    "http://127.0.0.1:9/file".parse().unwrap()
+}
+
+#[tokio::test]
+async fn reads_from_injected_stream() {
+    let data = Bytes::from((0..=255u8).cycle().take(10_000).collect::<Vec<_>>());
+    let meta = FileMeta {
+        content_length: Some(data.len() as u64),
+        etag: Some("\"v1\"".into()),
+        pos: 1000,
+        ..Default::default()
+    };
+    let mut file = HttpFile::from_parts(
+        reqwest::Client::new(),
+        unreachable_url(),
+        meta,
+        Some(chunked(&data, 1000, 333)),
+    );
+    assert_eq!(file.etag(), Some("\"v1\""));
+    assert_eq!(file.stream_position().await.unwrap(), 1000);
+
+    let mut buf = vec![0u8; 2000];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[1000..3000]);
+    file.seek(std::io::SeekFrom::Current(0)).await.unwrap();
+
+    let mut rest = vec![];
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[3000..]);
+    assert_eq!(file.connections_opened(), 0);
+}
+
+#[tokio::test]
+async fn seeks_without_a_stream() {
+    let data = Bytes::from(vec![7u8; 100]);
+    let meta = FileMeta {
+        content_length: Some(data.len() as u64),
+        ..Default::default()
+    };
+    let mut file = HttpFile::from_parts(reqwest::Client::new(), unreachable_url(), meta, None);
+
+    // seeking to EOF needs no request
+    assert_eq!(file.seek(std::io::SeekFrom::End(0)).await.unwrap(), 100);
+    let mut buf = vec![];
+    assert_eq!(file.read_to_end(&mut buf).await.unwrap(), 0);
+
+    // anything else goes to the network
+    file.seek(std::io::SeekFrom::Start(10)).await.unwrap_err();
+    assert_eq!(file.stream_position().await.unwrap(), 100);
+}
+
+#[tokio::test]
+async fn unknown_length_ends_with_the_stream() {
+    let data = Bytes::from(vec![1u8; 5000]);
+    let mut file = HttpFile::from_parts(
+        reqwest::Client::new(),
+        unreachable_url(),
+        FileMeta::default(),
+        Some(chunked(&data, 0, 1024)),
+    );
+    assert_eq!(file.content_length(), None);
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use common::{MockFile, random_bytes};
+use remote_file::{HashingReader, HttpFile};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
+
+#[tokio::test]
+async fn digest_of_the_bytes_read() {
+    let data = random_bytes(100_000);
+    let url = MockFile::new(data.clone()).serve().await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let mut reader = HashingReader::<_, Sha256>::new(file);
+
+    let mut buf = vec![0u8; 1_000];
+    reader.read_exact(&mut buf).await.unwrap();
+    assert!(reader.digest().is_none(), "not at EOF yet");
+    let mut rest = vec![];
+    reader.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(reader.digest(), Some(&Sha256::digest(&data)));
+    assert_eq!(reader.get_ref().position(), 100_000);
+
+    let (_, digest) = reader.into_inner();
+    assert_eq!(digest, Some(Sha256::digest(&data)));
+}
+
+#[tokio::test]
+async fn hasher_carries_on() {
+    let data = random_bytes(1_000);
+    let mut hasher = md5::Md5::new();
+    hasher.update(b"prefix");
+    let mut reader = HashingReader::with_hasher(&data[..], hasher);
+    let mut buf = vec![];
+    reader.read_to_end(&mut buf).await.unwrap();
+    let expected = md5::Md5::new().chain_update(b"prefix").chain_update(&data);
+    assert_eq!(reader.digest(), Some(&expected.finalize()));
+}
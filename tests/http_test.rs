@@ -1,18 +1,16 @@
-use core::panic;
 use std::{io::Write, net::SocketAddr, path::Path};
 
+mod common;
+
 use axum::Router;
 use remote_file::HttpFile;
 use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use tower_http::services::ServeDir;
 
-async fn setup_file_server(path: String, addr: SocketAddr) {
+/// Serve the files in `path` under `/files` on a random local port.
+async fn setup_file_server(path: String) -> SocketAddr {
     let app = Router::new().nest_service("/files", ServeDir::new(path));
-
-    // Bind to a socket address
-    log::info!("listening on {}", addr);
-    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    common::serve(app).await
 }
 
 fn create_test_file(file_path: &Path) {
@@ -42,22 +40,16 @@ async fn random_file_read() {
     create_test_file(&file_path);
     let mut file = tokio::fs::File::open(&file_path).await.unwrap();
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 13567));
-    let url = format!("http://localhost:13567/files/{}", file_name);
-
     // start file server
-    let workdir_str = workdir.to_string_lossy().into_owned();
-    tokio::spawn(async move {
-        setup_file_server(workdir_str, addr).await;
-        panic!("file server exited unexpectedly");
-    });
+    let addr = setup_file_server(workdir.to_string_lossy().into_owned()).await;
+    let url = format!("http://{}/files/{}", addr, file_name);
 
     let client = reqwest::Client::new();
     let resp = client
         .head(&url)
         .send()
         .await
-        .expect("failed to send request, forgot to start server?");
+        .expect("failed to send request");
 
     // len should be the same
     let len1 = file.metadata().await.unwrap().len();
@@ -118,19 +110,11 @@ async fn seek_to_end_of_file() {
     create_test_file(&file_path);
     let mut local_file = tokio::fs::File::open(&file_path).await.unwrap();
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 13568));
-    let url = format!("http://localhost:13568/files/{}", file_name);
-
     // start file server
-    let workdir_str = workdir.to_string_lossy().into_owned();
-    tokio::spawn(async move {
-        setup_file_server(workdir_str, addr).await;
-        panic!("file server exited unexpectedly");
-    });
+    let addr = setup_file_server(workdir.to_string_lossy().into_owned()).await;
+    let url = format!("http://{}/files/{}", addr, file_name);
 
     let client = reqwest::Client::new();
-    // Wait for server to be ready
-    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
     let mut http_file = HttpFile::new(client, &url).await.unwrap();
     let file_length = http_file.content_length().unwrap();
@@ -194,3 +178,42 @@ async fn seek_to_end_of_file() {
     let remote_bytes = http_file.read(&mut buf2).await.unwrap();
     assert_eq!(remote_bytes, 0, "should still read 0 bytes at EOF");
 }
+
+#[tokio::test]
+async fn seek_to_eof_drops_buffered_chunk() {
+    let data = common::random_bytes(256 * 1024);
+    let mock = common::MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut http_file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let file_length = data.len() as u64;
+
+    // a small read leaves the rest of the chunk buffered
+    let mut buf = [0u8; 10];
+    http_file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[..10]);
+    assert_eq!(mock.gets(), 1);
+
+    for seek in [
+        std::io::SeekFrom::End(0),
+        std::io::SeekFrom::Start(file_length),
+    ] {
+        let pos = http_file.seek(seek).await.unwrap();
+        assert_eq!(pos, file_length, "should be at EOF after {:?}", seek);
+        let read = http_file.read(&mut buf).await.unwrap();
+        assert_eq!(read, 0, "nothing buffered should be read after {:?}", seek);
+    }
+    assert_eq!(mock.gets(), 1, "seeking to EOF makes no request");
+
+    // back into the file, then to EOF relative to the current position
+    http_file.seek(std::io::SeekFrom::Start(100)).await.unwrap();
+    http_file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[100..110]);
+    let gets = mock.gets();
+    let pos = http_file
+        .seek(std::io::SeekFrom::Current((file_length - 110) as i64))
+        .await
+        .unwrap();
+    assert_eq!(pos, file_length);
+    assert_eq!(http_file.read(&mut buf).await.unwrap(), 0);
+    assert_eq!(mock.gets(), gets, "seeking to EOF makes no request");
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::MockFile;
+use futures_util::TryStreamExt;
+use remote_file::HttpFile;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt};
+
+#[tokio::test]
+async fn lines_stream_matches_local_lines() {
+    let mut text = String::new();
+    for i in 0..5000 {
+        let ending = if i % 3 == 0 { "\r\n" } else { "\n" };
+        text.push_str(&format!("line {i}: {}{ending}", "x".repeat(i % 50)));
+    }
+    text.push_str("no trailing newline");
+
+    let mock = MockFile::new(text.clone());
+    let url = mock.serve().await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let lines: Vec<String> = file.lines_stream().try_collect().await.unwrap();
+
+    assert_eq!(lines.len(), 5001);
+    assert_eq!(lines, text.lines().collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn lines_stream_from_current_position() {
+    let mock = MockFile::new("first\nsecond\nthird\n");
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    file.seek(std::io::SeekFrom::Start(6)).await.unwrap();
+
+    let lines: Vec<String> = file.lines_stream().try_collect().await.unwrap();
+    assert_eq!(lines, ["second", "third"]);
+}
+
+#[tokio::test]
+async fn buf_read_without_a_buf_reader() {
+    let text = "alpha\nbeta;gamma\n".repeat(2000);
+    let mock = MockFile::new(text.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let mut line = String::new();
+    file.read_line(&mut line).await.unwrap();
+    assert_eq!(line, "alpha\n");
+    let mut field = vec![];
+    file.read_until(b';', &mut field).await.unwrap();
+    assert_eq!(field, b"beta;");
+    assert_eq!(file.stream_position().await.unwrap(), 11);
+
+    // a seek drops the buffered chunk, the next fill starts from there
+    file.seek(std::io::SeekFrom::End(-6)).await.unwrap();
+    assert_eq!(file.fill_buf().await.unwrap(), b"gamma\n");
+    file.consume(5);
+    let mut rest = vec![];
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, b"\n");
+    assert!(file.fill_buf().await.unwrap().is_empty());
+
+    let mut lines = vec![];
+    file.rewind().await.unwrap();
+    let mut reader = file.lines();
+    while let Some(line) = reader.next_line().await.unwrap() {
+        lines.push(line);
+    }
+    assert_eq!(lines.len(), 4000);
+    assert_eq!(lines.join("\n") + "\n", text);
+}
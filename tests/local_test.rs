@@ -0,0 +1,54 @@
+mod common;
+
+use std::io::SeekFrom;
+
+use common::{MockFile, random_bytes};
+use remote_file::AnyFile;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+async fn read_tail(file: &mut AnyFile, from: u64) -> Vec<u8> {
+    file.seek(SeekFrom::Start(from)).await.unwrap();
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf).await.unwrap();
+    buf
+}
+
+#[tokio::test]
+async fn file_urls_open_from_the_filesystem() {
+    let data = random_bytes(10_000);
+    let path = std::env::temp_dir().join(format!("remote-file-local-{}.json", std::process::id()));
+    std::fs::write(&path, &data).unwrap();
+    let url = reqwest::Url::from_file_path(&path).unwrap();
+
+    let mut file = AnyFile::open(reqwest::Client::new(), url.as_str())
+        .await
+        .unwrap();
+    assert!(matches!(file, AnyFile::Local(_)));
+    assert_eq!(file.content_length(), Some(10_000));
+    assert_eq!(file.mime(), Some("application/json"));
+    assert_eq!(read_tail(&mut file, 9000).await, data[9000..]);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn http_urls_open_over_http() {
+    let data = random_bytes(10_000);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+
+    let mut file = AnyFile::open(reqwest::Client::new(), &url).await.unwrap();
+    assert!(matches!(file, AnyFile::Http(_)));
+    assert_eq!(file.content_length(), Some(10_000));
+    assert_eq!(read_tail(&mut file, 9000).await, data[9000..]);
+}
+
+#[tokio::test]
+async fn missing_local_file_is_not_found() {
+    let path = std::env::temp_dir().join("remote-file-no-such-file");
+    let url = reqwest::Url::from_file_path(&path).unwrap();
+    let err = AnyFile::open(reqwest::Client::new(), url.as_str())
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
@@ -0,0 +1,62 @@
+use std::io::SeekFrom;
+
+use remote_file::{MemoryFile, RemoteFile};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt};
+
+#[tokio::test]
+async fn reads_and_seeks_like_a_file() {
+    let data: Vec<u8> = (0..=255).collect();
+    let mut file = MemoryFile::new(data.clone())
+        .with_etag("\"v1\"")
+        .with_mime("application/octet-stream");
+    assert_eq!(file.content_length(), Some(256));
+    assert_eq!(file.etag(), Some("\"v1\""));
+    assert_eq!(file.mime(), Some("application/octet-stream"));
+
+    let mut buf = [0u8; 16];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[..16]);
+    assert_eq!(file.seek(SeekFrom::Current(16)).await.unwrap(), 32);
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[32..48]);
+    assert_eq!(file.seek(SeekFrom::End(-8)).await.unwrap(), 248);
+    let mut rest = Vec::new();
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[248..]);
+}
+
+#[tokio::test]
+async fn empty_file_is_at_eof() {
+    let mut file = MemoryFile::new(Vec::new());
+    assert_eq!(file.content_length(), Some(0));
+    assert_eq!(file.seek(SeekFrom::End(0)).await.unwrap(), 0);
+    let mut buf = [0u8; 4];
+    assert_eq!(file.read(&mut buf).await.unwrap(), 0);
+    assert!(file.fill_buf().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn seeks_outside_the_file_fail() {
+    let mut file = MemoryFile::new(vec![1u8; 10]);
+    assert_eq!(file.seek(SeekFrom::Start(10)).await.unwrap(), 10);
+    let mut buf = [0u8; 4];
+    assert_eq!(file.read(&mut buf).await.unwrap(), 0);
+
+    for seek in [
+        SeekFrom::Start(11),
+        SeekFrom::End(1),
+        SeekFrom::Current(-11),
+    ] {
+        let err = file.seek(seek).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput, "{seek:?}");
+    }
+    assert_eq!(file.position(), 10, "a failed seek doesn't move");
+}
+
+#[tokio::test]
+async fn reads_lines() {
+    let mut lines = MemoryFile::new("one\ntwo\n").lines();
+    assert_eq!(lines.next_line().await.unwrap().as_deref(), Some("one"));
+    assert_eq!(lines.next_line().await.unwrap().as_deref(), Some("two"));
+    assert_eq!(lines.next_line().await.unwrap(), None);
+}
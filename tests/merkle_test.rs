@@ -0,0 +1,111 @@
+mod common;
+
+use axum::http::header;
+use common::{MockFile, random_bytes};
+use remote_file::{HttpFile, HttpFileError, MerkleTree};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+const CHUNK: usize = 1024;
+
+fn tree_of(data: &[u8]) -> MerkleTree {
+    let tree = MerkleTree::from_chunks(data.chunks(CHUNK));
+    // as a reader would get it: leaves from anywhere, checked against a trusted root
+    MerkleTree::new(tree.root(), tree.leaves().to_vec()).unwrap()
+}
+
+fn ranges(mock: &MockFile) -> Vec<String> {
+    mock.requests()
+        .into_iter()
+        .filter_map(|(_, headers)| headers.get(header::RANGE).cloned())
+        .map(|range| range.to_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn verified_random_access() {
+    // the last chunk is short
+    let data = random_bytes(10 * CHUNK - 240);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::builder()
+        .with_chunk_merkle(tree_of(&data), CHUNK as u64)
+        .build(&url)
+        .await
+        .unwrap();
+
+    file.seek(std::io::SeekFrom::Start(3000)).await.unwrap();
+    let mut buf = vec![0u8; 2000];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[3000..5000]);
+    assert_eq!(file.stream_position().await.unwrap(), 5000);
+
+    let mut buf = vec![0u8; 300];
+    file.read_exact_at(data.len() as u64 - 500, &mut buf)
+        .await
+        .unwrap();
+    assert_eq!(buf, data[data.len() - 500..data.len() - 200]);
+
+    file.seek(std::io::SeekFrom::Start(8000)).await.unwrap();
+    let mut rest = vec![];
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[8000..]);
+
+    assert_eq!(
+        ranges(&mock),
+        ["bytes=2048-", "bytes=9216-9999", "bytes=7168-"]
+    );
+}
+
+#[tokio::test]
+async fn tampered_chunk_fails_verification() {
+    let data = random_bytes(8 * CHUNK);
+    let tree = tree_of(&data);
+    let mut tampered = data.clone();
+    tampered[5 * CHUNK + 100] ^= 0xff;
+    let mock = MockFile::new(tampered);
+    let url = mock.serve().await;
+    let mut file = HttpFile::builder()
+        .with_chunk_merkle(tree, CHUNK as u64)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![0u8; 100];
+    file.read_exact_at(CHUNK as u64, &mut buf).await.unwrap();
+    assert_eq!(buf, data[CHUNK..CHUNK + 100]);
+
+    let assert_mismatch = |err: std::io::Error| {
+        let err = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<HttpFileError>())
+            .expect("should be an HttpFileError");
+        assert!(
+            matches!(err, HttpFileError::ChunkHashMismatch { index: 5 }),
+            "unexpected error: {err}"
+        );
+    };
+    let mut buf = vec![0u8; 10];
+    assert_mismatch(
+        file.read_exact_at(5 * CHUNK as u64 + 1000, &mut buf)
+            .await
+            .unwrap_err(),
+    );
+
+    // nothing past the last good chunk is read
+    file.seek(std::io::SeekFrom::Start(0)).await.unwrap();
+    let mut buf = vec![];
+    assert_mismatch(file.read_to_end(&mut buf).await.unwrap_err());
+    assert_eq!(buf, data[..5 * CHUNK]);
+}
+
+#[tokio::test]
+async fn leaves_must_match_the_root() {
+    let data = random_bytes(4 * CHUNK);
+    let tree = MerkleTree::from_chunks(data.chunks(CHUNK));
+    let mut leaves = tree.leaves().to_vec();
+    leaves[2][0] ^= 1;
+    assert!(matches!(
+        MerkleTree::new(tree.root(), leaves),
+        Err(HttpFileError::MerkleRootMismatch)
+    ));
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use std::collections::HashMap;
+
+use axum::{Router, extract::Query, http::header, response::IntoResponse, routing::any};
+use remote_file::HttpFile;
+
+/// Serve a file under any path, answering with every query parameter as a
+/// header of the same name.
+async fn serve_with_headers() -> String {
+    let app = Router::new().route(
+        "/{*path}",
+        any(|Query(query): Query<HashMap<String, String>>| async move {
+            let mut resp = "hello".into_response();
+            for (name, value) in query {
+                let name = header::HeaderName::try_from(name).unwrap();
+                resp.headers_mut().insert(name, value.parse().unwrap());
+            }
+            resp
+        }),
+    );
+    format!("http://{}", common::serve(app).await)
+}
+
+/// Open the file at `path`, served with `headers`.
+async fn open(base: &str, path: &str, headers: &[(&str, &str)]) -> HttpFile {
+    let mut url = reqwest::Url::parse(&format!("{}{}", base, path)).unwrap();
+    if !headers.is_empty() {
+        url.query_pairs_mut().extend_pairs(headers);
+    }
+    HttpFile::new(reqwest::Client::new(), url.as_str())
+        .await
+        .unwrap()
+}
+
+async fn filename(base: &str, path: &str, disposition: Option<&str>) -> Option<String> {
+    let headers: Vec<_> = disposition
+        .map(|value| ("content-disposition", value))
+        .into_iter()
+        .collect();
+    open(base, path, &headers).await.filename()
+}
+
+#[tokio::test]
+async fn filename_from_content_disposition() {
+    let base = serve_with_headers().await;
+    let cases = [
+        ("attachment; filename=report.pdf", "report.pdf"),
+        (
+            "attachment; filename=\"annual \\\"report\\\"; 2024.pdf\"",
+            "annual \"report\"; 2024.pdf",
+        ),
+        (
+            "attachment; filename=\"fallback.txt\"; filename*=UTF-8''na%C3%AFve%20r%C3%A9sum%C3%A9.txt",
+            "naïve résumé.txt",
+        ),
+        (
+            "inline; FILENAME*=iso-8859-1'en'%A3%20rates.csv",
+            "£ rates.csv",
+        ),
+        ("attachment; filename=\"../../etc/passwd\"", "passwd"),
+        // a malformed extended name falls back on the plain one
+        (
+            "attachment; filename*=UTF-8''bad%zz; filename=plain.bin",
+            "plain.bin",
+        ),
+    ];
+    for (disposition, expected) in cases {
+        assert_eq!(
+            filename(&base, "/download", Some(disposition))
+                .await
+                .as_deref(),
+            Some(expected),
+            "{}",
+            disposition
+        );
+    }
+}
+
+#[tokio::test]
+async fn filename_falls_back_to_the_url() {
+    let base = serve_with_headers().await;
+    assert_eq!(
+        filename(&base, "/files/data%20set.tar.gz", None)
+            .await
+            .as_deref(),
+        Some("data set.tar.gz")
+    );
+    // no name in the header either
+    assert_eq!(
+        filename(&base, "/files/archive.zip", Some("attachment"))
+            .await
+            .as_deref(),
+        Some("archive.zip")
+    );
+    assert_eq!(filename(&base, "/files/", None).await, None);
+}
+
+#[tokio::test]
+async fn head_headers_are_kept() {
+    let base = serve_with_headers().await;
+    let file = open(
+        &base,
+        "/file",
+        &[
+            ("x-amz-meta-owner", "someone"),
+            ("cache-control", "max-age=60"),
+        ],
+    )
+    .await;
+    assert_eq!(file.headers()["x-amz-meta-owner"], "someone");
+    assert_eq!(file.headers()[header::CACHE_CONTROL], "max-age=60");
+    assert_eq!(file.headers()[header::CONTENT_LENGTH], "5");
+    // forks share them
+    assert_eq!(file.try_clone().headers(), file.headers());
+
+    let file = HttpFile::builder()
+        .skip_head(5)
+        .build(&format!("{}/file", base))
+        .await
+        .unwrap();
+    assert!(file.headers().is_empty());
+}
+
+#[tokio::test]
+async fn last_modified_is_parsed() {
+    let base = serve_with_headers().await;
+    let date = "Sun, 06 Nov 1994 08:49:37 GMT";
+    let file = open(&base, "/file", &[("last-modified", date)]).await;
+    assert_eq!(file.last_modified_str(), Some(date));
+    assert_eq!(
+        file.last_modified(),
+        Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(784111777))
+    );
+
+    let file = open(&base, "/file", &[("last-modified", "yesterday")]).await;
+    assert_eq!(file.last_modified_str(), Some("yesterday"));
+    assert_eq!(file.last_modified(), None);
+
+    let file = open(&base, "/file", &[]).await;
+    assert_eq!(file.last_modified(), None);
+}
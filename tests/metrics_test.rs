@@ -0,0 +1,77 @@
+mod common;
+
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use common::{MockFile, random_bytes};
+use remote_file::{HttpFile, Metrics};
+use reqwest::{Method, StatusCode};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+#[derive(Default)]
+struct Counting {
+    requests: Mutex<Vec<(Method, Option<StatusCode>)>>,
+    bytes: AtomicU64,
+    retries: AtomicU64,
+}
+
+impl Metrics for Counting {
+    fn request(&self, method: &Method, status: Option<StatusCode>, _latency: Duration) {
+        self.requests.lock().unwrap().push((method.clone(), status));
+    }
+
+    fn bytes_received(&self, bytes: u64) {
+        self.bytes.fetch_add(bytes, Ordering::SeqCst);
+    }
+
+    fn retry(&self) {
+        self.retries.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[tokio::test]
+async fn metrics_record_a_read_session() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let metrics = Arc::new(Counting::default());
+    let mut file = HttpFile::builder()
+        .with_metrics(metrics.clone())
+        .build(&url)
+        .await
+        .unwrap();
+    assert_eq!(
+        *metrics.requests.lock().unwrap(),
+        [(Method::HEAD, Some(StatusCode::OK))]
+    );
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    file.seek(std::io::SeekFrom::Start(1000)).await.unwrap();
+    let mut buf = vec![0u8; 24 * 1024];
+    file.read_exact(&mut buf).await.unwrap();
+    file.read_exact_at(60 * 1024, &mut buf[..1024])
+        .await
+        .unwrap();
+
+    let partial = Some(StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        *metrics.requests.lock().unwrap(),
+        [
+            (Method::HEAD, Some(StatusCode::OK)),
+            (Method::GET, partial),
+            (Method::GET, partial),
+            (Method::GET, partial),
+        ]
+    );
+    // the second response may be dropped before it is read to the end
+    let bytes = metrics.bytes.load(Ordering::SeqCst);
+    assert!(bytes >= (data.len() + 24 * 1024 + 1024) as u64);
+    assert!(bytes <= (2 * data.len() - 1000 + 1024) as u64);
+    assert_eq!(metrics.retries.load(Ordering::SeqCst), 0);
+}
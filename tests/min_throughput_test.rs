@@ -0,0 +1,53 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{MockFile, builder_at_rate, random_bytes};
+use remote_file::HttpFileError;
+use tokio::io::AsyncReadExt;
+
+#[tokio::test]
+async fn slow_transfer_is_aborted_after_grace() {
+    let data = random_bytes(256 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = builder_at_rate(1024)
+        .with_min_throughput(10 * 1024, Duration::from_secs(30))
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![];
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    let err = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<HttpFileError>())
+        .expect("should be an HttpFileError");
+    match err {
+        HttpFileError::TooSlow { bytes_per_sec, min } => {
+            assert_eq!(*min, 10 * 1024);
+            assert!(*bytes_per_sec < 10 * 1024, "{bytes_per_sec}");
+        }
+        e => panic!("unexpected error: {e}"),
+    }
+    // the grace period let about 30 seconds worth of data through
+    assert!(buf.len() >= 30 * 1024, "{}", buf.len());
+    assert!(buf.len() < data.len(), "{}", buf.len());
+    assert_eq!(buf, data[..buf.len()]);
+}
+
+#[tokio::test]
+async fn fast_enough_transfer_completes() {
+    let data = random_bytes(256 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = builder_at_rate(1024)
+        .with_min_throughput(512, Duration::from_secs(5))
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use std::io::SeekFrom;
+
+use common::{MockFile, random_bytes};
+use remote_file::{HttpFile, HttpFileError};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+#[tokio::test]
+async fn reads_fail_over_and_resume_on_the_next_mirror() {
+    let data = random_bytes(64 * 1024);
+    let primary = MockFile::new(data.clone()).with_etag("\"v1\"");
+    let mirror = MockFile::new(data.clone()).with_etag("\"v1\"");
+    let (primary_url, mirror_url) = (primary.serve().await, mirror.serve().await);
+    let mut file = HttpFile::builder()
+        .retries(0)
+        .with_mirrors(&[&mirror_url])
+        .build(&primary_url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![0u8; 1000];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[..1000]);
+    assert_eq!(file.stats().mirror, 0);
+
+    primary.set_failing(true);
+    file.seek(SeekFrom::Start(40_000)).await.unwrap();
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[40_000..41_000]);
+
+    let stats = file.stats();
+    assert_eq!((stats.mirror, stats.failovers), (1, 1));
+    assert_eq!(file.url().as_str(), mirror_url);
+    let requests = mirror.requests();
+    assert_eq!(requests.last().unwrap().1["range"], "bytes=40000-");
+}
+
+#[tokio::test]
+async fn every_mirror_failing_fails_the_read() {
+    let data = random_bytes(1024);
+    let mirrors = [MockFile::new(data.clone()), MockFile::new(data.clone())];
+    let urls = [mirrors[0].serve().await, mirrors[1].serve().await];
+    let mut file = HttpFile::builder()
+        .retries(0)
+        .with_mirrors(&[&urls[1]])
+        .build(&urls[0])
+        .await
+        .unwrap();
+    mirrors.iter().for_each(|m| m.set_failing(true));
+
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf).await.unwrap_err();
+    assert_eq!(mirrors.iter().map(MockFile::gets).sum::<usize>(), 2);
+}
+
+#[tokio::test]
+async fn diverging_mirror_is_an_error() {
+    let data = random_bytes(1024);
+    let primary = MockFile::new(data.clone()).with_etag("\"v1\"");
+    let mirror = MockFile::new(data.clone()).with_etag("\"v2\"");
+    let (primary_url, mirror_url) = (primary.serve().await, mirror.serve().await);
+    let mut file = HttpFile::builder()
+        .retries(0)
+        .with_mirrors(&[&mirror_url])
+        .build(&primary_url)
+        .await
+        .unwrap();
+    primary.set_failing(true);
+
+    let mut buf = Vec::new();
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    let err = err.get_ref().unwrap().downcast_ref::<HttpFileError>();
+    assert!(
+        matches!(err, Some(HttpFileError::MirrorMismatch { url }) if *url == mirror_url),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn opening_tries_the_mirrors_in_turn() {
+    let data = random_bytes(1024);
+    let mirror = MockFile::new(data.clone());
+    let mirror_url = mirror.serve().await;
+    // nothing listens there
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let dead_url = format!("http://{}/file", listener.local_addr().unwrap());
+    drop(listener);
+
+    let mut file = HttpFile::new_with_mirrors(reqwest::Client::new(), &dead_url, &[&mirror_url])
+        .await
+        .unwrap();
+    assert_eq!(file.stats().mirror, 1);
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+}
@@ -0,0 +1,198 @@
+mod common;
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use axum::{
+    Router,
+    body::{Body, Bytes},
+    extract::Request,
+    http::{Method, StatusCode, header},
+    response::Response,
+    routing::any,
+};
+use common::{MockFile, random_bytes, range_response};
+use remote_file::{FileMeta, HttpFile, HttpFileError};
+
+const BOUNDARY: &str = "3d6b6a416f9b5";
+
+/// The inclusive ranges of a `Range` header with several of them.
+fn ranges(req: &Request) -> Vec<(usize, usize)> {
+    let value = req.headers()[header::RANGE].to_str().unwrap();
+    value
+        .strip_prefix("bytes=")
+        .unwrap()
+        .split(',')
+        .map(|spec| {
+            let (start, end) = spec.split_once('-').unwrap();
+            (start.parse().unwrap(), end.parse().unwrap())
+        })
+        .collect()
+}
+
+/// Serve `data`, answering multi-range requests with the parts in reverse
+/// order, or with only the first range when `first_only`. Returns the url
+/// and the number of `GET`s.
+async fn serve_multipart(data: Vec<u8>, first_only: bool) -> (String, Arc<AtomicUsize>) {
+    let data = Bytes::from(data);
+    let gets = Arc::new(AtomicUsize::new(0));
+    let counter = gets.clone();
+    let app = Router::new().route(
+        "/file",
+        any(move |req: Request| async move {
+            if req.method() == Method::GET {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+            let multi = req
+                .headers()
+                .get(header::RANGE)
+                .is_some_and(|v| v.to_str().unwrap().contains(','));
+            if !multi {
+                return range_response(req.method(), req.headers(), data.clone(), None);
+            }
+            let len = data.len();
+            let builder = Response::builder().status(StatusCode::PARTIAL_CONTENT);
+            if first_only {
+                let (start, end) = ranges(&req)[0];
+                return builder
+                    .header(
+                        header::CONTENT_RANGE,
+                        format!("bytes {}-{}/{}", start, end, len),
+                    )
+                    .body(Body::from(data.slice(start..=end)))
+                    .unwrap();
+            }
+            let mut body = b"preamble\r\n".to_vec();
+            for (start, end) in ranges(&req).into_iter().rev() {
+                body.extend_from_slice(
+                    format!(
+                        "--{}\r\nContent-Type: application/octet-stream\r\n\
+                         Content-Range: bytes {}-{}/{}\r\n\r\n",
+                        BOUNDARY, start, end, len
+                    )
+                    .as_bytes(),
+                );
+                body.extend_from_slice(&data[start..=end]);
+                body.extend_from_slice(b"\r\n");
+            }
+            body.extend_from_slice(format!("--{}--\r\n", BOUNDARY).as_bytes());
+            builder
+                .header(
+                    header::CONTENT_TYPE,
+                    format!("multipart/byteranges; boundary=\"{}\"", BOUNDARY),
+                )
+                .body(Body::from(body))
+                .unwrap()
+        }),
+    );
+    (format!("http://{}/file", common::serve(app).await), gets)
+}
+
+#[tokio::test]
+async fn multipart_response_is_split_per_range() {
+    let data = random_bytes(10_000);
+    let (url, gets) = serve_multipart(data.clone(), false).await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let parts = file
+        .read_ranges(&[100..200, 5..5, 9_000..10_000, 150..160])
+        .await
+        .unwrap();
+    assert_eq!(parts[0], data[100..200]);
+    assert!(parts[1].is_empty());
+    assert_eq!(parts[2], data[9_000..]);
+    assert_eq!(parts[3], data[150..160]);
+    assert_eq!(gets.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn single_part_response_fetches_the_rest() {
+    let data = random_bytes(10_000);
+    let (url, gets) = serve_multipart(data.clone(), true).await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let parts = file
+        .read_ranges(&[0..10, 500..600, 7_000..7_010])
+        .await
+        .unwrap();
+    assert_eq!(parts[0], data[..10]);
+    assert_eq!(parts[1], data[500..600]);
+    assert_eq!(parts[2], data[7_000..7_010]);
+    assert_eq!(gets.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn whole_file_response_serves_every_range() {
+    let data = random_bytes(10_000);
+    // the mock file ignores multi-range requests
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let parts = file.read_ranges(&[10..20, 3_000..3_500]).await.unwrap();
+    assert_eq!(parts[0], data[10..20]);
+    assert_eq!(parts[1], data[3_000..3_500]);
+    assert_eq!(mock.gets(), 1);
+}
+
+#[tokio::test]
+async fn ranges_of_a_changed_snapshot_fail() {
+    let data = random_bytes(10_000);
+    let mock = MockFile::new(data.clone()).with_etag("\"v1\"");
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    file.snapshot();
+
+    mock.set(random_bytes(10_000), Some("\"v2\""));
+    let err = file.read_ranges(&[10..20, 3_000..3_500]).await.unwrap_err();
+    assert!(
+        matches!(err, HttpFileError::FileChanged { .. }),
+        "unexpected error: {err}"
+    );
+}
+
+#[tokio::test]
+async fn ranges_past_the_end_come_back_short_or_empty() {
+    let data = random_bytes(10_000);
+    let (url, gets) = serve_multipart(data.clone(), false).await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let parts = file
+        .read_ranges(&[9_990..10_100, 10_000..10_010, 20_000..30_000, 0..10])
+        .await
+        .unwrap();
+    assert_eq!(parts[0], data[9_990..]);
+    assert!(parts[1].is_empty());
+    assert!(parts[2].is_empty());
+    assert_eq!(parts[3], data[..10]);
+    assert_eq!(gets.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn ranges_past_the_end_of_unknown_length_come_back_empty() {
+    let data = random_bytes(10_000);
+    let url = MockFile::new(data.clone()).serve().await;
+    let file = HttpFile::from_parts(
+        reqwest::Client::new(),
+        url.parse().unwrap(),
+        FileMeta::default(),
+        None,
+    );
+    assert_eq!(file.content_length(), None);
+
+    let parts = file
+        .read_ranges(&[20_000..20_010, 9_990..10_100])
+        .await
+        .unwrap();
+    assert!(parts[0].is_empty());
+    assert_eq!(parts[1], data[9_990..]);
+
+    // a multi-range request with none of them satisfiable
+    let parts = file
+        .read_ranges(&[20_000..20_010, 30_000..30_010])
+        .await
+        .unwrap();
+    assert!(parts.iter().all(|part| part.is_empty()));
+}
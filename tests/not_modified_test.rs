@@ -0,0 +1,84 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    Router,
+    http::{HeaderMap, Method, StatusCode, header},
+    response::IntoResponse,
+    routing::any,
+};
+use remote_file::{HttpFile, HttpFileError};
+use tokio::io::AsyncReadExt;
+
+/// Answer every `GET` with `304 Not Modified`, like a misbehaving cache.
+async fn serve_not_modified() -> String {
+    let app = Router::new().route(
+        "/file",
+        any(|method: Method| async move {
+            if method == Method::HEAD {
+                [(header::CONTENT_LENGTH, "1000")].into_response()
+            } else {
+                StatusCode::NOT_MODIFIED.into_response()
+            }
+        }),
+    );
+    let addr = common::serve(app).await;
+    format!("http://{}/file", addr)
+}
+
+fn assert_not_modified(err: std::io::Error) {
+    let err = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<HttpFileError>())
+        .expect("should be an HttpFileError");
+    assert!(matches!(err, HttpFileError::UnexpectedNotModified));
+}
+
+#[tokio::test]
+async fn unconditional_304_is_an_error() {
+    let url = serve_not_modified().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert_eq!(file.content_length(), Some(1000));
+
+    let mut buf = vec![];
+    assert_not_modified(file.read_to_end(&mut buf).await.unwrap_err());
+    assert!(buf.is_empty());
+
+    let mut buf = [0u8; 10];
+    assert_not_modified(file.read_exact_at(100, &mut buf).await.unwrap_err());
+}
+
+/// Serve a file whose etag is the one in `etag`, honoring `If-None-Match`.
+async fn serve_revalidated(etag: Arc<Mutex<String>>) -> String {
+    let app = Router::new().route(
+        "/file",
+        any(move |headers: HeaderMap| {
+            let etag = etag.lock().unwrap().clone();
+            async move {
+                if headers
+                    .get(header::IF_NONE_MATCH)
+                    .is_some_and(|v| *v == *etag)
+                {
+                    return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+                }
+                ([(header::ETAG, etag)], "hello").into_response()
+            }
+        }),
+    );
+    let addr = common::serve(app).await;
+    format!("http://{}/file", addr)
+}
+
+#[tokio::test]
+async fn revalidation_by_etag() {
+    let etag = Arc::new(Mutex::new("\"v1\"".to_string()));
+    let url = serve_revalidated(etag.clone()).await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let cached = file.etag().unwrap().to_string();
+    assert!(!file.is_modified_since(&cached).await.unwrap());
+    assert!(file.is_modified_since("\"v0\"").await.unwrap());
+
+    *etag.lock().unwrap() = "\"v2\"".to_string();
+    assert!(file.is_modified_since(&cached).await.unwrap());
+}
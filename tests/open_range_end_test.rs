@@ -0,0 +1,44 @@
+mod common;
+
+use axum::http::header;
+use common::{MockFile, random_bytes};
+use remote_file::HttpFile;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+#[tokio::test]
+async fn open_range_end_sentinel() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::builder()
+        .with_open_range_end(99_999_999_999)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![0u8; 1024];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[..1024]);
+    file.seek(std::io::SeekFrom::Start(50_000)).await.unwrap();
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[50_000..51_024]);
+
+    let ranges: Vec<_> = mock
+        .requests()
+        .into_iter()
+        .filter_map(|(_, headers)| headers.get(header::RANGE).cloned())
+        .collect();
+    assert_eq!(ranges, ["bytes=0-99999999999", "bytes=50000-99999999999"]);
+}
+
+#[tokio::test]
+async fn open_ended_by_default() {
+    let mock = MockFile::new(random_bytes(1024));
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let mut buf = vec![0u8; 10];
+    file.read_exact(&mut buf).await.unwrap();
+    let (_, headers) = mock.requests().pop().unwrap();
+    assert_eq!(headers[header::RANGE], "bytes=0-");
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use bytes::Bytes;
+use common::random_bytes;
+use remote_file::HttpFile;
+use tokio::{
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// Serve `data` over raw HTTP/1.1, cutting off the first `drops` range
+/// responses after `sent` bytes of the body.
+async fn serve_flaky(data: Bytes, drops: usize, sent: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let dropped = Arc::new(AtomicUsize::new(0));
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let data = data.clone();
+            let dropped = dropped.clone();
+            tokio::spawn(async move {
+                let mut req = vec![];
+                let mut buf = [0u8; 1024];
+                while !req.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        return;
+                    }
+                    req.extend_from_slice(&buf[..n]);
+                }
+                let req = String::from_utf8(req).unwrap();
+
+                let len = data.len();
+                if req.starts_with("HEAD") {
+                    let head = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-length: {len}\r\naccept-ranges: bytes\r\nconnection: close\r\n\r\n"
+                    );
+                    socket.write_all(head.as_bytes()).await.unwrap();
+                    return;
+                }
+
+                let range = req
+                    .lines()
+                    .find_map(|l| {
+                        l.to_ascii_lowercase()
+                            .strip_prefix("range: ")
+                            .map(String::from)
+                    })
+                    .unwrap();
+                let Some(Ok((start, end))) = common::parse_range(&range, len as u64) else {
+                    panic!("unexpected range {range:?}");
+                };
+                let body = data.slice(start as usize..=end as usize);
+                let head = format!(
+                    "HTTP/1.1 206 Partial Content\r\ncontent-length: {}\r\ncontent-range: bytes {start}-{end}/{len}\r\nconnection: close\r\n\r\n",
+                    body.len()
+                );
+                socket.write_all(head.as_bytes()).await.unwrap();
+                if dropped.fetch_add(1, Ordering::SeqCst) < drops {
+                    socket.write_all(&body[..sent]).await.unwrap();
+                } else {
+                    socket.write_all(&body).await.unwrap();
+                }
+            });
+        }
+    });
+    format!("http://{}/file", addr)
+}
+
+#[tokio::test]
+async fn dropped_stream_resumes_from_position() {
+    let data = Bytes::from(random_bytes(64 * 1024));
+    let url = serve_flaky(data.clone(), 2, 1000).await;
+    let mut file = HttpFile::builder()
+        .with_partial_delivery(true)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+    assert_eq!(file.stream_position().await.unwrap(), data.len() as u64);
+    assert_eq!(file.connections_opened(), 3);
+}
+
+#[tokio::test]
+async fn dropped_stream_fails_by_default() {
+    let data = Bytes::from(random_bytes(64 * 1024));
+    let url = serve_flaky(data.clone(), 1, 1000).await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap_err();
+    assert_eq!(buf, data[..1000]);
+}
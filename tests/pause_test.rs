@@ -0,0 +1,36 @@
+mod common;
+
+use common::{MockFile, random_bytes};
+use remote_file::HttpFile;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+#[tokio::test]
+async fn pause_and_resume_from_same_position() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let mut buf = vec![0u8; 1000];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[..1000]);
+
+    file.pause();
+    assert_eq!(file.stream_position().await.unwrap(), 1000);
+    assert!(file.chunk_as_buf().is_none());
+    file.resume().await.unwrap();
+    assert_eq!(mock.gets(), 2, "resume opens a new response");
+    assert_eq!(file.stream_position().await.unwrap(), 1000);
+
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[1000..2000]);
+    assert_eq!(mock.gets(), 2);
+
+    // a second resume while streaming leaves the response alone
+    file.resume().await.unwrap();
+    assert_eq!(mock.gets(), 2);
+
+    let mut rest = vec![];
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[2000..]);
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use std::{
+    io::SeekFrom,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use axum::http::{StatusCode, header};
+use bytes::Bytes;
+use common::{MockFile, random_bytes};
+use futures_util::{FutureExt, StreamExt, future::BoxFuture};
+use remote_file::{HttpFile, Transport};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+const CHUNK: usize = 1_000;
+
+/// Answers range `GET`s itself with a body of `CHUNK` byte chunks, counting
+/// the bytes pulled from the bodies and the bodies dropped.
+#[derive(Clone)]
+struct CountingTransport {
+    data: Bytes,
+    pulled: Arc<AtomicUsize>,
+    dropped: Arc<AtomicUsize>,
+}
+
+/// Counts itself once dropped along with the body it is part of.
+struct DropCounter(Arc<AtomicUsize>);
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl Transport for CountingTransport {
+    fn execute(
+        &self,
+        client: &reqwest::Client,
+        request: reqwest::Request,
+    ) -> BoxFuture<'static, reqwest::Result<reqwest::Response>> {
+        let Some(range) = request.headers().get(header::RANGE) else {
+            return client.execute(request).boxed();
+        };
+        let range = range.to_str().unwrap();
+        let start: usize = range["bytes=".len()..range.len() - 1].parse().unwrap();
+        let len = self.data.len();
+        let chunks: Vec<_> = (start..len)
+            .step_by(CHUNK)
+            .map(|at| self.data.slice(at..(at + CHUNK).min(len)))
+            .collect();
+        let pulled = self.pulled.clone();
+        let counter = DropCounter(self.dropped.clone());
+        let body = futures_util::stream::iter(chunks).map(move |chunk| {
+            let _ = &counter;
+            pulled.fetch_add(chunk.len(), Ordering::SeqCst);
+            Ok::<_, std::io::Error>(chunk)
+        });
+        let resp = axum::http::Response::builder()
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, len - 1, len),
+            )
+            .body(reqwest::Body::wrap_stream(body))
+            .unwrap();
+        futures_util::future::ready(Ok(resp.into())).boxed()
+    }
+}
+
+async fn open(prefetch: u64) -> (HttpFile, CountingTransport, Bytes) {
+    let data = Bytes::from(random_bytes(20 * CHUNK));
+    let url = MockFile::new(data.clone()).serve().await;
+    let transport = CountingTransport {
+        data: data.clone(),
+        pulled: Arc::default(),
+        dropped: Arc::default(),
+    };
+    let file = HttpFile::builder()
+        .with_transport(Arc::new(transport.clone()))
+        .with_prefetch(prefetch)
+        .build(&url)
+        .await
+        .unwrap();
+    (file, transport, data)
+}
+
+/// Wait for the background reads to settle.
+async fn settle() {
+    tokio::time::sleep(Duration::from_millis(50)).await;
+}
+
+#[tokio::test]
+async fn reads_ahead_within_budget() {
+    let (mut file, transport, data) = open(5 * CHUNK as u64).await;
+    let mut buf = vec![0u8; CHUNK];
+    file.read_exact(&mut buf).await.unwrap();
+    settle().await;
+    // the budget, and the chunk waiting for room
+    assert_eq!(transport.pulled.load(Ordering::SeqCst), 7 * CHUNK);
+
+    file.read_exact(&mut buf).await.unwrap();
+    settle().await;
+    assert_eq!(transport.pulled.load(Ordering::SeqCst), 8 * CHUNK);
+
+    let mut rest = vec![];
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[2 * CHUNK..]);
+}
+
+#[tokio::test]
+async fn no_read_ahead_by_default() {
+    let (mut file, transport, _) = open(0).await;
+    let mut buf = vec![0u8; CHUNK];
+    file.read_exact(&mut buf).await.unwrap();
+    settle().await;
+    assert_eq!(transport.pulled.load(Ordering::SeqCst), CHUNK);
+}
+
+#[tokio::test]
+async fn seek_drops_read_ahead() {
+    let (mut file, transport, data) = open(5 * CHUNK as u64).await;
+    file.set_max_inline_skip(0);
+    let mut buf = vec![0u8; CHUNK];
+    file.read_exact(&mut buf).await.unwrap();
+    settle().await;
+    assert_eq!(transport.dropped.load(Ordering::SeqCst), 0);
+
+    file.seek(SeekFrom::Start(5 * CHUNK as u64)).await.unwrap();
+    settle().await;
+    assert_eq!(transport.dropped.load(Ordering::SeqCst), 1);
+    let mut rest = vec![];
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[5 * CHUNK..]);
+}
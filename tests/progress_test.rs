@@ -0,0 +1,63 @@
+mod common;
+
+use std::io::SeekFrom;
+use std::sync::{Arc, Mutex};
+
+use common::{MockFile, random_bytes};
+use remote_file::HttpFile;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt};
+
+type Calls = Arc<Mutex<Vec<(u64, Option<u64>)>>>;
+
+fn recorder() -> (Calls, remote_file::Progress) {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let sink = calls.clone();
+    let progress = Arc::new(move |pos, total| sink.lock().unwrap().push((pos, total)));
+    (calls, progress)
+}
+
+#[tokio::test]
+async fn progress_follows_reads_and_seeks() {
+    let data = random_bytes(4096);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let (calls, progress) = recorder();
+    let mut file = HttpFile::builder()
+        .with_progress(progress)
+        .build(&url)
+        .await
+        .unwrap();
+    assert!(calls.lock().unwrap().is_empty(), "opening reads nothing");
+
+    let mut head = [0u8; 100];
+    file.read_exact(&mut head).await.unwrap();
+    assert_eq!(calls.lock().unwrap().last(), Some(&(100, Some(4096))));
+
+    file.seek(SeekFrom::Start(3000)).await.unwrap();
+    assert_eq!(calls.lock().unwrap().last(), Some(&(3000, Some(4096))));
+
+    let mut rest = Vec::new();
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[3000..]);
+    let calls = calls.lock().unwrap();
+    assert_eq!(calls.last(), Some(&(4096, Some(4096))));
+    assert!(calls.windows(2).skip(1).all(|w| w[0].0 <= w[1].0));
+}
+
+#[tokio::test]
+async fn progress_follows_buffered_reads() {
+    let data = b"one\ntwo\nthree\n".to_vec();
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let (calls, progress) = recorder();
+    let mut file = HttpFile::builder()
+        .with_progress(progress)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut line = String::new();
+    file.read_line(&mut line).await.unwrap();
+    assert_eq!(line, "one\n");
+    assert_eq!(calls.lock().unwrap().last(), Some(&(4, Some(14))));
+}
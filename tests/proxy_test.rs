@@ -0,0 +1,156 @@
+mod common;
+
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
+};
+
+use axum::{
+    Router,
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri, header},
+    response::IntoResponse,
+    routing::any,
+};
+use remote_file::{CacheStatus, HttpFile, HttpFileError};
+use tokio::io::AsyncReadExt;
+
+const DATA: &[u8] = b"cached across the team";
+
+/// A caching proxy holding version `"v1"` of the file, or `"v2"` once
+/// `changed`, recording the URI and headers of every request.
+///
+/// A `GET` carrying the current etag in `If-None-Match` gets `304 Not
+/// Modified`, as once the proxy has checked its copy with the origin, and an
+/// unconditional one is served from the cache.
+async fn serve_proxy(changed: Arc<AtomicBool>) -> (String, Arc<Mutex<Vec<(Uri, HeaderMap)>>>) {
+    let requests = Arc::new(Mutex::new(vec![]));
+    let log = requests.clone();
+    let app = Router::new().fallback(any(move |method: Method, uri: Uri, headers: HeaderMap| {
+        log.lock().unwrap().push((uri, headers.clone()));
+        let etag = match changed.load(Ordering::SeqCst) {
+            false => "\"v1\"",
+            true => "\"v2\"",
+        };
+        async move {
+            if method == Method::HEAD {
+                return (
+                    [
+                        (header::CONTENT_LENGTH, DATA.len().to_string()),
+                        (header::ETAG, etag.to_string()),
+                        ("cache-status".parse().unwrap(), "proxy; fwd=miss".into()),
+                    ],
+                    (),
+                )
+                    .into_response();
+            }
+            let x_cache = match headers.get(header::IF_NONE_MATCH) {
+                Some(validator) if validator == etag => {
+                    return (StatusCode::NOT_MODIFIED, [("x-cache", "MISS")]).into_response();
+                }
+                Some(_) => "MISS",
+                None => "HIT from proxy",
+            };
+            (
+                [
+                    (header::ETAG, etag),
+                    (HeaderName::from_static("x-cache"), x_cache),
+                ],
+                DATA,
+            )
+                .into_response()
+        }
+    }));
+    let addr = common::serve(app).await;
+    (format!("http://{}", addr), requests)
+}
+
+async fn open(proxy: &str) -> HttpFile {
+    HttpFile::builder()
+        .with_proxy(reqwest::Proxy::http(proxy).unwrap())
+        .build("http://origin.invalid/file")
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn proxy_revalidates_with_the_etag() {
+    let (proxy, requests) = serve_proxy(Arc::default()).await;
+    let mut file = open(&proxy).await;
+    assert_eq!(file.last_cache_status(), Some(CacheStatus::Miss));
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, DATA);
+    assert_eq!(file.last_cache_status(), Some(CacheStatus::Hit));
+
+    let requests = requests.lock().unwrap();
+    let headers: Vec<_> = requests
+        .iter()
+        .map(|(uri, headers)| {
+            assert_eq!(uri.to_string(), "http://origin.invalid/file");
+            (
+                headers.get(header::CACHE_CONTROL).cloned(),
+                headers.get(header::IF_NONE_MATCH).cloned(),
+            )
+        })
+        .collect();
+    let no_cache = Some(HeaderValue::from_static("no-cache"));
+    assert_eq!(
+        headers,
+        [
+            // the `HEAD` when opening
+            (no_cache.clone(), None),
+            // answered `304 Not Modified`
+            (no_cache, Some(HeaderValue::from_static("\"v1\""))),
+            (None, None),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn proxy_serving_a_changed_file_fails() {
+    let changed = Arc::new(AtomicBool::new(false));
+    let (proxy, _) = serve_proxy(changed.clone()).await;
+    let mut file = open(&proxy).await;
+
+    changed.store(true, Ordering::SeqCst);
+    let mut buf = vec![];
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    let err = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<HttpFileError>())
+        .expect("should be an HttpFileError");
+    assert!(
+        matches!(err, HttpFileError::FileChanged { .. }),
+        "unexpected error: {err}"
+    );
+}
+
+#[tokio::test]
+async fn proxy_with_own_client_is_rejected() {
+    let err = HttpFile::builder()
+        .client(reqwest::Client::new())
+        .with_proxy(reqwest::Proxy::http("http://127.0.0.1:9").unwrap())
+        .build("http://origin.invalid/file")
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            HttpFileError::ClientOption {
+                option: "with_proxy"
+            }
+        ),
+        "unexpected error: {err}"
+    );
+}
+
+#[tokio::test]
+async fn no_cache_status_without_cache_headers() {
+    let mock = common::MockFile::new(DATA);
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(file.last_cache_status(), None);
+}
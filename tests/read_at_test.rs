@@ -0,0 +1,226 @@
+mod common;
+
+use axum::http::header;
+use common::{MockFile, random_bytes};
+use futures_util::TryStreamExt;
+use remote_file::{HttpFile, HttpFileError};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+#[tokio::test]
+async fn read_exact_at_matches_local_file() {
+    let data = random_bytes(256 * 1024);
+    let file_path = std::env::temp_dir().join("read_exact_at_test_file.bin");
+    std::fs::write(&file_path, &data).unwrap();
+    let mut local = tokio::fs::File::open(&file_path).await.unwrap();
+
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut remote = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    for _ in 0..20 {
+        let len = (rand::random::<u64>() % 4096) as usize;
+        let pos = rand::random::<u64>() % (data.len() - len) as u64;
+
+        let mut buf1 = vec![0u8; len];
+        let mut buf2 = vec![0u8; len];
+        remote.read_exact_at(pos, &mut buf1).await.unwrap();
+        local.seek(std::io::SeekFrom::Start(pos)).await.unwrap();
+        local.read_exact(&mut buf2).await.unwrap();
+        assert_eq!(buf1, buf2, "content should be the same at pos {}", pos);
+        assert_eq!(remote.stream_position().await.unwrap(), pos + len as u64);
+    }
+}
+
+#[tokio::test]
+async fn read_exact_at_fetches_a_bounded_range() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let mut buf = vec![0u8; 100];
+    file.read_exact_at(1000, &mut buf).await.unwrap();
+    assert_eq!(buf, data[1000..1100]);
+
+    let (_, headers) = mock.requests().pop().unwrap();
+    assert_eq!(headers[header::RANGE], "bytes=1000-1099");
+
+    // subsequent reads continue right after the range
+    let mut buf = vec![0u8; 100];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[1100..1200]);
+}
+
+#[tokio::test]
+async fn read_exact_at_past_eof_fails() {
+    let mock = MockFile::new(random_bytes(1024));
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let mut buf = vec![0u8; 100];
+    let err = file.read_exact_at(1000, &mut buf).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    assert_eq!(mock.gets(), 0, "no request should be made");
+}
+
+#[tokio::test]
+async fn read_at_leaves_the_cursor_alone() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let mut head = vec![0u8; 100];
+    file.read_exact(&mut head).await.unwrap();
+
+    let reads = (0..8u64).map(|i| {
+        let file = &file;
+        async move {
+            let mut buf = vec![0u8; 1000];
+            let n = file.read_at(i * 5000, &mut buf).await.unwrap();
+            (i, n, buf)
+        }
+    });
+    for (i, n, buf) in futures_util::future::join_all(reads).await {
+        let start = i as usize * 5000;
+        assert_eq!(n, 1000);
+        assert_eq!(buf, data[start..start + 1000]);
+    }
+
+    assert_eq!(file.stream_position().await.unwrap(), 100);
+    file.read_exact(&mut head).await.unwrap();
+    assert_eq!(head, data[100..200]);
+}
+
+#[tokio::test]
+async fn read_at_is_short_at_eof() {
+    let data = random_bytes(1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let mut buf = vec![0u8; 100];
+    assert_eq!(file.read_at(1000, &mut buf).await.unwrap(), 24);
+    assert_eq!(buf[..24], data[1000..]);
+    assert_eq!(file.read_at(2000, &mut buf).await.unwrap(), 0);
+    let (_, headers) = mock.requests().pop().unwrap();
+    assert_eq!(headers[header::RANGE], "bytes=1000-1023");
+}
+
+#[tokio::test]
+async fn read_range_fetches_the_closed_interval() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let bytes = file.read_range(100, 199).await.unwrap();
+    assert_eq!(bytes, data[100..200]);
+    let (_, headers) = mock.requests().pop().unwrap();
+    assert_eq!(headers[header::RANGE], "bytes=100-199");
+    assert_eq!(file.read_range(5, 5).await.unwrap(), data[5..6]);
+    assert_eq!(file.stream_position().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn read_range_past_eof_fails() {
+    let mock = MockFile::new(random_bytes(1024));
+    let url = mock.serve().await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    match file.read_range(1000, 1099).await.unwrap_err() {
+        HttpFileError::RangeMismatch {
+            requested,
+            content_range,
+            ..
+        } => {
+            assert_eq!(requested, (1000, 1099));
+            assert_eq!(content_range.as_deref(), Some("bytes 1000-1023/1024"));
+        }
+        e => panic!("unexpected error: {e}"),
+    }
+}
+
+#[tokio::test]
+async fn read_range_ending_before_start_fails() {
+    let mock = MockFile::new(random_bytes(1024));
+    let url = mock.serve().await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    assert!(matches!(
+        file.read_range(100, 99).await.unwrap_err(),
+        HttpFileError::InvalidRange {
+            start: 100,
+            end: 99
+        }
+    ));
+    assert_eq!(mock.gets(), 0);
+}
+
+#[tokio::test]
+async fn byte_stream_maps_ranges_to_headers() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let cases = [
+        (file.byte_stream(100..200), "bytes=100-199", &data[100..200]),
+        (
+            file.byte_stream(100..=200),
+            "bytes=100-200",
+            &data[100..=200],
+        ),
+        (file.byte_stream(60_000..), "bytes=60000-", &data[60_000..]),
+        (file.byte_stream(..10), "bytes=0-9", &data[..10]),
+    ];
+    for (stream, range, expected) in cases {
+        let chunks: Vec<_> = stream.try_collect().await.unwrap();
+        assert_eq!(chunks.concat(), expected);
+        let (_, headers) = mock.requests().pop().unwrap();
+        assert_eq!(headers[header::RANGE], range);
+    }
+
+    let gets = mock.gets();
+    let chunks: Vec<_> = file.byte_stream(10..10).try_collect().await.unwrap();
+    assert!(chunks.is_empty());
+    assert_eq!(mock.gets(), gets, "empty range needs no request");
+}
+
+#[tokio::test]
+async fn min_fetch_coalesces_small_reads() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    file.set_min_fetch(16 * 1024);
+
+    let gets = mock.gets();
+    let mut buf = vec![0u8; 100];
+    for i in 0..100 {
+        file.read_exact_at(1000 + i * 100, &mut buf).await.unwrap();
+        let start = 1000 + i as usize * 100;
+        assert_eq!(buf, data[start..start + 100]);
+    }
+    assert_eq!(mock.gets(), gets + 1);
+    let (_, headers) = mock.requests().pop().unwrap();
+    assert_eq!(headers[header::RANGE], "bytes=1000-17383");
+}
+
+#[tokio::test]
+async fn min_fetch_stops_at_eof() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    file.set_min_fetch(16 * 1024);
+
+    let mut buf = vec![0u8; 100];
+    file.read_exact_at(60_000, &mut buf).await.unwrap();
+    assert_eq!(buf, data[60_000..60_100]);
+    let (_, headers) = mock.requests().pop().unwrap();
+    assert_eq!(headers[header::RANGE], "bytes=60000-65535");
+
+    let mut rest = vec![];
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[60_100..]);
+}
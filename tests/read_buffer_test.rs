@@ -0,0 +1,116 @@
+mod common;
+
+use std::{io::SeekFrom, sync::Arc};
+
+use axum::http::{StatusCode, header};
+use common::{MockFile, random_bytes};
+use futures_util::{FutureExt, future::BoxFuture};
+use remote_file::{HttpFile, Transport};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+async fn open_buffered(data: &[u8], size: usize) -> (MockFile, HttpFile) {
+    let mock = MockFile::new(data.to_vec());
+    let url = mock.serve().await;
+    let file = HttpFile::builder()
+        .with_buffer_size(size)
+        .with_max_inline_skip(0)
+        .build(&url)
+        .await
+        .unwrap();
+    (mock, file)
+}
+
+#[tokio::test]
+async fn seeks_back_into_the_buffer_need_no_request() {
+    let data = random_bytes(256 * 1024);
+    let (mock, mut file) = open_buffered(&data, 64 * 1024).await;
+
+    let mut buf = vec![0u8; 1000];
+    file.seek(SeekFrom::Start(100_000)).await.unwrap();
+    file.read_exact(&mut buf).await.unwrap();
+    for pos in [100_500, 100_000, 100_900] {
+        file.seek(SeekFrom::Start(pos)).await.unwrap();
+        file.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, data[pos as usize..pos as usize + 1000]);
+    }
+    let mut rest = vec![];
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[101_900..]);
+    assert_eq!(mock.gets(), 1);
+}
+
+#[tokio::test]
+async fn read_exact_at_is_served_from_the_buffer() {
+    let data = random_bytes(256 * 1024);
+    let (mock, mut file) = open_buffered(&data, 64 * 1024).await;
+
+    let mut buf = vec![0u8; 1000];
+    file.read_exact(&mut buf).await.unwrap();
+    file.read_exact_at(10, &mut buf).await.unwrap();
+    assert_eq!(buf, data[10..1010]);
+    assert_eq!(mock.gets(), 1);
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[1010..2010]);
+}
+
+#[tokio::test]
+async fn seeks_outside_the_buffer_refetch() {
+    let data = random_bytes(256 * 1024);
+    let (mock, mut file) = open_buffered(&data, 4 * 1024).await;
+
+    let mut buf = vec![0u8; 1000];
+    file.seek(SeekFrom::Start(100_000)).await.unwrap();
+    file.read_exact(&mut buf).await.unwrap();
+    file.seek(SeekFrom::Start(10)).await.unwrap();
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[10..1010]);
+    assert_eq!(mock.gets(), 2);
+}
+
+/// Answers every request with the whole of `data`, in chunks of 1000 bytes.
+struct ChunkedTransport(bytes::Bytes);
+
+impl Transport for ChunkedTransport {
+    fn execute(
+        &self,
+        _client: &reqwest::Client,
+        _request: reqwest::Request,
+    ) -> BoxFuture<'static, reqwest::Result<reqwest::Response>> {
+        let chunks: Vec<_> = (0..self.0.len())
+            .step_by(1000)
+            .map(|at| Ok::<_, std::io::Error>(self.0.slice(at..(at + 1000).min(self.0.len()))))
+            .collect();
+        let resp = axum::http::Response::builder()
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(
+                header::CONTENT_RANGE,
+                format!("bytes 0-{}/{}", self.0.len() - 1, self.0.len()),
+            )
+            .body(reqwest::Body::wrap_stream(futures_util::stream::iter(
+                chunks,
+            )))
+            .unwrap();
+        futures_util::future::ready(Ok(resp.into())).boxed()
+    }
+}
+
+#[tokio::test]
+async fn seeks_back_span_several_buffered_chunks() {
+    let data = bytes::Bytes::from(random_bytes(64 * 1024));
+    let mut file = HttpFile::builder()
+        .with_buffer_size(16 * 1024)
+        .with_transport(Arc::new(ChunkedTransport(data.clone())))
+        .skip_head(data.len() as u64)
+        .build("http://127.0.0.1:9/file")
+        .await
+        .unwrap();
+
+    let mut buf = vec![0u8; 10_000];
+    file.read_exact(&mut buf).await.unwrap();
+    file.seek(SeekFrom::Start(500)).await.unwrap();
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[500..10_500]);
+    let mut rest = vec![];
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[10_500..]);
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use common::{MockFile, random_bytes};
+use remote_file::HttpFile;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+#[tokio::test]
+async fn read_rest_after_header() {
+    let data = random_bytes(128 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let mut header = [0u8; 100];
+    file.read_exact(&mut header).await.unwrap();
+    assert_eq!(header, data[..100]);
+
+    let rest = file.read_rest().await.unwrap();
+    assert_eq!(rest, data[100..]);
+    assert_eq!(file.stream_position().await.unwrap(), data.len() as u64);
+    assert!(file.read_rest().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn read_rest_after_seek() {
+    let data = random_bytes(16 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    file.seek(std::io::SeekFrom::End(-1000)).await.unwrap();
+    let rest = file.read_rest().await.unwrap();
+    assert_eq!(rest, data[data.len() - 1000..]);
+}
+
+#[tokio::test]
+async fn read_rest_with_bogus_length() {
+    let data = random_bytes(1000);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    // far more than could ever be allocated up front
+    let mut file =
+        HttpFile::from_known(reqwest::Client::new(), url.parse().unwrap(), 1 << 62, None);
+
+    let rest = file.read_rest().await.unwrap();
+    assert_eq!(rest, data);
+}
@@ -0,0 +1,220 @@
+mod common;
+
+use std::{
+    io::SeekFrom,
+    net::SocketAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+};
+
+use axum::{
+    Router,
+    extract::Path,
+    http::{HeaderMap, Method, StatusCode, header},
+    response::IntoResponse,
+    routing::any,
+};
+use bytes::Bytes;
+use remote_file::{HttpFile, HttpFileError};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tower_http::services::ServeDir;
+
+/// Serve `data` at `/files/data.bin` from a directory, and at `/old.bin`,
+/// which redirects there instead once `moved` is set. `/file` answers `HEAD`s
+/// itself, but redirects `GET`s to `/old.bin`. Returns the address and the
+/// number of requests `/file` got.
+async fn serve_redirecting(data: Bytes, moved: Arc<AtomicBool>) -> (SocketAddr, Arc<AtomicUsize>) {
+    let dir = std::env::temp_dir().join(format!("remote-file-redirect-{}", rand::random::<u64>()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("data.bin"), &data).unwrap();
+    let len = data.len();
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+    let app = Router::new()
+        .route(
+            "/file",
+            any(move |method: Method| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if method == Method::HEAD {
+                        return [(header::CONTENT_LENGTH, len.to_string())].into_response();
+                    }
+                    (StatusCode::FOUND, [(header::LOCATION, "/old.bin")]).into_response()
+                }
+            }),
+        )
+        .route(
+            "/old.bin",
+            any(move |method: Method, headers: HeaderMap| async move {
+                if moved.load(Ordering::SeqCst) {
+                    return (StatusCode::FOUND, [(header::LOCATION, "/files/data.bin")])
+                        .into_response();
+                }
+                common::range_response(&method, &headers, data, None)
+            }),
+        )
+        .nest_service("/files", ServeDir::new(dir));
+    (common::serve(app).await, hits)
+}
+
+#[tokio::test]
+async fn range_requests_go_to_the_redirect_target() {
+    let data = Bytes::from(common::random_bytes(1 << 20));
+    let moved = Arc::new(AtomicBool::new(true));
+    let (addr, hits) = serve_redirecting(data.clone(), moved).await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &format!("http://{}/file", addr))
+        .await
+        .unwrap();
+    assert_eq!(file.url().path(), "/file");
+
+    let mut buf = [0u8; 16];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[..16]);
+    assert_eq!(file.url().path(), "/files/data.bin");
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+    file.seek(SeekFrom::Start(900_000)).await.unwrap();
+    let mut rest = vec![];
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[900_000..]);
+    assert_eq!(
+        hits.load(Ordering::SeqCst),
+        2,
+        "the redirect is not taken again"
+    );
+}
+
+#[tokio::test]
+async fn latest_redirect_target_is_kept() {
+    let data = Bytes::from(common::random_bytes(1 << 20));
+    let moved = Arc::new(AtomicBool::new(false));
+    let (addr, _) = serve_redirecting(data.clone(), moved.clone()).await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &format!("http://{}/file", addr))
+        .await
+        .unwrap();
+    let mut buf = [0u8; 16];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(file.url().path(), "/old.bin");
+
+    // the file moves again
+    moved.store(true, Ordering::SeqCst);
+    file.seek(SeekFrom::Start(900_000)).await.unwrap();
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[900_000..900_016]);
+    assert_eq!(file.url().path(), "/files/data.bin");
+}
+
+/// Serve `data` at `/r/0`, reached from `/r/{hops}` through a redirect per
+/// hop, and a redirect loop between `/a` and `/b`.
+async fn serve_hops(data: Bytes) -> String {
+    let app = Router::new()
+        .route(
+            "/r/{hop}",
+            any(
+                move |Path(hop): Path<u32>, method: Method, headers: HeaderMap| async move {
+                    if hop == 0 {
+                        return common::range_response(&method, &headers, data, None);
+                    }
+                    let next = format!("/r/{}", hop - 1);
+                    (StatusCode::FOUND, [(header::LOCATION, next)]).into_response()
+                },
+            ),
+        )
+        .route(
+            "/a",
+            any(|| async { (StatusCode::FOUND, [(header::LOCATION, "/b")]) }),
+        )
+        .route(
+            "/b",
+            any(|| async { (StatusCode::FOUND, [(header::LOCATION, "/a")]) }),
+        );
+    format!("http://{}", common::serve(app).await)
+}
+
+#[tokio::test]
+async fn redirects_are_limited() {
+    let data = Bytes::from_static(b"hello, world");
+    let base = serve_hops(data.clone()).await;
+
+    let mut file = HttpFile::builder()
+        .with_max_redirects(3)
+        .build(&format!("{}/r/3", base))
+        .await
+        .unwrap();
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+
+    let err = HttpFile::builder()
+        .with_max_redirects(3)
+        .build(&format!("{}/r/4", base))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, HttpFileError::TooManyRedirects { limit: 3 }));
+
+    // range requests are held to the limit too
+    let mut file = HttpFile::builder()
+        .with_max_redirects(3)
+        .skip_head(data.len() as u64)
+        .build(&format!("{}/r/4", base))
+        .await
+        .unwrap();
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    let err = err.get_ref().unwrap().downcast_ref::<HttpFileError>();
+    assert!(matches!(
+        err,
+        Some(HttpFileError::TooManyRedirects { limit: 3 })
+    ));
+}
+
+#[tokio::test]
+async fn redirect_limit_with_own_client_is_rejected() {
+    let base = serve_hops(Bytes::new()).await;
+    let err = HttpFile::builder()
+        .client(reqwest::Client::new())
+        .with_max_redirects(3)
+        .build(&format!("{}/r/4", base))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            HttpFileError::ClientOption {
+                option: "with_max_redirects"
+            }
+        ),
+        "unexpected error: {err}"
+    );
+}
+
+#[tokio::test]
+async fn redirect_loops_fail_fast() {
+    let base = serve_hops(Bytes::new()).await;
+    let mut file = HttpFile::builder()
+        .with_max_redirects(100)
+        .skip_head(10)
+        .build(&format!("{}/a", base))
+        .await
+        .unwrap();
+    let mut buf = vec![];
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    let err = err
+        .get_ref()
+        .unwrap()
+        .downcast_ref::<HttpFileError>()
+        .unwrap();
+    let HttpFileError::RedirectLoop { cycle } = err else {
+        panic!("not a redirect loop: {:?}", err);
+    };
+    assert_eq!(
+        *cycle,
+        [
+            format!("{}/a", base),
+            format!("{}/b", base),
+            format!("{}/a", base)
+        ]
+    );
+    assert!(err.to_string().contains(" -> "));
+}
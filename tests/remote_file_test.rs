@@ -0,0 +1,50 @@
+mod common;
+
+use std::io::SeekFrom;
+
+use common::{MockFile, random_bytes};
+use remote_file::{AnyFile, HttpFile, LocalFile, RemoteFile};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// The last `n` bytes of any file of known length.
+async fn tail<F: RemoteFile + Unpin>(file: &mut F, n: u64) -> Vec<u8> {
+    let len = file.content_length().expect("length is known");
+    file.seek(SeekFrom::Start(len.saturating_sub(n)))
+        .await
+        .unwrap();
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf).await.unwrap();
+    buf
+}
+
+#[tokio::test]
+async fn backend_agnostic_code_reads_every_source() {
+    let data = random_bytes(10_000);
+    let mock = MockFile::new(data.clone()).with_etag("\"v1\"");
+    let url = mock.serve().await;
+    let path = std::env::temp_dir().join(format!("remote-file-trait-{}.bin", std::process::id()));
+    std::fs::write(&path, &data).unwrap();
+
+    let mut http = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert_eq!(RemoteFile::etag(&http), Some("\"v1\""));
+    assert_eq!(tail(&mut http, 100).await, data[9900..]);
+
+    let mut slice = http.slice(1000..2000);
+    assert_eq!(slice.etag(), Some("\"v1\""));
+    assert_eq!(tail(&mut slice, 100).await, data[1900..2000]);
+
+    let mut local = LocalFile::open(&path).await.unwrap();
+    assert_eq!(RemoteFile::etag(&local), None);
+    assert_eq!(tail(&mut local, 100).await, data[9900..]);
+
+    let files: Vec<Box<dyn RemoteFile + Unpin>> = vec![
+        Box::new(http.try_clone()),
+        Box::new(AnyFile::open(reqwest::Client::new(), &url).await.unwrap()),
+    ];
+    for mut file in files {
+        assert_eq!(file.content_length(), Some(10_000));
+        assert_eq!(tail(&mut file, 10).await, data[9990..]);
+    }
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,156 @@
+mod common;
+
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    Router,
+    body::Body,
+    http::{HeaderMap, Method, StatusCode, header},
+    response::IntoResponse,
+    routing::any,
+};
+use bytes::Bytes;
+use common::{MockFile, parse_range, random_bytes};
+use remote_file::HttpFile;
+
+const ETAG: &str = "\"v1\"";
+
+fn paths(name: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir();
+    let dest = dir.join(format!("{}.bin", name));
+    let checkpoint = dir.join(format!("{}.checkpoint", name));
+    let _ = std::fs::remove_file(&dest);
+    let _ = std::fs::remove_file(&checkpoint);
+    (dest, checkpoint)
+}
+
+/// Serve `data`, cutting the connection of the first `GET` after `cut_at`
+/// bytes. Returns the URL and the ranges requested.
+async fn serve_interrupted(data: Bytes, cut_at: usize) -> (String, Arc<Mutex<Vec<String>>>) {
+    let ranges = Arc::new(Mutex::new(Vec::<String>::new()));
+    let log = ranges.clone();
+    let app = Router::new().route(
+        "/file",
+        any(move |method: Method, headers: HeaderMap| {
+            let data = data.clone();
+            let log = log.clone();
+            async move {
+                let etag = [(header::ETAG, ETAG)];
+                if method == Method::HEAD {
+                    return (etag, [(header::CONTENT_LENGTH, data.len().to_string())])
+                        .into_response();
+                }
+                let range = headers[header::RANGE].to_str().unwrap().to_string();
+                let (start, end) = parse_range(&range, data.len() as u64).unwrap().unwrap();
+                let first = {
+                    let mut log = log.lock().unwrap();
+                    log.push(range);
+                    log.len() == 1
+                };
+                let body = data.slice(start as usize..=end as usize);
+                let body = if first {
+                    let chunks = body[..cut_at]
+                        .chunks(64 * 1024)
+                        .map(|c| Ok(Bytes::copy_from_slice(c)))
+                        .chain([Err(std::io::Error::other("process killed"))])
+                        .collect::<Vec<_>>();
+                    Body::from_stream(futures_util::stream::iter(chunks))
+                } else {
+                    Body::from(body)
+                };
+                (StatusCode::PARTIAL_CONTENT, etag, body).into_response()
+            }
+        }),
+    );
+    let addr = common::serve(app).await;
+    (format!("http://{}/file", addr), ranges)
+}
+
+#[tokio::test]
+async fn interrupted_download_resumes_from_checkpoint() {
+    let data = Bytes::from(random_bytes(3 * 1024 * 1024 + 1000));
+    let (url, ranges) = serve_interrupted(data.clone(), 2 * 1024 * 1024 + 500 * 1024).await;
+    let (dest, checkpoint) = paths("resumable_interrupted");
+
+    HttpFile::download_to_resumable(reqwest::Client::new(), &url, &dest, &checkpoint)
+        .await
+        .unwrap_err();
+    let saved = std::fs::read_to_string(&checkpoint).unwrap();
+    let (written, etag) = saved.split_once('\n').unwrap();
+    let written: u64 = written.parse().unwrap();
+    assert!(written >= 2 * 1024 * 1024);
+    assert_eq!(etag.trim(), ETAG);
+
+    let len = HttpFile::download_to_resumable(reqwest::Client::new(), &url, &dest, &checkpoint)
+        .await
+        .unwrap();
+    assert_eq!(len, data.len() as u64);
+    assert_eq!(std::fs::read(&dest).unwrap(), data);
+    assert!(!checkpoint.exists());
+    assert_eq!(
+        *ranges.lock().unwrap(),
+        ["bytes=0-".to_string(), format!("bytes={}-", written)]
+    );
+    std::fs::remove_file(&dest).unwrap();
+}
+
+#[tokio::test]
+async fn changed_etag_restarts() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone()).with_etag("\"new\"");
+    let url = mock.serve().await;
+    let (dest, checkpoint) = paths("resumable_changed");
+    std::fs::write(&dest, random_bytes(32 * 1024)).unwrap();
+    std::fs::write(&checkpoint, "32768\n\"old\"\n").unwrap();
+
+    HttpFile::download_to_resumable(reqwest::Client::new(), &url, &dest, &checkpoint)
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(&dest).unwrap(), data);
+    assert!(!checkpoint.exists());
+    let (_, headers) = mock.requests().pop().unwrap();
+    assert_eq!(headers[header::RANGE], "bytes=0-");
+    std::fs::remove_file(&dest).unwrap();
+}
+
+#[tokio::test]
+async fn download_to_file_resumes_a_part_file() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone()).with_etag(ETAG);
+    let url = mock.serve().await;
+    let (dest, _) = paths("download_to_file_resume");
+    let part = dest.with_extension("bin.part");
+    let etag = dest.with_extension("bin.part.etag");
+    std::fs::write(&part, &data[..20_000]).unwrap();
+    std::fs::write(&etag, ETAG).unwrap();
+
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    file.download_to_file(&dest).await.unwrap();
+    assert_eq!(std::fs::read(&dest).unwrap(), data);
+    assert!(!part.exists() && !etag.exists());
+    let (_, headers) = mock.requests().pop().unwrap();
+    assert_eq!(headers[header::RANGE], "bytes=20000-");
+    assert_eq!(headers[header::IF_RANGE], ETAG);
+    std::fs::remove_file(&dest).unwrap();
+}
+
+#[tokio::test]
+async fn download_to_file_restarts_a_part_of_another_version() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone()).with_etag(ETAG);
+    let url = mock.serve().await;
+    let (dest, _) = paths("download_to_file_restart");
+    let part = dest.with_extension("bin.part");
+    std::fs::write(&part, random_bytes(20_000)).unwrap();
+    std::fs::write(dest.with_extension("bin.part.etag"), "\"old\"").unwrap();
+
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    file.download_to_file(&dest).await.unwrap();
+    assert_eq!(std::fs::read(&dest).unwrap(), data);
+    let (_, headers) = mock.requests().pop().unwrap();
+    assert_eq!(headers[header::RANGE], "bytes=0-");
+    std::fs::remove_file(&dest).unwrap();
+}
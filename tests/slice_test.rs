@@ -0,0 +1,123 @@
+mod common;
+
+use std::io::SeekFrom;
+
+use common::{MockFile, random_bytes};
+use remote_file::{FileMeta, HttpFile};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+#[tokio::test]
+async fn split_covers_the_file_in_parallel() {
+    let data = random_bytes(100_003);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let slices = file.split(4).unwrap();
+    let ranges: Vec<_> = slices.iter().map(|s| s.range()).collect();
+    assert_eq!(
+        ranges,
+        [0..25_001, 25_001..50_002, 50_002..75_003, 75_003..100_003]
+    );
+
+    let readers = slices.into_iter().map(|mut slice| {
+        tokio::spawn(async move {
+            let mut buf = Vec::new();
+            slice.read_to_end(&mut buf).await.unwrap();
+            assert_eq!(buf.len() as u64, slice.len());
+            buf
+        })
+    });
+    let mut joined = Vec::new();
+    for reader in readers.collect::<Vec<_>>() {
+        joined.extend(reader.await.unwrap());
+    }
+    assert_eq!(joined, data);
+    assert_eq!(mock.gets(), 4);
+}
+
+#[tokio::test]
+async fn slice_positions_are_relative() {
+    let data = random_bytes(10_000);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let mut slice = file.split(2).unwrap().pop().unwrap();
+    assert_eq!(slice.range(), 5000..10_000);
+
+    assert_eq!(slice.seek(SeekFrom::Start(100)).await.unwrap(), 100);
+    let mut buf = [0u8; 10];
+    slice.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[5100..5110]);
+
+    assert_eq!(slice.seek(SeekFrom::End(-10)).await.unwrap(), 4990);
+    let mut rest = Vec::new();
+    slice.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[9990..]);
+
+    assert!(slice.seek(SeekFrom::Current(-6000)).await.is_err());
+}
+
+#[tokio::test]
+async fn slice_stops_at_its_end() {
+    let data = random_bytes(10_000);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let mut first = file.split(3).unwrap().remove(0);
+
+    let mut buf = Vec::new();
+    first.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data[..3334]);
+    assert!(
+        mock.requests()
+            .iter()
+            .any(|(_, headers)| { headers.get("range").is_some_and(|r| r == "bytes=0-3333") })
+    );
+}
+
+#[tokio::test]
+async fn split_needs_a_known_length() {
+    let url = reqwest::Url::parse("http://localhost/file").unwrap();
+    let file = HttpFile::from_parts(reqwest::Client::new(), url, FileMeta::default(), None);
+    let err = file.split(2).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[tokio::test]
+async fn slice_reads_an_embedded_resource() {
+    let data = random_bytes(10_000);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let mut entry = file.slice(2000..2500);
+    assert_eq!(entry.len(), 500);
+
+    // a read reaching past the window is cut at its end
+    let mut buf = vec![0u8; 1000];
+    assert_eq!(entry.seek(SeekFrom::Start(400)).await.unwrap(), 400);
+    let n = entry.read(&mut buf).await.unwrap();
+    assert_eq!(buf[..n], data[2400..2400 + n]);
+    let mut rest = Vec::new();
+    entry.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(n + rest.len(), 100);
+
+    assert_eq!(entry.seek(SeekFrom::End(0)).await.unwrap(), 500);
+    let err = entry.seek(SeekFrom::Start(501)).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(entry.seek(SeekFrom::Current(-501)).await.is_err());
+    assert_eq!(entry.seek(SeekFrom::Current(0)).await.unwrap(), 500);
+}
+
+#[tokio::test]
+async fn slice_past_the_end_of_the_file_stops_at_eof() {
+    let data = random_bytes(1000);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let mut tail = file.slice(900..2000);
+
+    let mut buf = Vec::new();
+    tail.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data[900..]);
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use axum::http::header;
+use common::{MockFile, builder_at_rate, random_bytes};
+use tokio::io::AsyncReadExt;
+
+fn requested_ranges(mock: &MockFile) -> Vec<(u64, Option<u64>)> {
+    mock.requests()
+        .iter()
+        .filter_map(|(_, headers)| headers.get(header::RANGE))
+        .map(|range| {
+            let range = range.to_str().unwrap().trim_start_matches("bytes=");
+            let (start, end) = range.split_once('-').unwrap();
+            (start.parse().unwrap(), end.parse().ok())
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn slow_stream_downgrades_to_bounded_ranges() {
+    let data = random_bytes(256 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = builder_at_rate(1024)
+        .with_slow_stream_policy(10 * 1024, 16 * 1024)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+
+    let ranges = requested_ranges(&mock);
+    assert_eq!(ranges[0], (0, None));
+    assert!(ranges.len() > 1, "the open stream is given up");
+    for pair in ranges[1..].windows(2) {
+        let (start, end) = pair[0];
+        assert_eq!(end, Some(start + 16 * 1024 - 1));
+        assert_eq!(pair[1].0, start + 16 * 1024);
+    }
+}
+
+#[tokio::test]
+async fn fast_enough_stream_is_kept() {
+    let data = random_bytes(256 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = builder_at_rate(1024)
+        .with_slow_stream_policy(512, 1024)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+    assert_eq!(requested_ranges(&mock), [(0, None)]);
+}
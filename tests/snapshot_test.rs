@@ -0,0 +1,193 @@
+mod common;
+
+use axum::http::header;
+use common::{MockFile, random_bytes};
+use remote_file::{HttpFile, HttpFileError};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+#[tokio::test]
+async fn snapshot_rejects_reads_after_change() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone()).with_etag("\"v1\"");
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    file.snapshot();
+
+    let mut buf = vec![0u8; 1024];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[..1024]);
+    let (_, headers) = mock.requests().pop().unwrap();
+    assert_eq!(headers[header::IF_RANGE], "\"v1\"");
+
+    mock.set(random_bytes(64 * 1024), Some("\"v2\""));
+    let err = file
+        .seek(std::io::SeekFrom::Start(40_000))
+        .await
+        .unwrap_err();
+    let err = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<HttpFileError>())
+        .expect("should be an HttpFileError");
+    match err {
+        HttpFileError::FileChanged { expected, actual } => {
+            assert_eq!(expected.as_deref(), Some("\"v1\""));
+            assert_eq!(actual.as_deref(), Some("\"v2\""));
+        }
+        e => panic!("unexpected error: {e}"),
+    }
+    // the failed seek leaves the cursor where it was
+    assert_eq!(file.stream_position().await.unwrap(), 1024);
+
+    let err = file.read_exact_at(40_000, &mut buf).await.unwrap_err();
+    let err = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<HttpFileError>());
+    assert!(matches!(err, Some(HttpFileError::FileChanged { .. })));
+}
+
+#[tokio::test]
+async fn snapshot_reads_unchanged_file() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone()).with_etag("\"v1\"");
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    file.snapshot();
+
+    for pos in [1000, 30_000, 10] {
+        let mut buf = vec![0u8; 1024];
+        file.seek(std::io::SeekFrom::Start(pos)).await.unwrap();
+        file.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, data[pos as usize..pos as usize + 1024]);
+    }
+}
+
+#[tokio::test]
+async fn if_range_pins_the_version_opened() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone()).with_etag("\"v1\"");
+    let url = mock.serve().await;
+    let mut file = HttpFile::builder()
+        .with_if_range(true)
+        .build(&url)
+        .await
+        .unwrap();
+
+    mock.set(random_bytes(64 * 1024), Some("\"v2\""));
+    let mut buf = vec![0u8; 1024];
+    let err = file.read_exact(&mut buf).await.unwrap_err();
+    let err = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<HttpFileError>());
+    assert!(matches!(err, Some(HttpFileError::FileChanged { .. })));
+    let (_, headers) = mock.requests().pop().unwrap();
+    assert_eq!(headers[header::IF_RANGE], "\"v1\"");
+}
+
+#[tokio::test]
+async fn if_range_is_off_by_default() {
+    let mock = MockFile::new(random_bytes(1024)).with_etag("\"v1\"");
+    let url = mock.serve().await;
+    let mut file = HttpFile::builder().build(&url).await.unwrap();
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    let (_, headers) = mock.requests().pop().unwrap();
+    assert!(!headers.contains_key(header::IF_RANGE));
+}
+
+#[tokio::test]
+async fn changed_etag_fails_unpinned_reads() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone()).with_etag("\"v1\"");
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let mut buf = vec![0u8; 1024];
+    file.read_exact(&mut buf).await.unwrap();
+    mock.set(random_bytes(64 * 1024), Some("\"v2\""));
+    file.seek(std::io::SeekFrom::Start(40_000))
+        .await
+        .expect_err("seek lands in the new version");
+    let err = file.read_exact_at(30_000, &mut buf).await.unwrap_err();
+    let err = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<HttpFileError>());
+    match err {
+        Some(HttpFileError::FileChanged { expected, actual }) => {
+            assert_eq!(expected.as_deref(), Some("\"v1\""));
+            assert_eq!(actual.as_deref(), Some("\"v2\""));
+        }
+        e => panic!("unexpected error: {e:?}"),
+    }
+}
+
+#[tokio::test]
+async fn etag_check_can_be_disabled() {
+    let mock = MockFile::new(random_bytes(1024)).with_etag("\"v1\"");
+    let url = mock.serve().await;
+    let mut file = HttpFile::builder()
+        .with_etag_check(false)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let data = random_bytes(1024);
+    mock.set(data.clone(), Some("\"v2\""));
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+}
+
+#[tokio::test]
+async fn refresh_picks_up_the_new_version() {
+    let mock = MockFile::new(random_bytes(64 * 1024)).with_etag("\"v1\"");
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let mut buf = vec![0u8; 1000];
+    file.read_exact(&mut buf).await.unwrap();
+
+    let data = random_bytes(32 * 1024);
+    mock.set(data.clone(), Some("\"v2\""));
+    file.refresh().await.unwrap();
+    assert_eq!(file.content_length(), Some(32 * 1024));
+    assert_eq!(file.etag(), Some("\"v2\""));
+    assert_eq!(file.position(), 1000);
+
+    // the old response is dropped, reads go on from the new version
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[1000..2000]);
+}
+
+#[tokio::test]
+async fn refresh_past_the_new_end_reads_nothing() {
+    let mock = MockFile::new(random_bytes(64 * 1024)).with_etag("\"v1\"");
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    file.seek(std::io::SeekFrom::Start(50_000)).await.unwrap();
+    let mut buf = vec![0u8; 1000];
+    file.read_exact(&mut buf).await.unwrap();
+
+    mock.set(random_bytes(1024), Some("\"v2\""));
+    file.refresh().await.unwrap();
+    assert_eq!(file.position(), 51_000);
+    assert_eq!(file.remaining(), Some(0));
+    assert_eq!(file.read(&mut buf).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn snapshot_rejects_suffix_of_changed_file() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone()).with_etag("\"v1\"");
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    file.snapshot();
+    assert_eq!(file.read_suffix(22).await.unwrap(), data[data.len() - 22..]);
+
+    // the range is ignored for the new version, sent whole
+    mock.set(random_bytes(64 * 1024), Some("\"v2\""));
+    let err = file.read_suffix(22).await.unwrap_err();
+    assert!(
+        matches!(err, HttpFileError::FileChanged { .. }),
+        "unexpected error: {err}"
+    );
+}
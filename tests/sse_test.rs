@@ -0,0 +1,58 @@
+mod common;
+
+use axum::http::Method;
+use common::{MockFile, random_bytes};
+use remote_file::{HttpFile, HttpFileError};
+use tokio::io::AsyncReadExt;
+
+#[tokio::test]
+async fn sse_customer_key_headers_on_every_request() {
+    let data = random_bytes(4096);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+
+    let key: Vec<u8> = (0u8..32).collect();
+    let mut file = HttpFile::builder()
+        .with_sse_customer_key("AES256", &key)
+        .unwrap()
+        .build(&url)
+        .await
+        .unwrap();
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+
+    let requests = mock.requests();
+    assert_eq!(requests[0].0, Method::HEAD);
+    assert_eq!(requests[1].0, Method::GET);
+    for (_, headers) in requests {
+        assert_eq!(
+            headers["x-amz-server-side-encryption-customer-algorithm"],
+            "AES256"
+        );
+        assert_eq!(
+            headers["x-amz-server-side-encryption-customer-key"],
+            "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8="
+        );
+        assert_eq!(
+            headers["x-amz-server-side-encryption-customer-key-md5"],
+            "tP/LI3N87DFaSk0aoqYgzg=="
+        );
+    }
+}
+
+#[test]
+fn sse_customer_key_is_validated() {
+    let err = HttpFile::builder()
+        .with_sse_customer_key("AES256", &[0u8; 16])
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        HttpFileError::InvalidSseCustomerKey { key_len: 16, .. }
+    ));
+
+    let err = HttpFile::builder()
+        .with_sse_customer_key("aws:kms", &[0u8; 32])
+        .unwrap_err();
+    assert!(matches!(err, HttpFileError::InvalidSseCustomerKey { .. }));
+}
@@ -0,0 +1,241 @@
+mod common;
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Router,
+    body::Body,
+    http::{HeaderMap, Method, StatusCode, header},
+    response::Response,
+    routing::any,
+};
+use bytes::Bytes;
+use common::{parse_range, random_bytes};
+use futures_util::StreamExt;
+use remote_file::{HttpFile, HttpFileError};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Serve `data`, with the first `stalls` `GET`s stalling without ending: the
+/// first one after sending 1000 bytes, the others right away. Returns the
+/// ranges requested.
+async fn serve_stalling(data: Bytes, stalls: usize) -> (String, Arc<Mutex<Vec<String>>>) {
+    let ranges = Arc::new(Mutex::new(vec![]));
+    let requested = ranges.clone();
+    let app = Router::new().route(
+        "/file",
+        any(move |method: Method, headers: HeaderMap| {
+            let (data, requested) = (data.clone(), requested.clone());
+            async move {
+                let len = data.len();
+                if method == Method::HEAD {
+                    return Response::builder()
+                        .header(header::CONTENT_LENGTH, len)
+                        .body(Body::empty())
+                        .unwrap();
+                }
+                let range = headers[header::RANGE].to_str().unwrap().to_string();
+                let (start, end) = parse_range(&range, len as u64).unwrap().unwrap();
+                let body = data.slice(start as usize..=end as usize);
+                let count = {
+                    let mut requested = requested.lock().unwrap();
+                    requested.push(range);
+                    requested.len()
+                };
+                let body = if count <= stalls {
+                    let head = body.slice(..if count == 1 { 1000 } else { 0 });
+                    Body::from_stream(
+                        futures_util::stream::iter([Ok::<_, std::io::Error>(head)])
+                            .chain(futures_util::stream::pending()),
+                    )
+                } else {
+                    Body::from(body)
+                };
+                Response::builder()
+                    .status(StatusCode::PARTIAL_CONTENT)
+                    .header(
+                        header::CONTENT_RANGE,
+                        format!("bytes {}-{}/{}", start, end, len),
+                    )
+                    .body(body)
+                    .unwrap()
+            }
+        }),
+    );
+    let addr = common::serve(app).await;
+    (format!("http://{}/file", addr), ranges)
+}
+
+#[tokio::test]
+async fn stalled_stream_reconnects() {
+    let data = Bytes::from(random_bytes(64 * 1024));
+    let (url, ranges) = serve_stalling(data.clone(), 1).await;
+    let mut file = HttpFile::builder()
+        .with_stall_timeout(Duration::from_millis(100))
+        .with_backoff(Duration::ZERO, 2.0, Duration::ZERO, false)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+    assert_eq!(*ranges.lock().unwrap(), ["bytes=0-", "bytes=1000-"]);
+}
+
+#[tokio::test]
+async fn stall_fails_once_out_of_retries() {
+    let data = Bytes::from(random_bytes(64 * 1024));
+    let (url, ranges) = serve_stalling(data.clone(), usize::MAX).await;
+    let mut file = HttpFile::builder()
+        .retries(1)
+        .with_stall_timeout(Duration::from_millis(100))
+        .with_backoff(Duration::ZERO, 2.0, Duration::ZERO, false)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![];
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    assert!(matches!(
+        err.get_ref().unwrap().downcast_ref::<HttpFileError>(),
+        Some(HttpFileError::Stalled { .. })
+    ));
+    assert_eq!(buf, data[..1000], "data before the stall is delivered");
+    assert_eq!(*ranges.lock().unwrap(), ["bytes=0-", "bytes=1000-"]);
+
+    let err = file.read_exact_at(0, &mut [0u8; 2000]).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+}
+
+/// Serve `data`, leaving the first `hangs` `GET`s unanswered. Returns the
+/// number of `GET`s received.
+async fn serve_hanging(data: Bytes, hangs: usize) -> (String, Arc<Mutex<usize>>) {
+    let gets = Arc::new(Mutex::new(0));
+    let counter = gets.clone();
+    let app = Router::new().route(
+        "/file",
+        any(move |method: Method, headers: HeaderMap| {
+            let (data, counter) = (data.clone(), counter.clone());
+            async move {
+                let len = data.len();
+                if method == Method::HEAD {
+                    return Response::builder()
+                        .header(header::CONTENT_LENGTH, len)
+                        .body(Body::empty())
+                        .unwrap();
+                }
+                let count = {
+                    let mut counter = counter.lock().unwrap();
+                    *counter += 1;
+                    *counter
+                };
+                if count <= hangs {
+                    std::future::pending::<()>().await;
+                }
+                let range = headers[header::RANGE].to_str().unwrap();
+                let (start, end) = parse_range(range, len as u64).unwrap().unwrap();
+                Response::builder()
+                    .status(StatusCode::PARTIAL_CONTENT)
+                    .header(
+                        header::CONTENT_RANGE,
+                        format!("bytes {}-{}/{}", start, end, len),
+                    )
+                    .body(Body::from(data.slice(start as usize..=end as usize)))
+                    .unwrap()
+            }
+        }),
+    );
+    let addr = common::serve(app).await;
+    (format!("http://{}/file", addr), gets)
+}
+
+#[tokio::test]
+async fn unanswered_request_is_sent_again() {
+    let data = Bytes::from(random_bytes(64 * 1024));
+    let (url, gets) = serve_hanging(data.clone(), 2).await;
+    let mut file = HttpFile::builder()
+        .with_backoff(Duration::ZERO, 2.0, Duration::ZERO, false)
+        .build(&url)
+        .await
+        .unwrap();
+    file.set_request_timeout(Duration::from_millis(100));
+
+    // answered on the third attempt, and read through from there
+    file.seek(std::io::SeekFrom::Start(1000)).await.unwrap();
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data[1000..]);
+    assert_eq!(*gets.lock().unwrap(), 3);
+}
+
+#[tokio::test]
+async fn request_timeout_fails_once_out_of_retries() {
+    let data = Bytes::from(random_bytes(1024));
+    let (url, gets) = serve_hanging(data, usize::MAX).await;
+    let mut file = HttpFile::builder()
+        .retries(1)
+        .with_request_timeout(Duration::from_millis(100))
+        .with_backoff(Duration::ZERO, 2.0, Duration::ZERO, false)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![];
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    assert!(matches!(
+        err.get_ref().unwrap().downcast_ref::<HttpFileError>(),
+        Some(HttpFileError::ResponseTimeout { .. })
+    ));
+    assert_eq!(*gets.lock().unwrap(), 2);
+
+    let err = file.read_exact_at(0, &mut [0u8; 10]).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+}
+
+#[tokio::test]
+async fn deadline_interrupts_requests_and_backoff() {
+    let data = Bytes::from(random_bytes(1024));
+    let (url, gets) = serve_hanging(data.clone(), 1).await;
+    let started = Instant::now();
+    let mut file = HttpFile::builder()
+        .with_deadline(started + Duration::from_millis(200))
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![];
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    assert!(matches!(
+        err.get_ref().unwrap().downcast_ref::<HttpFileError>(),
+        Some(HttpFileError::Cancelled)
+    ));
+    assert!(started.elapsed() < Duration::from_secs(2));
+    let err = file.read_exact_at(0, &mut [0u8; 10]).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+    // a long backoff is cut short too
+    let started = Instant::now();
+    let (url, _) = serve_hanging(data.clone(), usize::MAX).await;
+    let mut hanging = HttpFile::builder()
+        .with_request_timeout(Duration::from_millis(50))
+        .with_backoff(Duration::from_secs(30), 2.0, Duration::from_secs(30), false)
+        .with_deadline(started + Duration::from_millis(300))
+        .build(&url)
+        .await
+        .unwrap();
+    hanging.read_to_end(&mut buf).await.unwrap_err();
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    // without a deadline, the file is usable again
+    file.set_deadline(None);
+    file.rewind().await.unwrap();
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+    assert!(*gets.lock().unwrap() >= 2);
+}
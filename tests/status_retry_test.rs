@@ -0,0 +1,321 @@
+mod common;
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Router,
+    http::{Method, StatusCode, header},
+    response::IntoResponse,
+    routing::any,
+};
+use remote_file::{HttpFile, HttpFileError, RetryAction, RetryPolicy};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+const DATA: &[u8] = b"hello, world";
+
+/// Answer the first `failures` `GET`s with `status` and the given
+/// `Retry-After`, then serve the file. Returns the URL and the `GET` count.
+async fn serve_failing(
+    status: StatusCode,
+    retry_after: Option<&str>,
+    failures: usize,
+) -> (String, Arc<AtomicUsize>) {
+    let retry_after = retry_after.map(str::to_string);
+    let gets = Arc::new(AtomicUsize::new(0));
+    let counter = gets.clone();
+    let app = Router::new().route(
+        "/file",
+        any(move |method: Method| {
+            let counter = counter.clone();
+            let retry_after = retry_after.clone();
+            async move {
+                if method == Method::HEAD {
+                    return [(header::CONTENT_LENGTH, DATA.len().to_string())].into_response();
+                }
+                if counter.fetch_add(1, Ordering::SeqCst) < failures {
+                    let mut resp = status.into_response();
+                    if let Some(retry_after) = &retry_after {
+                        resp.headers_mut()
+                            .insert(header::RETRY_AFTER, retry_after.parse().unwrap());
+                    }
+                    return resp;
+                }
+                DATA.into_response()
+            }
+        }),
+    );
+    let addr = common::serve(app).await;
+    (format!("http://{}/file", addr), gets)
+}
+
+fn status_of(err: &std::io::Error) -> Option<StatusCode> {
+    err.get_ref()?.downcast_ref::<HttpFileError>()?.status()
+}
+
+#[tokio::test]
+async fn too_many_requests_waits_for_retry_after() {
+    let (url, gets) = serve_failing(StatusCode::TOO_MANY_REQUESTS, Some("1"), 1).await;
+    let mut file = HttpFile::builder()
+        .with_status_retry_policy(HashMap::from([(
+            StatusCode::TOO_MANY_REQUESTS,
+            RetryAction::RetryAfter,
+        )]))
+        .build(&url)
+        .await
+        .unwrap();
+
+    let start = Instant::now();
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, DATA);
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert_eq!(gets.load(Ordering::SeqCst), 2);
+    let stats = file.stats();
+    assert_eq!((stats.requests, stats.retries), (2, 1));
+    assert_eq!(stats.bytes_read, DATA.len() as u64);
+}
+
+#[tokio::test]
+async fn not_found_fails_fast() {
+    let (url, gets) = serve_failing(StatusCode::NOT_FOUND, None, 1).await;
+    let mut file = HttpFile::builder()
+        .with_status_retry_policy(HashMap::from([
+            (StatusCode::NOT_FOUND, RetryAction::Fail),
+            (StatusCode::TOO_MANY_REQUESTS, RetryAction::RetryAfter),
+        ]))
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![];
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    assert_eq!(status_of(&err), Some(StatusCode::NOT_FOUND));
+    assert_eq!(gets.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn server_errors_are_retried_by_default() {
+    let (url, gets) = serve_failing(StatusCode::SERVICE_UNAVAILABLE, None, 2).await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, DATA);
+    assert_eq!(gets.load(Ordering::SeqCst), 3);
+
+    // other statuses fail without a policy
+    let (url, gets) = serve_failing(StatusCode::FORBIDDEN, None, 1).await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    assert_eq!(status_of(&err), Some(StatusCode::FORBIDDEN));
+    assert_eq!(gets.load(Ordering::SeqCst), 1);
+}
+
+/// Read the whole file with no backoff of its own, returning how long it took.
+async fn timed_read(url: &str) -> Duration {
+    let mut file = HttpFile::builder()
+        .with_backoff(Duration::ZERO, 2.0, Duration::ZERO, false)
+        .build(url)
+        .await
+        .unwrap();
+    let start = Instant::now();
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, DATA);
+    start.elapsed()
+}
+
+#[tokio::test]
+async fn retry_after_honored_by_default() {
+    let (url, gets) = serve_failing(StatusCode::TOO_MANY_REQUESTS, Some("1"), 1).await;
+    assert!(timed_read(&url).await >= Duration::from_secs(1));
+    assert_eq!(gets.load(Ordering::SeqCst), 2);
+
+    let date = httpdate::fmt_http_date(std::time::SystemTime::now() + Duration::from_secs(2));
+    let (url, gets) = serve_failing(StatusCode::SERVICE_UNAVAILABLE, Some(&date), 1).await;
+    // the date has a resolution of a second
+    assert!(timed_read(&url).await >= Duration::from_millis(900));
+    assert_eq!(gets.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn invalid_retry_after_falls_back_to_backoff() {
+    for value in ["soon", "Mon, 32 Foo 2000 99:99:99 GMT"] {
+        let (url, gets) = serve_failing(StatusCode::SERVICE_UNAVAILABLE, Some(value), 2).await;
+        assert!(timed_read(&url).await < Duration::from_millis(500));
+        assert_eq!(gets.load(Ordering::SeqCst), 3);
+    }
+
+    // a date already past means no wait
+    let (url, _) = serve_failing(
+        StatusCode::TOO_MANY_REQUESTS,
+        Some("Sun, 06 Nov 1994 08:49:37 GMT"),
+        1,
+    )
+    .await;
+    assert!(timed_read(&url).await < Duration::from_millis(500));
+}
+
+#[tokio::test]
+async fn retries_are_bounded() {
+    let (url, gets) = serve_failing(StatusCode::SERVICE_UNAVAILABLE, None, usize::MAX).await;
+    let mut file = HttpFile::builder()
+        .with_status_retry_policy(HashMap::from([(
+            StatusCode::SERVICE_UNAVAILABLE,
+            RetryAction::Retry,
+        )]))
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![];
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    assert_eq!(status_of(&err), Some(StatusCode::SERVICE_UNAVAILABLE));
+    assert_eq!(gets.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn seek_retries_failed_requests() {
+    let (url, gets) = serve_failing(StatusCode::SERVICE_UNAVAILABLE, None, 2).await;
+    let mut file = HttpFile::builder()
+        .with_backoff(Duration::ZERO, 2.0, Duration::ZERO, false)
+        .build(&url)
+        .await
+        .unwrap();
+
+    assert_eq!(file.seek(std::io::SeekFrom::Start(7)).await.unwrap(), 7);
+    assert_eq!(gets.load(Ordering::SeqCst), 3);
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, b"world");
+}
+
+#[tokio::test]
+async fn seek_shares_the_retry_attempts() {
+    let (url, gets) = serve_failing(StatusCode::SERVICE_UNAVAILABLE, None, 10).await;
+    let mut file = HttpFile::builder()
+        .retries(1)
+        .with_backoff(Duration::ZERO, 2.0, Duration::ZERO, false)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let err = file.seek(std::io::SeekFrom::Start(7)).await.unwrap_err();
+    assert_eq!(status_of(&err), Some(StatusCode::SERVICE_UNAVAILABLE));
+    assert_eq!(gets.load(Ordering::SeqCst), 2);
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap_err();
+    assert_eq!(
+        gets.load(Ordering::SeqCst),
+        3,
+        "no attempts left for the read"
+    );
+}
+
+#[tokio::test]
+async fn failed_requests_are_typed_errors() {
+    let (url, _) = serve_failing(StatusCode::NOT_FOUND, None, usize::MAX).await;
+    let mut file = HttpFile::builder()
+        .skip_head(100)
+        .build(&url)
+        .await
+        .unwrap();
+    let mut buf = vec![];
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    let err = err.get_ref().unwrap().downcast_ref::<HttpFileError>();
+    assert!(matches!(
+        err,
+        Some(HttpFileError::Status {
+            code: StatusCode::NOT_FOUND,
+            ..
+        })
+    ));
+
+    let (url, _) = serve_failing(StatusCode::RANGE_NOT_SATISFIABLE, None, usize::MAX).await;
+    let mut file = HttpFile::builder()
+        .skip_head(100)
+        .build(&url)
+        .await
+        .unwrap();
+    let err = file.read_exact_at(10, &mut [0u8; 10]).await.unwrap_err();
+    let err = err.get_ref().unwrap().downcast_ref::<HttpFileError>();
+    assert!(matches!(
+        err,
+        Some(HttpFileError::RangeNotSatisfiable { .. })
+    ));
+}
+
+#[tokio::test]
+async fn forbidden_is_permission_denied() {
+    let (url, _) = serve_failing(StatusCode::FORBIDDEN, None, usize::MAX).await;
+    let mut file = HttpFile::builder()
+        .skip_head(100)
+        .build(&url)
+        .await
+        .unwrap();
+    let err = file.read_exact_at(0, &mut [0u8; 10]).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+    assert_eq!(status_of(&err), Some(StatusCode::FORBIDDEN));
+}
+
+/// Retries `400 Bad Request` only, recording the attempts it was asked about.
+#[derive(Default)]
+struct RetryBadRequest(std::sync::Mutex<Vec<u8>>);
+
+impl RetryPolicy for RetryBadRequest {
+    fn should_retry(&self, err: &reqwest::Error, attempt: u8) -> Option<Duration> {
+        self.0.lock().unwrap().push(attempt);
+        (err.status() == Some(StatusCode::BAD_REQUEST)).then_some(Duration::ZERO)
+    }
+}
+
+#[tokio::test]
+async fn retry_policy_decides_which_failures_retry() {
+    let (url, gets) = serve_failing(StatusCode::BAD_REQUEST, None, 2).await;
+    let policy = Arc::new(RetryBadRequest::default());
+    let mut file = HttpFile::builder()
+        .with_retry_policy(policy.clone())
+        .build(&url)
+        .await
+        .unwrap();
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, DATA);
+    assert_eq!(gets.load(Ordering::SeqCst), 3);
+    assert_eq!(*policy.0.lock().unwrap(), [0, 1]);
+
+    // a 503 is retried by default, but not by this policy
+    let (url, gets) = serve_failing(StatusCode::SERVICE_UNAVAILABLE, None, 1).await;
+    let mut file = HttpFile::builder()
+        .with_retry_policy(Arc::new(RetryBadRequest::default()))
+        .build(&url)
+        .await
+        .unwrap();
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    assert_eq!(status_of(&err), Some(StatusCode::SERVICE_UNAVAILABLE));
+    assert_eq!(gets.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn huge_retry_after_is_capped() {
+    for value in ["99999999", "Fri, 31 Dec 9999 23:59:59 GMT"] {
+        let (url, gets) = serve_failing(StatusCode::SERVICE_UNAVAILABLE, Some(value), 1).await;
+        let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+        let start = tokio::time::Instant::now();
+        let mut buf = vec![];
+        file.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, DATA);
+        // virtual time, which idle connection timers also move along
+        assert!(start.elapsed() >= Duration::from_secs(5 * 60));
+        assert!(start.elapsed() < Duration::from_secs(60 * 60));
+        assert_eq!(gets.load(Ordering::SeqCst), 2);
+    }
+}
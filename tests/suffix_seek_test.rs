@@ -0,0 +1,118 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    Router,
+    http::{HeaderMap, Method, StatusCode, header},
+    response::IntoResponse,
+    routing::any,
+};
+use common::{parse_range, random_bytes};
+use remote_file::HttpFile;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Serve `data` with range support but no length on `HEAD`. Returns the URL
+/// and the ranges requested.
+async fn serve_without_length(data: Vec<u8>) -> (String, Arc<Mutex<Vec<String>>>) {
+    let ranges = Arc::new(Mutex::new(Vec::<String>::new()));
+    let log = ranges.clone();
+    let app = Router::new().route(
+        "/file",
+        any(move |method: Method, headers: HeaderMap| {
+            let data = data.clone();
+            let log = log.clone();
+            async move {
+                if method == Method::HEAD {
+                    return common::unsized_empty().into_response();
+                }
+                let range = headers[header::RANGE].to_str().unwrap().to_string();
+                let (start, end) = parse_range(&range, data.len() as u64).unwrap().unwrap();
+                log.lock().unwrap().push(range);
+                let content_range = format!("bytes {}-{}/{}", start, end, data.len());
+                (
+                    StatusCode::PARTIAL_CONTENT,
+                    [(header::CONTENT_RANGE, content_range)],
+                    data[start as usize..=end as usize].to_vec(),
+                )
+                    .into_response()
+            }
+        }),
+    );
+    let addr = common::serve(app).await;
+    (format!("http://{}/file", addr), ranges)
+}
+
+#[tokio::test]
+async fn seek_from_end_with_unknown_length() {
+    let data = random_bytes(10_000);
+    let (url, ranges) = serve_without_length(data.clone()).await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert_eq!(file.content_length(), None);
+
+    assert_eq!(file.seek(std::io::SeekFrom::End(-22)).await.unwrap(), 9_978);
+    assert_eq!(file.content_length(), Some(10_000));
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data[9_978..]);
+    assert_eq!(
+        *ranges.lock().unwrap(),
+        ["bytes=-22"],
+        "suffix response is read"
+    );
+}
+
+#[tokio::test]
+async fn seek_to_end_with_unknown_length() {
+    let data = random_bytes(1_000);
+    let (url, _) = serve_without_length(data.clone()).await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    assert_eq!(file.seek(std::io::SeekFrom::End(0)).await.unwrap(), 1_000);
+    let mut buf = vec![];
+    assert_eq!(file.read_to_end(&mut buf).await.unwrap(), 0);
+
+    file.seek(std::io::SeekFrom::Start(0)).await.unwrap();
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+}
+
+#[tokio::test]
+async fn seek_before_start_with_unknown_length_fails() {
+    let (url, _) = serve_without_length(random_bytes(100)).await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let err = file.seek(std::io::SeekFrom::End(-101)).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let err = file.seek(std::io::SeekFrom::End(1)).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[tokio::test]
+async fn read_suffix_learns_length() {
+    let data = random_bytes(10_000);
+    let (url, ranges) = serve_without_length(data.clone()).await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    assert_eq!(file.read_suffix(22).await.unwrap(), data[9_978..]);
+    assert_eq!(file.content_length(), Some(10_000));
+    assert_eq!(file.position(), 0);
+    assert_eq!(file.read_suffix(100).await.unwrap(), data[9_900..]);
+    assert_eq!(file.read_suffix(20_000).await.unwrap(), data);
+    assert_eq!(
+        *ranges.lock().unwrap(),
+        ["bytes=-22", "bytes=9900-9999", "bytes=0-9999"],
+        "absolute ranges once the length is known"
+    );
+}
+
+#[tokio::test]
+async fn read_suffix_with_known_length() {
+    let data = random_bytes(1_000);
+    let mock = common::MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert_eq!(file.read_suffix(10).await.unwrap(), data[990..]);
+    assert_eq!(file.read_suffix(0).await.unwrap(), b""[..]);
+    assert_eq!(mock.gets(), 1);
+}
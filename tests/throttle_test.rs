@@ -0,0 +1,138 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use common::{MockFile, random_bytes};
+use futures_util::StreamExt;
+use remote_file::{FileMeta, HttpFile};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+#[tokio::test]
+async fn max_rate_caps_sustained_reads() {
+    let data = random_bytes(96 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    file.set_max_rate(64 * 1024);
+    file.set_rate_burst(16 * 1024);
+
+    let started = Instant::now();
+    let mut out = Vec::new();
+    file.read_to_end(&mut out).await.unwrap();
+    let elapsed = started.elapsed();
+
+    assert_eq!(out, data);
+    // the burst is free, the other 80 KiB take 1.25s at 64 KiB/s
+    assert!(elapsed >= Duration::from_millis(1100), "{elapsed:?}");
+    assert!(elapsed < Duration::from_secs(5), "{elapsed:?}");
+}
+
+#[tokio::test]
+async fn reads_within_the_burst_do_not_wait() {
+    let data = random_bytes(8 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    file.set_max_rate(16 * 1024);
+
+    let started = Instant::now();
+    let mut out = Vec::new();
+    file.read_to_end(&mut out).await.unwrap();
+
+    assert_eq!(out, data);
+    assert!(started.elapsed() < Duration::from_millis(400));
+}
+
+#[tokio::test]
+async fn zero_lifts_the_cap() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    file.set_max_rate(1024);
+    file.set_max_rate(0);
+
+    let started = Instant::now();
+    let mut out = Vec::new();
+    file.read_to_end(&mut out).await.unwrap();
+
+    assert_eq!(out, data);
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn max_rate_caps_download_to_writer() {
+    let data = Bytes::from(random_bytes(128 * 1024));
+    // a single chunk, most of which is written out through `chunk_as_buf`
+    let stream = futures_util::stream::iter([Ok(data.clone())]).boxed();
+    let meta = FileMeta {
+        content_length: Some(data.len() as u64),
+        ..Default::default()
+    };
+    // nothing listens there, so any request made would fail
+    let url = "http://127.0.0.1:9/file".parse().unwrap();
+    let mut file = HttpFile::from_parts(reqwest::Client::new(), url, meta, Some(stream));
+    file.set_max_rate(64 * 1024);
+    file.set_rate_burst(16 * 1024);
+
+    let started = Instant::now();
+    let mut out = Vec::new();
+    assert_eq!(
+        file.download_to_writer(&mut out).await.unwrap(),
+        data.len() as u64
+    );
+    let elapsed = started.elapsed();
+
+    assert_eq!(out, data);
+    // the burst is free, the other 112 KiB take 1.75s at 64 KiB/s
+    assert!(elapsed >= Duration::from_millis(1600), "{elapsed:?}");
+    assert!(elapsed < Duration::from_secs(5), "{elapsed:?}");
+}
+
+/// A file served without range support, so it is downloaded whole and
+/// read from memory, capped at 64 KiB/s with a 16 KiB burst.
+async fn open_downloaded(data: Bytes) -> HttpFile {
+    let app = axum::Router::new().route("/file", axum::routing::get(move || async move { data }));
+    let url = format!("http://{}/file", common::serve(app).await);
+    let mut file = HttpFile::builder()
+        .with_full_download_fallback(true)
+        .build(&url)
+        .await
+        .unwrap();
+    file.set_max_rate(64 * 1024);
+    file.set_rate_burst(16 * 1024);
+    file
+}
+
+#[tokio::test]
+async fn max_rate_caps_reads_of_a_downloaded_file() {
+    let data = Bytes::from(random_bytes(96 * 1024));
+
+    let mut file = open_downloaded(data.clone()).await;
+    let started = Instant::now();
+    let mut out = Vec::new();
+    file.read_to_end(&mut out).await.unwrap();
+    let elapsed = started.elapsed();
+    assert_eq!(out, data);
+    // the burst is free, the other 80 KiB take 1.25s at 64 KiB/s
+    assert!(elapsed >= Duration::from_millis(1100), "{elapsed:?}");
+
+    // through `fill_buf` and `consume` alike
+    let mut file = open_downloaded(data.clone()).await;
+    let started = Instant::now();
+    let mut out = Vec::new();
+    loop {
+        let chunk = file.fill_buf().await.unwrap();
+        if chunk.is_empty() {
+            break;
+        }
+        let len = chunk.len();
+        out.extend_from_slice(chunk);
+        file.consume(len);
+    }
+    let elapsed = started.elapsed();
+    assert_eq!(out, data);
+    assert!(elapsed >= Duration::from_millis(1100), "{elapsed:?}");
+    assert!(elapsed < Duration::from_secs(5), "{elapsed:?}");
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::MockFile;
+use remote_file::HttpFile;
+use tokio::io::AsyncSeekExt;
+
+fn tlv_fixture() -> Vec<u8> {
+    let mut data = vec![];
+    // big endian: 2-byte type, 4-byte length
+    for (t, value) in [(1u16, &b"hello"[..]), (0x0203, &[]), (7, &[0xab; 300])] {
+        data.extend_from_slice(&t.to_be_bytes());
+        data.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        data.extend_from_slice(value);
+    }
+    // little endian: 1-byte type, 2-byte length
+    data.push(9);
+    data.extend_from_slice(&3u16.to_le_bytes());
+    data.extend_from_slice(b"abc");
+    data
+}
+
+#[tokio::test]
+async fn read_tlv_records() {
+    let data = tlv_fixture();
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let (t, value) = file.read_tlv(2, 4, true).await.unwrap();
+    assert_eq!((t, &value[..]), (1, &b"hello"[..]));
+    let (t, value) = file.read_tlv(2, 4, true).await.unwrap();
+    assert_eq!((t, &value[..]), (0x0203, &[][..]));
+    let (t, value) = file.read_tlv(2, 4, true).await.unwrap();
+    assert_eq!((t, &value[..]), (7, &[0xab; 300][..]));
+    let (t, value) = file.read_tlv(1, 2, false).await.unwrap();
+    assert_eq!((t, &value[..]), (9, &b"abc"[..]));
+
+    assert_eq!(file.stream_position().await.unwrap(), data.len() as u64);
+    assert_eq!(mock.gets(), 1, "records are read from one response");
+}
+
+#[tokio::test]
+async fn read_tlv_truncated_value() {
+    let mut data = vec![];
+    data.extend_from_slice(&1u16.to_be_bytes());
+    data.extend_from_slice(&100u32.to_be_bytes());
+    data.extend_from_slice(b"short");
+    let mock = MockFile::new(data);
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let err = file.read_tlv(2, 4, true).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+
+    let err = file.read_tlv(0, 4, true).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
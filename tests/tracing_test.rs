@@ -0,0 +1,97 @@
+#![cfg(feature = "tracing")]
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::{MockFile, random_bytes};
+use remote_file::HttpFile;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+type Fields = Vec<(String, String)>;
+
+/// Fields recorded on each span, in the order the spans were opened.
+#[derive(Clone, Default)]
+struct Recorder {
+    spans: Arc<Mutex<Vec<Fields>>>,
+    events: Arc<Mutex<Vec<String>>>,
+}
+
+struct Visitor<'a>(&'a mut Fields);
+
+impl Visit for Visitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .push((field.name().to_string(), format!("{value:?}")));
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target().starts_with("remote_file")
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut spans = self.spans.lock().unwrap();
+        let mut fields = Vec::new();
+        span.record(&mut Visitor(&mut fields));
+        spans.push(fields);
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut spans = self.spans.lock().unwrap();
+        values.record(&mut Visitor(&mut spans[span.into_u64() as usize - 1]));
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Vec::new();
+        event.record(&mut Visitor(&mut fields));
+        let message = fields.into_iter().find(|(name, _)| name == "message");
+        self.events.lock().unwrap().push(message.unwrap().1);
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+fn field<'a>(fields: &'a Fields, name: &str) -> Option<&'a str> {
+    fields
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, value)| value.as_str())
+}
+
+#[tokio::test]
+async fn a_span_per_range_request() {
+    let data = random_bytes(64 * 1024);
+    let mock = MockFile::new(data.clone());
+    let url = mock.serve().await;
+    let recorder = Recorder::default();
+    let _guard = tracing::subscriber::set_default(recorder.clone());
+
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let mut buf = vec![0u8; 1000];
+    file.read_exact(&mut buf).await.unwrap();
+    file.seek(std::io::SeekFrom::Start(60_000)).await.unwrap();
+    let mut rest = Vec::new();
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[60_000..]);
+
+    let spans = recorder.spans.lock().unwrap();
+    assert_eq!(spans.len(), 2);
+    assert_eq!(field(&spans[0], "start"), Some("0"));
+    assert_eq!(field(&spans[0], "status"), Some("206"));
+    assert_eq!(field(&spans[0], "attempt"), Some("1"));
+    assert_eq!(field(&spans[0], "bytes"), Some("1000"));
+    assert_eq!(field(&spans[1], "start"), Some("60000"));
+    assert_eq!(field(&spans[1], "bytes"), Some("5536"));
+    let events = recorder.events.lock().unwrap();
+    assert_eq!(events.as_slice(), ["reconnecting", "end of response"]);
+}
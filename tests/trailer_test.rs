@@ -0,0 +1,113 @@
+mod common;
+
+use base64::Engine;
+use bytes::Bytes;
+use common::random_bytes;
+use md5::Digest;
+use remote_file::{HttpFile, HttpFileError};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// Serve `data` chunked over raw HTTP/1.1 with `trailer` sent after the body,
+/// without a length on `HEAD`.
+async fn serve_with_trailer(data: Bytes, trailer: String) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let data = data.clone();
+            let trailer = trailer.clone();
+            tokio::spawn(async move {
+                let mut req = vec![];
+                let mut buf = [0u8; 1024];
+                while !req.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        return;
+                    }
+                    req.extend_from_slice(&buf[..n]);
+                }
+                if req.starts_with(b"HEAD") {
+                    let head = "HTTP/1.1 200 OK\r\nconnection: close\r\n\r\n";
+                    socket.write_all(head.as_bytes()).await.unwrap();
+                    return;
+                }
+
+                let names: Vec<_> = trailer
+                    .lines()
+                    .filter_map(|l| l.split_once(':'))
+                    .map(|(name, _)| name)
+                    .collect();
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\ntrailer: {}\r\nconnection: close\r\n\r\n",
+                    names.join(", ")
+                );
+                socket.write_all(head.as_bytes()).await.unwrap();
+                for chunk in data.chunks(4096) {
+                    let size = format!("{:x}\r\n", chunk.len());
+                    socket.write_all(size.as_bytes()).await.unwrap();
+                    socket.write_all(chunk).await.unwrap();
+                    socket.write_all(b"\r\n").await.unwrap();
+                }
+                let end = format!("0\r\n{}\r\n", trailer);
+                socket.write_all(end.as_bytes()).await.unwrap();
+            });
+        }
+    });
+    format!("http://{}/file", addr)
+}
+
+fn md5_base64(data: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(md5::Md5::digest(data))
+}
+
+#[tokio::test]
+async fn length_and_etag_learned_from_trailer() {
+    let data = Bytes::from(random_bytes(64 * 1024));
+    let trailer = format!(
+        "content-length: {}\r\netag: \"v1\"\r\ncontent-md5: {}\r\n",
+        data.len(),
+        md5_base64(&data)
+    );
+    let url = serve_with_trailer(data.clone(), trailer).await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    assert_eq!(file.content_length(), None);
+    assert_eq!(file.etag(), None);
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+    assert_eq!(file.content_length(), Some(data.len() as u64));
+    assert_eq!(file.etag(), Some("\"v1\""));
+}
+
+#[tokio::test]
+async fn trailer_mismatch_fails_the_read() {
+    let data = Bytes::from(random_bytes(64 * 1024));
+    for (name, trailer) in [
+        (
+            "content-md5",
+            format!("content-md5: {}\r\n", md5_base64(b"x")),
+        ),
+        ("content-length", "content-length: 100\r\n".to_string()),
+    ] {
+        let url = serve_with_trailer(data.clone(), trailer).await;
+        let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+        let mut buf = vec![];
+        let err = file.read_to_end(&mut buf).await.unwrap_err();
+        assert_eq!(buf, data);
+        let err = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<HttpFileError>())
+            .expect("should be an HttpFileError");
+        assert!(
+            matches!(err, HttpFileError::TrailerMismatch { name: n, .. } if n == name),
+            "unexpected error: {err}"
+        );
+        assert_eq!(file.content_length(), None);
+    }
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use axum::{
+    Router,
+    http::{HeaderMap, Method, StatusCode, header},
+    response::IntoResponse,
+    routing::any,
+};
+use bytes::Bytes;
+use futures_util::{FutureExt, future::BoxFuture};
+use remote_file::{HttpFile, Transport};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Serve `data` to requests bearing the token currently in `valid`, and
+/// answer `401` to the others.
+async fn serve_authenticated(data: Bytes, valid: Arc<Mutex<String>>) -> String {
+    let app = Router::new().route(
+        "/file",
+        any(move |method: Method, headers: HeaderMap| {
+            let data = data.clone();
+            let valid = valid.clone();
+            async move {
+                let expected = format!("Bearer {}", valid.lock().unwrap());
+                if headers
+                    .get(header::AUTHORIZATION)
+                    .is_none_or(|v| *v != *expected)
+                {
+                    return StatusCode::UNAUTHORIZED.into_response();
+                }
+                common::range_response(&method, &headers, data, None)
+            }
+        }),
+    );
+    let addr = common::serve(app).await;
+    format!("http://{}/file", addr)
+}
+
+/// Adds a bearer token, and fetches a new one and sends the request again
+/// when it is rejected.
+#[derive(Clone)]
+struct TokenRefresh {
+    token: Arc<Mutex<String>>,
+    issuer: Arc<Mutex<String>>,
+    refreshes: Arc<AtomicUsize>,
+}
+
+impl TokenRefresh {
+    fn authorize(&self, mut request: reqwest::Request) -> reqwest::Request {
+        let value = format!("Bearer {}", self.token.lock().unwrap());
+        request
+            .headers_mut()
+            .insert(header::AUTHORIZATION, value.parse().unwrap());
+        request
+    }
+}
+
+impl Transport for TokenRefresh {
+    fn execute(
+        &self,
+        client: &reqwest::Client,
+        request: reqwest::Request,
+    ) -> BoxFuture<'static, reqwest::Result<reqwest::Response>> {
+        let client = client.clone();
+        let this = self.clone();
+        async move {
+            let retry = request.try_clone().unwrap();
+            let resp = client.execute(this.authorize(request)).await?;
+            if resp.status() != StatusCode::UNAUTHORIZED {
+                return Ok(resp);
+            }
+            this.refreshes.fetch_add(1, Ordering::SeqCst);
+            *this.token.lock().unwrap() = this.issuer.lock().unwrap().clone();
+            client.execute(this.authorize(retry)).await
+        }
+        .boxed()
+    }
+}
+
+#[tokio::test]
+async fn transport_refreshes_an_expired_token_during_a_seek() {
+    let data = Bytes::from(common::random_bytes(256 * 1024));
+    let valid = Arc::new(Mutex::new("first".to_string()));
+    let url = serve_authenticated(data.clone(), valid.clone()).await;
+    let refresh = TokenRefresh {
+        token: Arc::new(Mutex::new("first".to_string())),
+        issuer: valid.clone(),
+        refreshes: Arc::new(AtomicUsize::new(0)),
+    };
+    let mut file = HttpFile::builder()
+        .with_transport(Arc::new(refresh.clone()))
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![0u8; 1024];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[..1024]);
+    assert_eq!(refresh.refreshes.load(Ordering::SeqCst), 0);
+
+    // the token expires, the next request made for a deep seek is refused
+    *valid.lock().unwrap() = "second".to_string();
+    file.seek(std::io::SeekFrom::Start(200_000)).await.unwrap();
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[200_000..201_024]);
+    assert_eq!(refresh.refreshes.load(Ordering::SeqCst), 1);
+    assert_eq!(*refresh.token.lock().unwrap(), "second");
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use axum::{
+    Router,
+    body::Body,
+    http::{HeaderMap, Method, StatusCode, header},
+    response::IntoResponse,
+    routing::any,
+};
+use common::{parse_range, random_bytes};
+use remote_file::HttpFile;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// `HEAD` reports `reported` bytes, `GET` serves all of `data`, streamed
+/// without a length, and with a correct `Content-Range` if `with_range`.
+async fn serve_under_reported(data: Vec<u8>, reported: usize, with_range: bool) -> String {
+    let app = Router::new().route(
+        "/file",
+        any(move |method: Method, headers: HeaderMap| {
+            let data = data.clone();
+            async move {
+                if method == Method::HEAD {
+                    return [(header::CONTENT_LENGTH, reported.to_string())].into_response();
+                }
+                let range = headers[header::RANGE].to_str().unwrap();
+                let (start, end) = parse_range(range, data.len() as u64).unwrap().unwrap();
+                let body = data[start as usize..=end as usize].to_vec();
+                let chunks = body
+                    .chunks(1000)
+                    .map(|c| Ok::<_, std::io::Error>(c.to_vec()))
+                    .collect::<Vec<_>>();
+                let body = Body::from_stream(futures_util::stream::iter(chunks));
+                if with_range {
+                    let content_range = format!("bytes {}-{}/{}", start, end, data.len());
+                    (
+                        StatusCode::PARTIAL_CONTENT,
+                        [(header::CONTENT_RANGE, content_range)],
+                        body,
+                    )
+                        .into_response()
+                } else {
+                    (StatusCode::PARTIAL_CONTENT, body).into_response()
+                }
+            }
+        }),
+    );
+    let addr = common::serve(app).await;
+    format!("http://{}/file", addr)
+}
+
+#[tokio::test]
+async fn full_body_read_past_reported_length() {
+    let data = random_bytes(10_000);
+    let url = serve_under_reported(data.clone(), 6_000, false).await;
+    let mut file = HttpFile::builder()
+        .with_trust_content_length(false)
+        .build(&url)
+        .await
+        .unwrap();
+    assert_eq!(file.content_length(), Some(6_000));
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+    assert_eq!(file.content_length(), Some(10_000));
+    assert_eq!(file.seek(std::io::SeekFrom::End(0)).await.unwrap(), 10_000);
+}
+
+#[tokio::test]
+async fn length_corrected_from_content_range() {
+    let data = random_bytes(10_000);
+    let url = serve_under_reported(data.clone(), 6_000, true).await;
+    let mut file = HttpFile::builder()
+        .with_trust_content_length(false)
+        .build(&url)
+        .await
+        .unwrap();
+
+    let mut buf = vec![0u8; 10];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(file.content_length(), Some(10_000));
+
+    let mut buf = vec![0u8; 1000];
+    file.read_exact_at(8_000, &mut buf).await.unwrap();
+    assert_eq!(buf, data[8_000..9_000]);
+}
+
+#[tokio::test]
+async fn reported_length_trusted_by_default() {
+    let data = random_bytes(10_000);
+    let url = serve_under_reported(data.clone(), 6_000, false).await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+
+    let mut buf = vec![];
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data[..6_000]);
+    assert_eq!(file.content_length(), Some(6_000));
+}
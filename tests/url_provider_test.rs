@@ -0,0 +1,129 @@
+mod common;
+
+use std::{
+    io::SeekFrom,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use axum::{
+    Router,
+    http::{HeaderMap, Method, StatusCode, Uri},
+    response::IntoResponse,
+    routing::any,
+};
+use bytes::Bytes;
+use futures_util::FutureExt;
+use remote_file::{HttpFile, UrlProvider};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Serve `data` to requests signed with the signature currently in `valid`,
+/// and answer `refused` to the others, as expired presigned urls are.
+async fn serve_signed(data: Bytes, valid: Arc<Mutex<String>>, refused: StatusCode) -> String {
+    let app = Router::new().route(
+        "/file",
+        any(move |method: Method, uri: Uri, headers: HeaderMap| {
+            let data = data.clone();
+            let valid = valid.clone();
+            async move {
+                let expected = format!("sig={}", valid.lock().unwrap());
+                if uri.query() != Some(&*expected) {
+                    return refused.into_response();
+                }
+                common::range_response(&method, &headers, data, None)
+            }
+        }),
+    );
+    let addr = common::serve(app).await;
+    format!("http://{}/file", addr)
+}
+
+/// Sign `url` with the signature in `issuer` at the time of the call,
+/// counting the calls.
+fn signer(url: String, issuer: Arc<Mutex<String>>, calls: Arc<AtomicUsize>) -> UrlProvider {
+    Arc::new(move || {
+        calls.fetch_add(1, Ordering::SeqCst);
+        let signed = format!("{}?sig={}", url, issuer.lock().unwrap());
+        async move { reqwest::Url::parse(&signed).unwrap() }.boxed()
+    })
+}
+
+#[tokio::test]
+async fn expired_url_is_refreshed() {
+    let data = Bytes::from(common::random_bytes(1 << 20));
+    let valid = Arc::new(Mutex::new("a".to_string()));
+    let url = serve_signed(data.clone(), valid.clone(), StatusCode::FORBIDDEN).await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut file = HttpFile::builder()
+        .build_with_url_provider(signer(url, valid.clone(), calls.clone()))
+        .await
+        .unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    let mut buf = [0u8; 16];
+    file.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, data[..16]);
+
+    // the signature expires, the next request is refused
+    *valid.lock().unwrap() = "b".to_string();
+    file.seek(SeekFrom::Start(800_000)).await.unwrap();
+    let mut rest = vec![];
+    file.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, data[800_000..]);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn refresh_statuses_are_configurable() {
+    let data = Bytes::from_static(b"hello, world");
+    let valid = Arc::new(Mutex::new("a".to_string()));
+    let url = serve_signed(data.clone(), valid.clone(), StatusCode::UNAUTHORIZED).await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let provider = signer(url.clone(), valid.clone(), calls.clone());
+
+    // 401 isn't a refresh status by default
+    *valid.lock().unwrap() = "b".to_string();
+    let mut file = HttpFile::builder()
+        .skip_head(data.len() as u64)
+        .with_url_provider(provider.clone())
+        .build(&format!("{}?sig=a", url))
+        .await
+        .unwrap();
+    let mut buf = vec![];
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Other);
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    let mut file = HttpFile::builder()
+        .skip_head(data.len() as u64)
+        .with_url_refresh_statuses(&[StatusCode::UNAUTHORIZED])
+        .build(&format!("{}?sig=a", url))
+        .await
+        .unwrap();
+    file.set_url_provider(provider);
+    file.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, data);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn refreshes_spend_retry_attempts() {
+    let data = Bytes::from_static(b"hello, world");
+    let valid = Arc::new(Mutex::new("a".to_string()));
+    let url = serve_signed(data.clone(), valid.clone(), StatusCode::FORBIDDEN).await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    // the provider keeps handing out an expired signature
+    let stale = Arc::new(Mutex::new("stale".to_string()));
+    let mut file = HttpFile::builder()
+        .skip_head(data.len() as u64)
+        .retries(2)
+        .with_url_provider(signer(url.clone(), stale, calls.clone()))
+        .build(&format!("{}?sig=stale", url))
+        .await
+        .unwrap();
+    let mut buf = vec![];
+    let err = file.read_to_end(&mut buf).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::MockFile;
+use remote_file::HttpFile;
+use tokio::io::AsyncSeekExt;
+
+/// Entries of the fixtures, as listed by Python's `zipfile`:
+/// `(name, method, compressed, uncompressed, crc32)`.
+const ENTRIES: [(&str, u16, u64, u64, u32); 3] = [
+    ("hello.txt", 0, 39, 39, 0xe6f94cf0),
+    ("dir/", 0, 0, 0, 0),
+    ("dir/données.bin", 8, 366, 10240, 0xbbce3b9d),
+];
+
+async fn list(fixture: &'static [u8], offsets: [u64; 3]) {
+    let mock = MockFile::new(fixture);
+    let url = mock.serve().await;
+    let mut file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    file.seek(std::io::SeekFrom::Start(10)).await.unwrap();
+
+    let entries = file.zip_central_directory().await.unwrap();
+    assert_eq!(entries.len(), ENTRIES.len());
+    for ((entry, expected), offset) in entries.iter().zip(ENTRIES).zip(offsets) {
+        let (name, method, compressed, uncompressed, crc32) = expected;
+        assert_eq!(entry.name, name);
+        assert_eq!(entry.compression_method, method);
+        assert_eq!(entry.compressed_size, compressed);
+        assert_eq!(entry.uncompressed_size, uncompressed);
+        assert_eq!(entry.crc32, crc32);
+        assert_eq!(entry.local_header_offset, offset);
+    }
+    assert_eq!(file.stream_position().await.unwrap(), 10);
+}
+
+#[tokio::test]
+async fn list_zip_with_comment() {
+    list(include_bytes!("fixtures/sample.zip"), [0, 78, 112]).await;
+}
+
+#[tokio::test]
+async fn list_zip64() {
+    list(include_bytes!("fixtures/sample64.zip"), [0, 98, 132]).await;
+}
+
+#[tokio::test]
+async fn not_a_zip() {
+    let mock = MockFile::new(common::random_bytes(100 * 1024));
+    let url = mock.serve().await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    let err = file.zip_central_directory().await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+/// A ZIP64 end of central directory record listing a directory of `size`
+/// bytes at `offset`, and the locator pointing to it at `record_offset`.
+fn zip64_tail(record_offset: u64, offset: u64, size: u64) -> Vec<u8> {
+    let mut archive = vec![];
+    archive.extend_from_slice(&0x0606_4b50u32.to_le_bytes());
+    archive.resize(40, 0);
+    archive.extend_from_slice(&size.to_le_bytes());
+    archive.extend_from_slice(&offset.to_le_bytes());
+    // locator
+    archive.extend_from_slice(&0x0706_4b50u32.to_le_bytes());
+    archive.extend_from_slice(&0u32.to_le_bytes());
+    archive.extend_from_slice(&record_offset.to_le_bytes());
+    archive.extend_from_slice(&1u32.to_le_bytes());
+    // end of central directory
+    archive.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    archive.resize(archive.len() + 18, 0);
+    archive
+}
+
+async fn list_crafted(archive: Vec<u8>) -> std::io::Error {
+    let url = MockFile::new(archive).serve().await;
+    let file = HttpFile::new(reqwest::Client::new(), &url).await.unwrap();
+    file.zip_central_directory().await.unwrap_err()
+}
+
+#[tokio::test]
+async fn crafted_zip64_locator_out_of_bounds() {
+    let err = list_crafted(zip64_tail(u64::MAX - 10, 0, 0)).await;
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[tokio::test]
+async fn crafted_central_directory_size_out_of_bounds() {
+    for (offset, size) in [(10, u64::MAX), (0, 1 << 40), (1 << 40, 0)] {
+        let err = list_crafted(zip64_tail(0, offset, size)).await;
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}